@@ -0,0 +1,6 @@
+#import bevy_pbr::mesh_vertex_output MeshVertexOutput
+
+@fragment
+fn fragment(mesh: MeshVertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(normalize(mesh.world_normal) * 0.5 + 0.5, 1.0);
+}
@@ -0,0 +1,280 @@
+//! Warnings about dangerous situations, each with its own beep and a colored message on the HUD.
+//!
+//! Systems that notice something send a [`RaiseAlert`] every frame the condition holds,
+//! [`process_alerts`] takes care of only announcing it once, when it started to hold.
+
+use bevy::{audio::VolumeLevel, prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    audio::{self, AudioEnabled},
+    dominant_attractor,
+    forces::ExternalForceSet,
+    hud::WarningText,
+    landing::Landed,
+    orbit::Orbit,
+    reference::BodyVelocities,
+    GravityAttractor, Planet, Spaceship,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Alert {
+    /// Faster than the escape velocity of the dominant body.
+    EscapeVelocity,
+    /// The orbit intersects the surface.
+    PeriapsisBelowSurface,
+    /// Something else is about to hit the ship.
+    Proximity,
+}
+
+impl Alert {
+    const ALL: [Alert; 3] = [
+        Alert::EscapeVelocity,
+        Alert::PeriapsisBelowSurface,
+        Alert::Proximity,
+    ];
+
+    fn message(self) -> &'static str {
+        match self {
+            Alert::EscapeVelocity => "Escape velocity",
+            Alert::PeriapsisBelowSurface => "Periapsis below surface",
+            Alert::Proximity => "Proximity",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Alert::EscapeVelocity => Color::YELLOW,
+            Alert::PeriapsisBelowSurface => Color::ORANGE,
+            Alert::Proximity => Color::RED,
+        }
+    }
+
+    /// The playback speed of the beep, so that each alert sounds different.
+    fn pitch(self) -> f32 {
+        match self {
+            Alert::EscapeVelocity => 2.0,
+            Alert::PeriapsisBelowSurface => 2.5,
+            Alert::Proximity => 3.5,
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaiseAlert(pub Alert);
+
+/// How long an alert has been going on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AlertState {
+    since: f64,
+    /// When it stopped being raised, if it did.
+    cleared_at: Option<f64>,
+    announced: bool,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct Alerts {
+    pub enabled: bool,
+    /// An alert is only announced once it was raised for this many seconds, so that a condition
+    /// that holds for a frame or two doesn't beep.
+    pub hold: f64,
+    /// An alert is only announced again after it wasn't raised for this many seconds.
+    pub debounce: f64,
+    /// How long the message stays on the HUD, in seconds.
+    pub message_duration: f64,
+    /// Closer than this, other bodies raise [`Alert::Proximity`].
+    pub proximity_distance: f32,
+    states: HashMap<Alert, AlertState>,
+    message: Option<(Alert, f64)>,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hold: 0.5,
+            debounce: 5.0,
+            message_duration: 4.0,
+            proximity_distance: 20.0,
+            states: HashMap::default(),
+            message: None,
+        }
+    }
+}
+
+impl Alerts {
+    /// Records whether `alert` is `raised` at `now`, returning whether it should be announced.
+    /// That's only once per time it starts, after it held for [`Alerts::hold`].
+    pub fn update(&mut self, alert: Alert, raised: bool, now: f64) -> bool {
+        let state = self.states.get_mut(&alert);
+        if !raised {
+            if let Some(state) = state {
+                let cleared_at = *state.cleared_at.get_or_insert(now);
+                if now - cleared_at >= self.debounce {
+                    self.states.remove(&alert);
+                }
+            }
+            return false;
+        }
+
+        let state = match state {
+            Some(state) => {
+                // it has to hold without a break to be announced at all
+                if state.cleared_at.take().is_some() && !state.announced {
+                    state.since = now;
+                }
+                state
+            }
+            None => self.states.entry(alert).or_insert(AlertState {
+                since: now,
+                cleared_at: None,
+                announced: false,
+            }),
+        };
+        let announce = !state.announced && now - state.since >= self.hold;
+        state.announced |= announce;
+        announce
+    }
+}
+
+pub fn check_orbit_alerts(
+    query: Query<(&Transform, &Velocity), (With<Spaceship>, Without<Landed>)>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor, &Planet), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    mut alerts: EventWriter<RaiseAlert>,
+) {
+    let Ok((transform, v)) = query.get_single() else {
+        return;
+    };
+    let Some((body, body_transform, gravity, planet)) =
+        dominant_attractor(&body_query, transform.translation, |(_, t, g, _)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let orbit = Orbit::from_pos_dir_3d(
+        gravity.mass,
+        (transform.translation - body_transform.translation).as_dvec3(),
+        (v.linvel - velocities.of(body)).as_dvec3(),
+    );
+    if orbit.semi_major_axis < 0.0 || orbit.eccentricity >= 1.0 {
+        alerts.send(RaiseAlert(Alert::EscapeVelocity));
+    } else if orbit.periapsis() < planet.radius {
+        alerts.send(RaiseAlert(Alert::PeriapsisBelowSurface));
+    }
+}
+
+pub fn check_proximity(
+    config: Res<Alerts>,
+    query: Query<(Entity, &Transform), With<Spaceship>>,
+    others: Query<(Entity, &Transform), With<ExternalForceSet>>,
+    mut alerts: EventWriter<RaiseAlert>,
+) {
+    let Ok((ship, transform)) = query.get_single() else {
+        return;
+    };
+
+    let close = others.iter().any(|(other, other_transform)| {
+        other != ship
+            && other_transform.translation.distance(transform.translation)
+                < config.proximity_distance
+    });
+    if close {
+        alerts.send(RaiseAlert(Alert::Proximity));
+    }
+}
+
+pub fn process_alerts(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    audio: Res<AudioEnabled>,
+    mut config: ResMut<Alerts>,
+    mut raised: EventReader<RaiseAlert>,
+    mut text_query: Query<&mut Text, With<WarningText>>,
+) {
+    let now = time.elapsed_seconds_f64();
+    if !config.enabled {
+        raised.clear();
+        return;
+    }
+
+    let raised = raised
+        .iter()
+        .map(|&RaiseAlert(alert)| alert)
+        .collect::<Vec<_>>();
+    for alert in Alert::ALL {
+        if !config.update(alert, raised.contains(&alert), now) {
+            continue;
+        }
+
+        warn!("Alert: {}", alert.message());
+        config.message = Some((alert, now));
+        // there's no beep sample, a sped up thruster sound is short enough
+        audio::play_one_shot(
+            &mut commands,
+            &asset_server,
+            *audio,
+            "thrusters_loop.ogg",
+            PlaybackSettings {
+                volume: bevy::audio::Volume::Relative(VolumeLevel::new(0.5)),
+                speed: alert.pitch(),
+                ..PlaybackSettings::ONCE
+            },
+        );
+    }
+
+    let message_duration = config.message_duration;
+    let mut text = text_query.single_mut();
+    let section = &mut text.sections[1];
+    match config.message {
+        Some((alert, at)) if now - at < message_duration => {
+            section.value = alert.message().to_owned();
+            section.style.color = alert.color();
+        }
+        _ => {
+            section.value = "-".to_owned();
+            section.style.color = Color::GRAY;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alert, Alerts};
+
+    #[test]
+    fn announced_once_when_it_starts() {
+        let mut alerts = Alerts::default();
+        let announced = |alerts: &mut Alerts, raised, from: f64, to: f64| {
+            let mut count = 0;
+            for frame in (from * 60.0) as u32..(to * 60.0) as u32 {
+                count += u32::from(alerts.update(Alert::Proximity, raised, frame as f64 / 60.0));
+            }
+            count
+        };
+
+        assert_eq!(announced(&mut alerts, true, 0.0, 3.0), 1);
+        // gone for a moment, it's still the same one
+        assert_eq!(announced(&mut alerts, false, 3.0, 4.0), 0);
+        assert_eq!(announced(&mut alerts, true, 4.0, 6.0), 0);
+        // came back after a while
+        assert_eq!(announced(&mut alerts, false, 6.0, 12.0), 0);
+        assert_eq!(announced(&mut alerts, true, 12.0, 13.0), 1);
+    }
+
+    #[test]
+    fn spurious_alerts_are_ignored() {
+        let mut alerts = Alerts::default();
+        // a frame here and there, never long enough
+        for frame in 0..600 {
+            let raised = frame % 20 == 0;
+            assert!(!alerts.update(Alert::EscapeVelocity, raised, frame as f64 / 60.0));
+        }
+        // other alerts are separate
+        assert!(!alerts.update(Alert::Proximity, true, 10.0));
+        assert!(alerts.update(Alert::Proximity, true, 10.5));
+    }
+}
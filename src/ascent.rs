@@ -0,0 +1,225 @@
+//! Flying the ship from the surface up to a target apoapsis with a gravity turn.
+//!
+//! The ship rises vertically at first, then is kicked over towards the horizon and from
+//! there on thrusts along its velocity, so that gravity bends the trajectory over instead of
+//! the engine having to. The throttle is eased off as the apoapsis gets close to the target,
+//! and the engine cut once it's there.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use glam::DVec3;
+
+use crate::{
+    autopilot::{AutopilotForce, LandAt, ATTITUDE_DAMPING, ATTITUDE_STIFFNESS},
+    dominant_attractor,
+    forces::ExternalForceSet,
+    input::InputBindings,
+    mass_of, orbit, GravityAttractor, Planet, Spaceship, Thrusters,
+};
+
+/// Flies the ship up until its apoapsis is `target_apoapsis` above the surface.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct GravityTurnAscent {
+    pub target_apoapsis: f64,
+}
+
+/// The apoapsis altitude the ascent key aims for.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct AscentSettings {
+    pub target_apoapsis: f64,
+}
+
+impl Default for AscentSettings {
+    fn default() -> Self {
+        Self {
+            target_apoapsis: 1000.0,
+        }
+    }
+}
+
+/// The fraction of the target altitude climbed vertically before pitching over.
+pub const TURN_START: f32 = 0.02;
+/// How far the ship pitches over from the vertical to start the turn, in radians. It's held
+/// until the velocity has turned as far, then the ship follows the velocity.
+pub const KICK_ANGLE: f32 = 0.8;
+/// How much of the target apoapsis altitude is left when the throttle starts to ease off.
+pub const THROTTLE_DOWN: f64 = 0.1;
+/// The lowest throttle while easing off, so the last bit doesn't take forever.
+pub const MIN_THROTTLE: f32 = 0.05;
+
+/// The furthest distance from the attractor, from the energy and angular momentum.
+/// Unlike [`Orbit::apoapsis`], this also works going straight up, where the orbit is a
+/// degenerate ellipse with an eccentricity of 1.
+fn apoapsis_radius(m: f64, pos: DVec3, vel: DVec3) -> f64 {
+    let mu = orbit::G * m;
+    let a = 1.0 / (2.0 / pos.length() - vel.length_squared() / mu);
+    if a <= 0.0 {
+        return f64::INFINITY;
+    }
+    let h = pos.cross(vel).length_squared();
+    let e = (1.0 - h / (mu * a)).max(0.0).sqrt();
+    a * (1.0 + e)
+}
+
+/// The direction to thrust in and the throttle for a ship at `pos` moving with `vel`, both
+/// relative to a planet of `radius` and mass `m`. `None` once the apoapsis is at
+/// `target_apoapsis` above the surface.
+pub fn ascent_guidance(
+    m: f64,
+    radius: f64,
+    target_apoapsis: f64,
+    (pos, vel): (Vec3, Vec3),
+) -> Option<(Vec3, f32)> {
+    let apoapsis = apoapsis_radius(m, pos.as_dvec3(), vel.as_dvec3());
+    let remaining = radius + target_apoapsis - apoapsis;
+    if remaining <= 0.0 {
+        return None;
+    }
+
+    let up = pos.normalize();
+    let altitude = (pos.length() as f64 - radius) as f32;
+    // eastwards, towards a prograde equatorial orbit, tilted towards the horizontal velocity
+    let east = Vec3::Y.cross(up).try_normalize().unwrap_or(Vec3::NEG_Z);
+    let horizontal = vel - up * vel.dot(up);
+    let downrange = (horizontal + east * 0.1).try_normalize().unwrap_or(east);
+    let kick = up * KICK_ANGLE.cos() + downrange * KICK_ANGLE.sin();
+
+    let direction = match vel.try_normalize() {
+        _ if altitude < TURN_START * target_apoapsis as f32 => up,
+        Some(prograde) if prograde.dot(up) < KICK_ANGLE.cos() => prograde,
+        // the velocity hasn't turned as far as the kick yet, prograde would just go up again
+        _ => kick,
+    };
+
+    let throttle = (remaining / (THROTTLE_DOWN * target_apoapsis)) as f32;
+    Some((direction, throttle.clamp(MIN_THROTTLE, 1.0)))
+}
+
+pub fn start_gravity_turn(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    settings: Res<AscentSettings>,
+    query: Query<Entity, (With<Spaceship>, Without<GravityTurnAscent>)>,
+) {
+    if !keyboard_input.just_pressed(bindings.ascent) {
+        return;
+    }
+    for ship in &query {
+        // both autopilots fly with the same force, only one of them can be on
+        commands
+            .entity(ship)
+            .remove::<LandAt>()
+            .insert(GravityTurnAscent {
+                target_apoapsis: settings.target_apoapsis,
+            });
+        info!("Ascending to an apoapsis of {}", settings.target_apoapsis);
+    }
+}
+
+pub fn gravity_turn(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut query: Query<
+        (
+            Entity,
+            &GravityTurnAscent,
+            &mut ExternalForceSet,
+            &Transform,
+            &Velocity,
+            Option<&ReadMassProperties>,
+            &Thrusters,
+        ),
+        With<Spaceship>,
+    >,
+    body_query: Query<(&Transform, &GravityAttractor, &Planet), Without<Spaceship>>,
+) {
+    for (entity, ascent, mut forces, transform, velocity, mass, thrusters) in &mut query {
+        let guidance = dominant_attractor(&body_query, transform.translation, |(t, g, _)| {
+            (t.translation, g.mass)
+        })
+        .and_then(|(body, gravity, planet)| {
+            ascent_guidance(
+                gravity.mass,
+                planet.radius,
+                ascent.target_apoapsis,
+                (transform.translation - body.translation, velocity.linvel),
+            )
+        });
+
+        let Some((direction, throttle)) =
+            guidance.filter(|_| !keyboard_input.pressed(bindings.thrust))
+        else {
+            commands.entity(entity).remove::<GravityTurnAscent>();
+            forces.set::<AutopilotForce>(ExternalForce::default());
+            info!("Ascent autopilot off");
+            continue;
+        };
+
+        // the engine only pushes along the ship, it has to be turned to where it should go
+        let nose = transform.rotation * Vec3::Y;
+        let error = nose.cross(direction);
+        let torque = ATTITUDE_STIFFNESS * error - ATTITUDE_DAMPING * velocity.angvel;
+        forces.set::<AutopilotForce>(ExternalForce {
+            force: nose * thrusters.max_force() * throttle,
+            torque: torque * mass_of(mass),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{ascent_guidance, KICK_ANGLE, TURN_START};
+    use crate::orbit::{Orbit, G};
+
+    #[test]
+    fn reaches_target_apoapsis() {
+        let radius = 1000.0;
+        // 0.5 surface gravity, with twice that in thrust
+        let m = 0.5 * radius * radius / G;
+        let max_acceleration = 1.0;
+        let target = 200.0;
+
+        let mut pos = Vec3::new(radius as f32, 0.0, 0.0);
+        let mut vel = Vec3::ZERO;
+        let dt = 1.0 / 60.0;
+        let mut ticks = 0;
+        let mut turned = false;
+        // rotated instantly to the commanded direction
+        while let Some((direction, throttle)) = ascent_guidance(m, radius, target, (pos, vel)) {
+            let up = pos.normalize();
+            let altitude = pos.length() - radius as f32;
+            if altitude < TURN_START * target as f32 {
+                assert_eq!(direction, up, "not vertical at {altitude}");
+            } else if vel.normalize().dot(up) < KICK_ANGLE.cos() {
+                // a gravity turn from here on, with the engine along the velocity
+                turned = true;
+                assert!(
+                    direction.dot(vel.normalize()) > 1.0 - 1e-6,
+                    "{direction} {vel}"
+                );
+            }
+
+            let gravity = -up * (G * m / pos.length_squared() as f64) as f32;
+            vel += (direction * throttle * max_acceleration + gravity) * dt;
+            pos += vel * dt;
+
+            ticks += 1;
+            assert!(pos.length() as f64 >= radius - 1.0, "crashed at {pos}");
+            assert!(ticks < 60 * 600, "still ascending at {pos} with {vel}");
+        }
+        assert!(turned, "never pitched over");
+
+        let orbit = Orbit::from_pos_dir_3d(m, pos.as_dvec3(), vel.as_dvec3());
+        let apoapsis = orbit.apoapsis() - radius;
+        assert!(
+            (apoapsis - target).abs() < target * 0.05,
+            "{apoapsis} == {target}"
+        );
+        // pitched over, not just thrown straight up
+        assert!(orbit.periapsis() > radius * 0.3, "{orbit:?}");
+    }
+}
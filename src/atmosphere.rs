@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{forces::ExternalForceSet, Planet};
+
+/// An exponential atmosphere around a [`Planet`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Atmosphere {
+    /// Density at the surface.
+    pub surface_density: f64,
+    /// Altitude over which the density drops by a factor of e.
+    pub scale_height: f64,
+}
+
+impl Atmosphere {
+    pub fn density(&self, altitude: f64) -> f64 {
+        self.surface_density * f64::exp(-altitude.max(0.0) / self.scale_height)
+    }
+}
+
+/// Drag coefficient times the reference area of the ship.
+/// The ship isn't exactly aerodynamic, so this is a rough guess.
+pub const SHIP_DRAG_AREA: f64 = 1.0;
+
+/// The drag force `1/2 * rho * v^2 * Cd * A`, opposing the velocity relative to the air.
+pub fn drag_force(density: f64, drag_area: f64, velocity: Vec3) -> Vec3 {
+    let speed = velocity.length() as f64;
+    let magnitude = 0.5 * density * speed * speed * drag_area;
+    -velocity.normalize_or_zero() * (magnitude as f32)
+}
+
+pub fn apply_drag(
+    mut query: Query<(&mut ExternalForceSet, &Transform, &Velocity)>,
+    body_query: Query<(&Atmosphere, &Planet, &Transform)>,
+) {
+    struct DragForce;
+
+    for (mut ship_forces, ship_transform, v) in &mut query {
+        let force = body_query
+            .iter()
+            .map(|(atmosphere, planet, body_transform)| {
+                let altitude = ship_transform
+                    .translation
+                    .distance(body_transform.translation) as f64
+                    - planet.radius;
+                drag_force(atmosphere.density(altitude), SHIP_DRAG_AREA, v.linvel)
+            })
+            .sum();
+
+        ship_forces.set::<DragForce>(ExternalForce {
+            force,
+            torque: Vec3::ZERO,
+        });
+    }
+}
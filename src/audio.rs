@@ -0,0 +1,209 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{
+    audio::{Decodable, Source},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+};
+
+/// Whether to play any sounds at all. Disabled with `--no-audio` or by setting
+/// `SPACEFLIGHT_NO_AUDIO`, for headless runs or machines without an audio device.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioEnabled(pub bool);
+
+impl Default for AudioEnabled {
+    fn default() -> Self {
+        AudioEnabled(true)
+    }
+}
+
+impl AudioEnabled {
+    pub fn from_env() -> Self {
+        let disabled = std::env::args().any(|arg| arg == "--no-audio")
+            || std::env::var_os("SPACEFLIGHT_NO_AUDIO").is_some();
+        AudioEnabled(!disabled)
+    }
+}
+
+/// How the looping thruster sound follows the throttle. At zero throttle it plays
+/// at the minimums, at full throttle at the maximums.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ThrusterSoundSettings {
+    pub min_speed: f32,
+    pub max_speed: f32,
+    pub min_volume: f32,
+    pub max_volume: f32,
+}
+
+impl Default for ThrusterSoundSettings {
+    fn default() -> Self {
+        Self {
+            min_speed: 0.6,
+            max_speed: 1.3,
+            min_volume: 0.3,
+            max_volume: 1.0,
+        }
+    }
+}
+
+/// Playback speeds outside of this range sound broken rather than lower or higher.
+const SPEED_RANGE: (f32, f32) = (0.25, 4.0);
+
+impl ThrusterSoundSettings {
+    /// The playback speed and volume at `throttle`.
+    pub fn at_throttle(&self, throttle: f32) -> (f32, f32) {
+        let throttle = throttle.clamp(0.0, 1.0);
+        let speed = self.min_speed + (self.max_speed - self.min_speed) * throttle;
+        let volume = self.min_volume + (self.max_volume - self.min_volume) * throttle;
+        (speed.clamp(SPEED_RANGE.0, SPEED_RANGE.1), volume.max(0.0))
+    }
+}
+
+/// Plays `path` once and despawns the entity when it's done, unless audio is disabled.
+pub fn play_one_shot(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    audio: AudioEnabled,
+    path: &str,
+    settings: PlaybackSettings,
+) {
+    if !audio.0 {
+        return;
+    }
+
+    commands.spawn(AudioBundle {
+        source: asset_server.load(path),
+        settings: PlaybackSettings {
+            mode: bevy::audio::PlaybackMode::Despawn,
+            ..settings
+        },
+    });
+}
+
+/// A sine tone that fades out, for short chimes that don't need a sound file. Only there with
+/// audio enabled, registered with `add_audio_source`.
+#[derive(TypeUuid, TypePath, Debug, Clone, Copy, PartialEq)]
+#[uuid = "aeb52142-dcf7-4ded-8447-d664c29f857b"]
+pub struct Tone {
+    /// In Hz.
+    pub frequency: f32,
+    /// In seconds.
+    pub duration: f32,
+}
+
+const TONE_SAMPLE_RATE: u32 = 44_100;
+
+impl Decodable for Tone {
+    type DecoderItem = f32;
+    type Decoder = ToneDecoder;
+
+    fn decoder(&self) -> ToneDecoder {
+        ToneDecoder {
+            tone: *self,
+            sample: 0,
+        }
+    }
+}
+
+/// The samples of a [`Tone`].
+pub struct ToneDecoder {
+    tone: Tone,
+    sample: u32,
+}
+
+impl Iterator for ToneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let t = self.sample as f32 / TONE_SAMPLE_RATE as f32;
+        if t >= self.tone.duration {
+            return None;
+        }
+        self.sample += 1;
+        // fading out all the way, so it doesn't end with a click
+        let envelope = 1.0 - t / self.tone.duration;
+        Some((TAU * self.tone.frequency * t).sin() * envelope)
+    }
+}
+
+impl Source for ToneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        TONE_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.tone.duration))
+    }
+}
+
+/// Plays `tone` once like [`play_one_shot`]. There are no `tones` when audio is disabled.
+pub fn play_tone(
+    commands: &mut Commands,
+    tones: Option<&mut Assets<Tone>>,
+    tone: Tone,
+    settings: PlaybackSettings,
+) {
+    let Some(tones) = tones else {
+        return;
+    };
+
+    commands.spawn(AudioSourceBundle {
+        source: tones.add(tone),
+        settings: PlaybackSettings {
+            mode: bevy::audio::PlaybackMode::Despawn,
+            ..settings
+        },
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decodable, ThrusterSoundSettings, Tone};
+
+    #[test]
+    fn follows_throttle() {
+        let settings = ThrusterSoundSettings::default();
+        assert_eq!(settings.at_throttle(0.0), (0.6, 0.3));
+        assert_eq!(settings.at_throttle(1.0), (1.3, 1.0));
+
+        let (half_speed, half_volume) = settings.at_throttle(0.5);
+        assert!(0.6 < half_speed && half_speed < 1.3);
+        assert!(0.3 < half_volume && half_volume < 1.0);
+    }
+
+    #[test]
+    fn clamps_speed() {
+        let settings = ThrusterSoundSettings {
+            min_speed: 0.0,
+            max_speed: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(settings.at_throttle(0.0).0, 0.25);
+        assert_eq!(settings.at_throttle(1.0).0, 4.0);
+        assert_eq!(settings.at_throttle(2.0), settings.at_throttle(1.0));
+    }
+
+    #[test]
+    fn tone_fades_out() {
+        let tone = Tone {
+            frequency: 440.0,
+            duration: 0.5,
+        };
+        let samples: Vec<f32> = tone.decoder().collect();
+        assert_eq!(samples.len(), 22_050);
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+        let start = samples[..1000].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        let end = samples[samples.len() - 1000..]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(start > 0.9 && end < 0.05, "{start} {end}");
+    }
+}
@@ -0,0 +1,274 @@
+//! Flying the ship down to a soft touchdown on a point of the surface.
+//!
+//! The descent is flown like a suicide burn: the ship falls freely until it has to brake
+//! at (a bit less than) full thrust to arrive at the surface with almost no speed left.
+//! The horizontal velocity is cancelled on the way down.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    ascent::GravityTurnAscent, forces::ExternalForceSet, input::InputBindings, landing::Landed,
+    GravityForce, Planet, Spaceship, Thrusters, SHIP_HEIGHT,
+};
+
+/// Lands the ship at `target`, a point on the surface.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct LandAt {
+    pub target: Vec3,
+}
+
+/// How much of the thrust left after hovering is planned for braking,
+/// the rest is reserve for correcting errors.
+pub const BRAKING_MARGIN: f32 = 0.7;
+/// The vertical speed to touch down with.
+pub const TOUCHDOWN_SPEED: f32 = 0.3;
+/// How fast the vertical speed follows the descent profile, per second.
+pub const VERTICAL_GAIN: f32 = 2.0;
+/// Spring constant of the horizontal controller, per second squared.
+pub const HORIZONTAL_STIFFNESS: f32 = 0.04;
+/// Damping of the horizontal controller, per second. Critically damped.
+pub const HORIZONTAL_DAMPING: f32 = 0.4;
+/// Further away from the target than this, the ship doesn't descend below [`HOVER_ALTITUDE`].
+pub const LANDING_RADIUS: f32 = 2.0;
+pub const HOVER_ALTITUDE: f32 = 10.0;
+
+/// The thrust acceleration that guides a ship at `pos` moving with `vel` down
+/// to `target`, with `gravity` pulling on it and at most `max_acceleration` of thrust.
+/// All are relative to the surface, which is assumed to be locally flat.
+pub fn descent_acceleration(
+    (pos, vel): (Vec3, Vec3),
+    target: Vec3,
+    gravity: Vec3,
+    max_acceleration: f32,
+) -> Vec3 {
+    let g = gravity.length();
+    let up = -gravity.normalize_or_zero();
+
+    let offset = pos - target;
+    let altitude = offset.dot(up);
+    let horizontal_offset = offset - altitude * up;
+    let vertical_speed = vel.dot(up);
+    let horizontal_vel = vel - vertical_speed * up;
+
+    let far = horizontal_offset.length() > LANDING_RADIUS;
+    let braking = (BRAKING_MARGIN * (max_acceleration - g)).max(f32::EPSILON);
+
+    // the speed that can just be braked to the touchdown speed until reaching the surface,
+    // or hovering at a safe altitude while the ship isn't above the target yet
+    let altitude = if far {
+        altitude - HOVER_ALTITUDE
+    } else {
+        altitude
+    };
+    let target_speed = f32::sqrt(2.0 * braking * altitude.abs()) * altitude.signum();
+    let target_speed = if far {
+        target_speed
+    } else {
+        target_speed + TOUCHDOWN_SPEED
+    };
+
+    let vertical =
+        (g + VERTICAL_GAIN * (-target_speed - vertical_speed)).clamp(0.0, max_acceleration);
+
+    let horizontal =
+        -HORIZONTAL_STIFFNESS * horizontal_offset - HORIZONTAL_DAMPING * horizontal_vel;
+    let remaining = f32::sqrt(max_acceleration * max_acceleration - vertical * vertical);
+    let horizontal = horizontal.clamp_length_max(remaining);
+
+    up * vertical + horizontal
+}
+
+/// Marks the autopilot thrust in the [`ExternalForceSet`].
+pub struct AutopilotForce;
+
+/// Strength of the attitude controller keeping the engine pointed along the thrust.
+pub const ATTITUDE_STIFFNESS: f32 = 2.0;
+pub const ATTITUDE_DAMPING: f32 = 3.0;
+
+pub fn land_at(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut query: Query<
+        (
+            Entity,
+            &LandAt,
+            &mut ExternalForceSet,
+            &Transform,
+            &Velocity,
+            &ReadMassProperties,
+            &Thrusters,
+            Option<&Landed>,
+        ),
+        With<Spaceship>,
+    >,
+) {
+    for (entity, land_at, mut forces, transform, velocity, mass, thrusters, landed) in &mut query {
+        let mass = mass.0.mass;
+        if mass <= 0.0 {
+            // rapier hasn't computed the mass yet
+            continue;
+        }
+        let gravity = forces.get::<GravityForce>().force / mass;
+        let up = -gravity.normalize_or_zero();
+        // the target is for the bottom of the ship
+        let target = land_at.target + up * SHIP_HEIGHT / 2.0;
+
+        let touched_down = (transform.translation - target).dot(up) <= 0.05;
+        if landed.is_some() || touched_down || keyboard_input.pressed(bindings.thrust) {
+            commands.entity(entity).remove::<LandAt>();
+            forces.set::<AutopilotForce>(ExternalForce::default());
+            info!("Autopilot off");
+            continue;
+        }
+
+        let acceleration = descent_acceleration(
+            (transform.translation, velocity.linvel),
+            target,
+            gravity,
+            thrusters.max_force() / mass,
+        );
+
+        // keep the engine below the ship, pointing along the thrust or straight down
+        let thrust_dir = acceleration.try_normalize().unwrap_or(up);
+        let error = (transform.rotation * Vec3::Y).cross(thrust_dir);
+        let torque = ATTITUDE_STIFFNESS * error - ATTITUDE_DAMPING * velocity.angvel;
+
+        forces.set::<AutopilotForce>(ExternalForce {
+            force: acceleration * mass,
+            torque,
+        });
+    }
+}
+
+/// Clicking on a planet lands the ship there.
+pub fn pick_landing_target(
+    mut commands: Commands,
+    mouse_input: Res<Input<MouseButton>>,
+    rapier_context: Res<RapierContext>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    ship_query: Query<Entity, With<Spaceship>>,
+    planet_query: Query<(), With<Planet>>,
+) {
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform)), Ok(ship)) = (
+        window_query.get_single(),
+        camera_query.get_single(),
+        ship_query.get_single(),
+    ) else {
+        return;
+    };
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    let filter = QueryFilter::default().exclude_collider(ship);
+    let Some((hit, toi)) =
+        rapier_context.cast_ray(ray.origin, ray.direction, f32::MAX, true, filter)
+    else {
+        return;
+    };
+    if !planet_query.contains(hit) {
+        return;
+    }
+
+    let target = ray.get_point(toi);
+    commands
+        .entity(ship)
+        .remove::<GravityTurnAscent>()
+        .insert(LandAt { target });
+    info!("Landing at {target}");
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{descent_acceleration, AutopilotForce, LandAt};
+    use crate::{
+        ascent::GravityTurnAscent,
+        forces::ExternalForceSet,
+        headless::{self, KeyHold},
+        input::InputBindings,
+        scenario::{Scenario, SMALL_PLANET_RADIUS},
+        Spaceship,
+    };
+
+    #[test]
+    fn soft_landing_in_uniform_gravity() {
+        let gravity = Vec3::new(0.0, -0.5, 0.0);
+        let max_acceleration = 1.0;
+        let target = Vec3::new(50.0, 0.0, -20.0);
+
+        let mut pos = Vec3::new(0.0, 200.0, 0.0);
+        let mut vel = Vec3::new(3.0, -2.0, 1.0);
+
+        let dt = 1.0 / 60.0;
+        let mut ticks = 0;
+        while pos.y > 0.0 {
+            let thrust = descent_acceleration((pos, vel), target, gravity, max_acceleration);
+            assert!(thrust.length() <= max_acceleration + 1e-4);
+
+            vel += (thrust + gravity) * dt;
+            pos += vel * dt;
+
+            ticks += 1;
+            assert!(
+                ticks < 60 * 600,
+                "didn't land in 10 minutes, at {pos} with {vel}"
+            );
+        }
+
+        assert!(vel.length() < 1.0, "touched down at {vel}");
+        let miss = (pos - target) * Vec3::new(1.0, 0.0, 1.0);
+        assert!(miss.length() < 1.0, "missed by {miss}");
+    }
+
+    /// The circular orbit, with the ship landing below where it starts after the first tick.
+    fn landing_app() -> (App, Entity) {
+        let mut app = headless::headless_app(Scenario::CircularOrbit);
+        headless::run_app(&mut app, &[], 1);
+        let ship = app
+            .world
+            .query_filtered::<Entity, With<Spaceship>>()
+            .single(&app.world);
+        app.world.entity_mut(ship).insert(LandAt {
+            target: Vec3::new(SMALL_PLANET_RADIUS as f32, 0.0, 0.0),
+        });
+        (app, ship)
+    }
+
+    #[test]
+    fn flies_the_spawned_ship() {
+        let (mut app, ship) = landing_app();
+        let state = headless::run_app(&mut app, &[], 30);
+
+        let forces = app.world.get::<ExternalForceSet>(ship).unwrap();
+        let force = forces.get::<AutopilotForce>().force;
+        assert!(force.length() > 0.0, "{force}");
+        assert!(app.world.get::<LandAt>(ship).is_some());
+        // up against gravity, the engine only moves the ship once its mass is known
+        assert!(force.dot(state.position) > 0.0, "{force}");
+    }
+
+    #[test]
+    fn ascent_takes_over_from_landing() {
+        let (mut app, ship) = landing_app();
+        let ascent = KeyHold {
+            key: app.world.resource::<InputBindings>().ascent,
+            ticks: 10..11,
+        };
+        headless::run_app(&mut app, &[ascent], 12);
+
+        assert!(app.world.get::<LandAt>(ship).is_none());
+        assert!(app.world.get::<GravityTurnAscent>(ship).is_some());
+    }
+}
@@ -0,0 +1,112 @@
+//! Tinting planets by latitude with vertex colors baked into their meshes, so that they get
+//! ice caps on top of the base texture.
+
+use bevy::{
+    prelude::*,
+    render::mesh::{Mesh, VertexAttributeValues},
+};
+
+use crate::{lod::PlanetLod, Planet};
+
+/// Enabled with `--biomes`, otherwise planets only show the plain texture.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PlanetBiomes {
+    pub enabled: bool,
+    /// From this latitude on in degrees, towards the poles, the surface turns icy.
+    pub ice_cap_latitude: f32,
+    /// The latitude band in degrees over which the ice fades in.
+    pub ice_cap_blend: f32,
+    /// How bright the surface away from the ice is. The colors multiply the texture, so the
+    /// ice can only stand out against a darker surface.
+    pub surface_brightness: f32,
+}
+
+impl Default for PlanetBiomes {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ice_cap_latitude: 65.0,
+            ice_cap_blend: 10.0,
+            surface_brightness: 0.8,
+        }
+    }
+}
+
+impl PlanetBiomes {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: std::env::args().any(|arg| arg == "--biomes"),
+            ..default()
+        }
+    }
+
+    /// The tint for a vertex at `latitude` degrees. White leaves the texture as it is.
+    pub fn tint(&self, latitude: f32) -> Color {
+        let ice = ((latitude.abs() - self.ice_cap_latitude) / self.ice_cap_blend).clamp(0.0, 1.0);
+        let brightness = self.surface_brightness + (1.0 - self.surface_brightness) * ice;
+        // the surface is a bit warmer than the ice
+        let blue = brightness * (0.9 + 0.1 * ice);
+        Color::rgb(brightness, brightness, blue)
+    }
+
+    /// Adds vertex colors to a planet `mesh` centered on the origin with the poles along Y.
+    pub fn bake(&self, mesh: &mut Mesh) {
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return;
+        };
+
+        let colors = positions
+            .iter()
+            .map(|&position| {
+                let position = Vec3::from(position);
+                let latitude = (position.y / position.length())
+                    .clamp(-1.0, 1.0)
+                    .asin()
+                    .to_degrees();
+                self.tint(latitude).as_rgba_f32()
+            })
+            .collect::<Vec<_>>();
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    }
+}
+
+pub fn bake_planet_biomes(
+    biomes: Res<PlanetBiomes>,
+    query: Query<&PlanetLod, (With<Planet>, Added<PlanetLod>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !biomes.enabled {
+        return;
+    }
+
+    for lod in &query {
+        for (_, handle) in &lod.levels {
+            if let Some(mesh) = meshes.get_mut(handle) {
+                biomes.bake(mesh);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::PlanetBiomes;
+
+    #[test]
+    fn ice_at_poles_only() {
+        let biomes = PlanetBiomes::default();
+
+        let equator = biomes.tint(0.0);
+        assert_eq!(biomes.tint(-40.0), equator);
+        assert!(equator.r() < 0.9, "{equator:?}");
+        assert_eq!(biomes.tint(90.0), Color::WHITE);
+        assert_eq!(biomes.tint(-90.0), Color::WHITE);
+
+        let fading = biomes.tint(70.0);
+        assert!(equator.r() < fading.r() && fading.r() < 1.0, "{fading:?}");
+    }
+}
@@ -0,0 +1,118 @@
+//! Tunnel vision at high g: a dark vignette closing in on the screen the harder the ship
+//! accelerates, like a pilot losing their sight.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{forces::ExternalForceSet, health, Spaceship};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Blackout {
+    pub enabled: bool,
+    /// From this load on in g, the vignette starts to show.
+    pub onset: f32,
+    /// At this load in g, the screen is as dark as it gets.
+    pub full: f32,
+    /// How fast the effect follows the load, in intensity per second.
+    pub fade_rate: f32,
+}
+
+impl Default for Blackout {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            onset: 4.0,
+            full: 9.0,
+            fade_rate: 0.8,
+        }
+    }
+}
+
+impl Blackout {
+    /// The intensity the effect approaches at `g_load`, from 0 to 1.
+    pub fn target_intensity(&self, g_load: f32) -> f32 {
+        if !self.enabled {
+            return 0.0;
+        }
+        ((g_load - self.onset) / (self.full - self.onset)).clamp(0.0, 1.0)
+    }
+}
+
+/// The screen overlay, with its current intensity.
+#[derive(Component, Default)]
+pub struct BlackoutOverlay {
+    pub intensity: f32,
+}
+
+pub fn spawn_blackout_overlay(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            // above the HUD, it's the pilot who can't see
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        BorderColor(Color::NONE),
+        BlackoutOverlay::default(),
+    ));
+}
+
+pub fn update_blackout(
+    time: Res<Time>,
+    config: Res<Blackout>,
+    query: Query<(&ExternalForceSet, &ReadMassProperties), With<Spaceship>>,
+    mut overlay_query: Query<(
+        &mut BlackoutOverlay,
+        &mut Style,
+        &mut BorderColor,
+        &mut BackgroundColor,
+    )>,
+) {
+    let target = match query.get_single() {
+        Ok((forces, mass)) if mass.0.mass > 0.0 => {
+            config.target_intensity(health::g_load(forces, mass.0.mass))
+        }
+        _ => 0.0,
+    };
+
+    for (mut overlay, mut style, mut border, mut background) in &mut overlay_query {
+        // fade instead of popping on and off
+        let step = config.fade_rate * time.delta_seconds();
+        overlay.intensity += (target - overlay.intensity).clamp(-step, step);
+        let intensity = overlay.intensity;
+
+        // the edges go dark first, closing in towards the center
+        style.border = UiRect::all(Val::Percent(35.0 * intensity));
+        border.0 = Color::rgba(0.0, 0.0, 0.0, 0.9 * intensity);
+        background.0 = Color::rgba(0.0, 0.0, 0.0, 0.6 * intensity * intensity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Blackout;
+
+    #[test]
+    fn intensity_follows_load() {
+        let config = Blackout::default();
+
+        assert_eq!(config.target_intensity(1.0), 0.0);
+        assert_eq!(config.target_intensity(config.onset), 0.0);
+        assert_eq!(
+            config.target_intensity((config.onset + config.full) / 2.0),
+            0.5
+        );
+        assert_eq!(config.target_intensity(20.0), 1.0);
+
+        let disabled = Blackout {
+            enabled: false,
+            ..config
+        };
+        assert_eq!(disabled.target_intensity(20.0), 0.0);
+    }
+}
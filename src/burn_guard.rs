@@ -0,0 +1,190 @@
+//! Cutting the main engine the moment the orbit reaches a target apoapsis or periapsis, so a
+//! manual burn doesn't overshoot it. `--guard-apoapsis=<radius>` and `--guard-periapsis=<radius>`
+//! set the targets, as distances from the center of the attractor like in the HUD.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    gamepad::GamepadInput,
+    input::InputBindings,
+    orbit::Orbit,
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    GravityAttractor, Spaceship,
+};
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct BurnGuard {
+    pub target_apoapsis: Option<f64>,
+    pub target_periapsis: Option<f64>,
+    /// Set once a target was reached. It keeps the engine off until the thrust key is let go,
+    /// after that the guard is done and the engine works as usual.
+    pub tripped: bool,
+    /// The body and the apoapsis and periapsis around it the last frame the engine fired, to
+    /// tell when they cross a target.
+    last: Option<(Entity, f64, f64)>,
+}
+
+impl BurnGuard {
+    pub fn from_args() -> Self {
+        let target = |prefix: &str| {
+            let arg =
+                std::env::args().find_map(|arg| arg.strip_prefix(prefix).map(str::to_owned))?;
+            match arg.parse() {
+                Ok(radius) => Some(radius),
+                Err(_) => {
+                    warn!("Invalid burn guard target `{arg}`, ignoring it");
+                    None
+                }
+            }
+        };
+        Self {
+            target_apoapsis: target("--guard-apoapsis="),
+            target_periapsis: target("--guard-periapsis="),
+            ..default()
+        }
+    }
+
+    /// Whether the orbit around `body` with `apoapsis` and `periapsis` has reached a target
+    /// since the last update, either way. A target that's reached is cleared, so it only trips
+    /// once. Call it only while the engine fires, and [`Self::coast`] otherwise.
+    pub fn update(&mut self, body: Entity, apoapsis: f64, periapsis: f64) -> bool {
+        let last = self.last.replace((body, apoapsis, periapsis));
+        // the orbit around another body has nothing to do with the one before
+        let Some((_, last_apoapsis, last_periapsis)) =
+            last.filter(|&(last_body, ..)| last_body == body)
+        else {
+            return false;
+        };
+        let mut reached = false;
+        for (target, last, now) in [
+            (&mut self.target_apoapsis, last_apoapsis, apoapsis),
+            (&mut self.target_periapsis, last_periapsis, periapsis),
+        ] {
+            if target.is_some_and(|target| crossed(target, last, now)) {
+                *target = None;
+                reached = true;
+            }
+        }
+        self.tripped |= reached;
+        reached
+    }
+
+    /// Forgets the last orbit, whatever changes it without the engine firing doesn't count.
+    pub fn coast(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Whether going from `last` to `now` got to `target`. An infinite apoapsis counts as beyond
+/// any target, a NaN one as not there yet.
+fn crossed(target: f64, last: f64, now: f64) -> bool {
+    now == target || ((last < target) != (now < target) && !now.is_nan() && !last.is_nan())
+}
+
+pub fn check_burn_guard(
+    mut guard: ResMut<BurnGuard>,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    gamepad: Res<GamepadInput>,
+    reference: Res<ReferenceBody>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+) {
+    if guard.target_apoapsis.is_none() && guard.target_periapsis.is_none() {
+        return;
+    }
+    let firing =
+        !guard.tripped && (keyboard_input.pressed(bindings.thrust) || gamepad.trigger > 0.0);
+    let (true, Ok((ship, velocity))) = (firing, query.get_single()) else {
+        guard.coast();
+        return;
+    };
+    let Some((body, body_transform, gravity)) =
+        reference_attractor(&reference, &body_query, ship.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        guard.coast();
+        return;
+    };
+
+    let orbit = Orbit::from_pos_dir_3d(
+        gravity.mass,
+        (ship.translation - body_transform.translation).as_dvec3(),
+        (velocity.linvel - velocities.of(body)).as_dvec3(),
+    );
+    if guard.update(body, orbit.apoapsis(), orbit.periapsis()) {
+        info!(
+            "Burn guard cut the engine at Ap {:.1}, Pe {:.1}",
+            orbit.apoapsis(),
+            orbit.periapsis()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::BurnGuard;
+
+    const BODY: Entity = Entity::from_raw(1);
+
+    #[test]
+    fn trips_once_when_raising_the_apoapsis() {
+        let mut guard = BurnGuard {
+            target_apoapsis: Some(2000.0),
+            ..Default::default()
+        };
+        assert!(!guard.update(BODY, 1500.0, 1000.0));
+        assert!(!guard.update(BODY, 1990.0, 1000.0));
+        assert!(guard.update(BODY, 2010.0, 1000.0));
+        assert!(guard.tripped);
+        assert_eq!(guard.target_apoapsis, None);
+        // it's done, going back down doesn't trip it again
+        assert!(!guard.update(BODY, 1990.0, 1000.0));
+    }
+
+    #[test]
+    fn trips_when_lowering_the_periapsis() {
+        let mut guard = BurnGuard {
+            target_periapsis: Some(1100.0),
+            ..Default::default()
+        };
+        assert!(!guard.update(BODY, 2000.0, 1500.0));
+        assert!(guard.update(BODY, 2000.0, 1050.0));
+        assert_eq!(guard.target_periapsis, None);
+    }
+
+    #[test]
+    fn escaping_is_beyond_any_apoapsis() {
+        let mut guard = BurnGuard {
+            target_apoapsis: Some(1e9),
+            ..Default::default()
+        };
+        assert!(!guard.update(BODY, 5e8, 1000.0));
+        assert!(guard.update(BODY, f64::INFINITY, 1000.0));
+    }
+
+    #[test]
+    fn only_crossed_while_burning() {
+        let mut guard = BurnGuard {
+            target_periapsis: Some(1100.0),
+            ..Default::default()
+        };
+        assert!(!guard.update(BODY, 2000.0, 1500.0));
+        // the drag lowers it in the meantime
+        guard.coast();
+        assert!(!guard.update(BODY, 2000.0, 1050.0));
+        // and another body's orbit isn't a crossing either
+        assert!(!guard.update(Entity::from_raw(2), 2000.0, 1500.0));
+        assert!(!guard.update(BODY, 2000.0, 1000.0));
+        assert_eq!(guard.target_periapsis, Some(1100.0));
+
+        guard.coast();
+        assert!(!guard.update(BODY, 2000.0, 1200.0));
+        assert!(guard.update(BODY, 2000.0, 1000.0));
+    }
+}
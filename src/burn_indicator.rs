@@ -0,0 +1,131 @@
+//! A countdown to the next scheduled burn, and how much of its delta-v is still left to apply
+//! while it's executed.
+
+use bevy::prelude::*;
+
+use crate::{
+    hud::WarningText,
+    impulse::{BurnProgress, ExecutingBurn, ScheduledBurns},
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct BurnIndicator {
+    pub enabled: bool,
+    /// The countdown only shows up this many seconds before the burn.
+    pub countdown_from: f64,
+    /// How long a finished burn stays on the HUD, in seconds.
+    pub complete_for: f64,
+}
+
+impl Default for BurnIndicator {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            countdown_from: 120.0,
+            complete_for: 3.0,
+        }
+    }
+}
+
+/// A bar filling up with the applied delta-v.
+fn progress_bar(burn: &ExecutingBurn, width: usize) -> String {
+    let fraction = if burn.planned > 0.0 {
+        (burn.applied / burn.planned).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let filled = (fraction * width as f32).round() as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+/// What to show at `now`: the burn in progress or just done first, then the next one.
+pub fn burn_status(
+    config: &BurnIndicator,
+    schedule: &ScheduledBurns,
+    progress: &BurnProgress,
+    now: f64,
+) -> Option<String> {
+    if let Some(burn) = progress.0 {
+        if burn.remaining() > 0.0 {
+            return Some(format!(
+                "BURN {} Δv left {:.2} of {:.2}",
+                progress_bar(&burn, 10),
+                burn.remaining(),
+                burn.planned
+            ));
+        }
+        if now - burn.updated < config.complete_for {
+            return Some(format!("Burn complete, Δv {:.2}", burn.applied));
+        }
+    }
+
+    let next = schedule.next()?;
+    let t_minus = next.ignition() - now;
+    (t_minus <= config.countdown_from)
+        .then(|| format!("Burn in T-{t_minus:.1} s, Δv {:.2}", next.prograde_dv.abs()))
+}
+
+pub fn update_burn_indicator(
+    config: Res<BurnIndicator>,
+    time: Res<Time>,
+    schedule: Res<ScheduledBurns>,
+    progress: Res<BurnProgress>,
+    mut text_query: Query<&mut Text, With<WarningText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let status = config
+        .enabled
+        .then(|| burn_status(&config, &schedule, &progress, time.elapsed_seconds_f64()))
+        .flatten();
+    text.sections[3].value = status.unwrap_or_else(|| "-".to_owned());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{burn_status, BurnIndicator};
+    use crate::impulse::{BurnProgress, ExecutingBurn, ScheduledBurn, ScheduledBurns};
+
+    #[test]
+    fn counts_down_then_shows_progress() {
+        let config = BurnIndicator::default();
+        let mut schedule = ScheduledBurns::default();
+        schedule.schedule(ScheduledBurn {
+            at: 200.0,
+            prograde_dv: 1.5,
+        });
+        let none = BurnProgress::default();
+
+        // too far away
+        assert_eq!(burn_status(&config, &schedule, &none, 0.0), None);
+        assert_eq!(
+            // it ignites half the burn early, a quarter of a second
+            burn_status(&config, &schedule, &none, 189.625).as_deref(),
+            Some("Burn in T-10.0 s, Δv 1.50")
+        );
+
+        let mut burn = ExecutingBurn {
+            planned: 2.0,
+            applied: 0.5,
+            direction: 1.0,
+            updated: 200.0,
+        };
+        let status = burn_status(&config, &schedule, &BurnProgress(Some(burn)), 200.0);
+        assert_eq!(
+            status.as_deref(),
+            Some("BURN [###-------] Δv left 1.50 of 2.00")
+        );
+
+        burn.applied = 2.0;
+        let done = BurnProgress(Some(burn));
+        assert_eq!(
+            burn_status(&config, &schedule, &done, 201.0).as_deref(),
+            Some("Burn complete, Δv 2.00")
+        );
+        // back to the countdown of the next one
+        assert!(burn_status(&config, &schedule, &done, 210.0)
+            .unwrap()
+            .starts_with("Burn in"));
+    }
+}
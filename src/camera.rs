@@ -0,0 +1,177 @@
+//! Camera modes besides the orbit camera following the ship.
+
+use bevy::prelude::*;
+
+use crate::input::InputBindings;
+
+/// A static pose, for watching the whole scene from the same vantage point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedCamera {
+    pub position: Vec3,
+    pub look_at: Vec3,
+}
+
+impl Default for FixedCamera {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(0.0, 30_000.0, 60_000.0),
+            look_at: Vec3::ZERO,
+        }
+    }
+}
+
+impl FixedCamera {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position).looking_at(self.look_at, Vec3::Y)
+    }
+}
+
+/// How far in front of an obstruction the follow camera stops.
+pub const COLLISION_MARGIN: f32 = 0.5;
+
+/// The distance of the follow camera from the ship, pulled in before the first thing hit by a
+/// ray from the ship towards the desired camera position at `time_of_impact`, if any.
+pub fn unobstructed_radius(radius: f32, time_of_impact: Option<f32>) -> f32 {
+    match time_of_impact {
+        Some(toi) if toi < radius => (toi - COLLISION_MARGIN).max(0.0),
+        _ => radius,
+    }
+}
+
+/// What the follow camera keeps its orientation relative to.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraFrame {
+    /// Fixed in space, the ship moves along its orbit in view.
+    #[default]
+    Inertial,
+    /// Turning with the ship's motion around the attractor, so the orbit stands still
+    /// and the rest of the scene turns around it.
+    Orbital,
+}
+
+/// The rotation that turns the view along with the ship moving around the attractor from
+/// `previous` to `current`, both relative to it. It's around the orbit normal, so the
+/// orbit stays in place.
+pub fn orbital_co_rotation(previous: Vec3, current: Vec3) -> Quat {
+    match (previous.try_normalize(), current.try_normalize()) {
+        (Some(previous), Some(current)) => Quat::from_rotation_arc(previous, current),
+        _ => Quat::IDENTITY,
+    }
+}
+
+pub fn toggle_camera_frame(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut frame: ResMut<CameraFrame>,
+) {
+    if keyboard_input.just_pressed(bindings.camera_frame) {
+        *frame = match *frame {
+            CameraFrame::Inertial => CameraFrame::Orbital,
+            CameraFrame::Orbital => CameraFrame::Inertial,
+        };
+        info!("Camera frame: {:?}", *frame);
+    }
+}
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    /// Orbiting around the ship with the mouse.
+    #[default]
+    Follow,
+    Fixed(FixedCamera),
+}
+
+/// F10 switches between following the ship and the fixed camera,
+/// which keeps its pose from the last time it was used.
+pub fn toggle_camera_mode(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mode: ResMut<CameraMode>,
+    mut last_fixed: Local<FixedCamera>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F10) {
+        return;
+    }
+
+    *mode = match *mode {
+        CameraMode::Follow => CameraMode::Fixed(*last_fixed),
+        CameraMode::Fixed(fixed) => {
+            *last_fixed = fixed;
+            CameraMode::Follow
+        }
+    };
+}
+
+/// How fast the fixed camera moves, relative to its distance to the point it looks at.
+const REPOSITION_SPEED: f32 = 0.5;
+
+/// Holds the camera at the fixed pose. The numpad moves it around: 8/2/4/6 across
+/// the view and +/- towards and away from the point it looks at.
+pub fn fixed_camera(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mode: ResMut<CameraMode>,
+    mut query: Query<&mut Transform, With<Camera3d>>,
+) {
+    let CameraMode::Fixed(fixed) = &mut *mode else {
+        return;
+    };
+
+    let forward = (fixed.look_at - fixed.position).normalize_or_zero();
+    let right = forward.cross(Vec3::Y).normalize_or_zero();
+    let up = right.cross(forward);
+
+    let keybinds = [
+        (KeyCode::Numpad8, up),
+        (KeyCode::Numpad2, -up),
+        (KeyCode::Numpad4, -right),
+        (KeyCode::Numpad6, right),
+        (KeyCode::NumpadAdd, forward),
+        (KeyCode::NumpadSubtract, -forward),
+    ];
+    let direction: Vec3 = keybinds
+        .into_iter()
+        .filter(|&(bind, _)| keyboard_input.pressed(bind))
+        .map(|(_, dir)| dir)
+        .sum();
+
+    if direction != Vec3::ZERO {
+        let distance = fixed.position.distance(fixed.look_at);
+        let step = direction * distance * REPOSITION_SPEED * time.delta_seconds();
+        // dollying moves only the camera, but moving across the view moves both
+        let dolly = step.project_onto(forward);
+        fixed.position += step;
+        fixed.look_at += step - dolly;
+    }
+
+    for mut transform in &mut query {
+        *transform = fixed.transform();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{orbital_co_rotation, unobstructed_radius, COLLISION_MARGIN};
+
+    #[test]
+    fn pulls_in_before_obstruction() {
+        assert_eq!(unobstructed_radius(10.0, None), 10.0);
+        assert_eq!(unobstructed_radius(10.0, Some(20.0)), 10.0);
+        assert_eq!(unobstructed_radius(10.0, Some(4.0)), 4.0 - COLLISION_MARGIN);
+        assert_eq!(unobstructed_radius(10.0, Some(0.1)), 0.0);
+    }
+
+    #[test]
+    fn co_rotation_follows_the_orbit() {
+        let previous = Vec3::new(100.0, 0.0, 0.0);
+        let current = Vec3::new(99.0, 0.0, -14.0);
+        let rotation = orbital_co_rotation(previous, current);
+
+        let turned = rotation * previous.normalize();
+        assert!(turned.abs_diff_eq(current.normalize(), 1e-5), "{turned}");
+        // the orbit normal stays put
+        assert!((rotation * Vec3::Y).abs_diff_eq(Vec3::Y, 1e-5));
+        assert_eq!(orbital_co_rotation(Vec3::ZERO, current), Quat::IDENTITY);
+    }
+}
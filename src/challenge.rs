@@ -0,0 +1,347 @@
+//! Randomly generated orbit challenges for practice: start on one orbit and match another,
+//! graded by how much delta-v it took compared to a transfer estimate.
+
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use fastrand::Rng;
+
+use crate::{
+    autopilot::AutopilotForce,
+    dominant_attractor,
+    forces::ExternalForceSet,
+    impulse::ImpulseBurn,
+    mass_of,
+    orbit::{self, Orbit, OrbitalElements},
+    reference::BodyVelocities,
+    snapshot::orbit_points,
+    GravityAttractor, OrbitText, Planet, Spaceship, ThrusterForce,
+};
+
+/// `--challenge=<seed>[,<difficulty>]`, with the difficulty from 0 to 1.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct ChallengeSeed {
+    pub seed: u64,
+    pub difficulty: f64,
+}
+
+impl ChallengeSeed {
+    const DEFAULT_DIFFICULTY: f64 = 0.5;
+
+    pub fn parse(arg: &str) -> Option<Self> {
+        let (seed, difficulty) = match arg.split_once(',') {
+            Some((seed, difficulty)) => (seed, difficulty.parse().ok()?),
+            None => (arg, Self::DEFAULT_DIFFICULTY),
+        };
+        Some(Self {
+            seed: seed.parse().ok()?,
+            difficulty: f64::clamp(difficulty, 0.0, 1.0),
+        })
+    }
+
+    pub fn from_args() -> Option<Self> {
+        let arg =
+            std::env::args().find_map(|arg| arg.strip_prefix("--challenge=").map(str::to_owned))?;
+        let seed = Self::parse(&arg);
+        if seed.is_none() {
+            error!("Invalid challenge `{arg}`, expected `<seed>` or `<seed>,<difficulty>`");
+        }
+        seed
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Challenge {
+    pub start: OrbitalElements,
+    pub target: OrbitalElements,
+    /// What a Hohmann transfer and a plane change would take, as the reference for grading.
+    pub ideal_dv: f64,
+    pub used_dv: f64,
+    /// The grade once the target orbit is reached.
+    pub completed: Option<Grade>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grade {
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Grade {
+    /// From the ideal delta-v divided by the used one.
+    pub fn from_efficiency(efficiency: f64) -> Self {
+        match efficiency {
+            e if e >= 0.9 => Grade::A,
+            e if e >= 0.75 => Grade::B,
+            e if e >= 0.5 => Grade::C,
+            _ => Grade::D,
+        }
+    }
+}
+
+/// How close the live orbit has to get to the target.
+const SEMI_MAJOR_AXIS_TOLERANCE: f64 = 0.02;
+const ECCENTRICITY_TOLERANCE: f64 = 0.02;
+const INCLINATION_TOLERANCE: f64 = 1.0 * std::f64::consts::PI / 180.0;
+/// Neither orbit comes closer to the surface than this, relative to the radius.
+const MIN_PERIAPSIS: f64 = 1.1;
+
+/// Whether `live` matches `target` in size, shape and tilt; the orientation in the plane
+/// doesn't count.
+pub fn matches_target(live: &OrbitalElements, target: &OrbitalElements) -> bool {
+    (live.semi_major_axis - target.semi_major_axis).abs()
+        <= target.semi_major_axis * SEMI_MAJOR_AXIS_TOLERANCE
+        && (live.eccentricity - target.eccentricity).abs() <= ECCENTRICITY_TOLERANCE
+        && (live.inclination - target.inclination).abs() <= INCLINATION_TOLERANCE
+}
+
+/// A random start and target orbit around a planet with `radius` and mass `m`, further apart
+/// the higher the `difficulty` from 0 to 1. Both are closed and stay clear of the surface.
+pub fn generate_challenge(rng: &mut Rng, difficulty: f64, radius: f64, m: f64) -> Challenge {
+    let difficulty = difficulty.clamp(0.0, 1.0);
+
+    let start = OrbitalElements {
+        semi_major_axis: radius * (1.2 + 0.6 * rng.f64()),
+        eccentricity: 0.02 * rng.f64(),
+        inclination: 0.0,
+        longitude_of_ascending_node: 0.0,
+        argument_of_periapsis: 0.0,
+        true_anomaly: TAU * rng.f64(),
+    };
+
+    let change = 0.1 + 0.9 * difficulty * rng.f64();
+    let sign = if rng.bool() { 1.0 } else { -0.5 };
+    let eccentricity = 0.3 * difficulty * rng.f64();
+    let semi_major_axis = (start.semi_major_axis * (1.0 + sign * change))
+        .max(radius * MIN_PERIAPSIS / (1.0 - eccentricity));
+    let target = OrbitalElements {
+        semi_major_axis,
+        eccentricity,
+        inclination: 20f64.to_radians() * difficulty * rng.f64(),
+        longitude_of_ascending_node: TAU * rng.f64(),
+        argument_of_periapsis: TAU * rng.f64(),
+        true_anomaly: 0.0,
+    };
+
+    let start_orbit = Orbit {
+        semi_major_axis: start.semi_major_axis,
+        eccentricity: start.eccentricity,
+    };
+    let (burn, circularize) = orbit::hohmann_dv(m, &start_orbit, target.semi_major_axis);
+    let target_speed = f64::sqrt(orbit::G * m / target.semi_major_axis);
+    let plane_change = orbit::plane_change_dv(target_speed, target.inclination);
+
+    Challenge {
+        start,
+        target,
+        ideal_dv: burn.abs() + circularize.abs() + plane_change,
+        used_dv: 0.0,
+        completed: None,
+    }
+}
+
+/// Puts the ship on the start orbit once the scene exists.
+pub fn start_challenge(
+    mut commands: Commands,
+    seed: Option<Res<ChallengeSeed>>,
+    mut query: Query<(&mut Transform, &mut Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor, &Planet), Without<Spaceship>>,
+    velocities: BodyVelocities,
+) {
+    let Some(seed) = seed else {
+        return;
+    };
+    let Ok((mut transform, mut velocity)) = query.get_single_mut() else {
+        return;
+    };
+    let Some((body, body_transform, gravity, planet)) =
+        dominant_attractor(&body_query, transform.translation, |(_, t, g, _)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let mut rng = Rng::with_seed(seed.seed);
+    let challenge = generate_challenge(&mut rng, seed.difficulty, planet.radius, gravity.mass);
+    let (pos, v) = challenge.start.to_state(gravity.mass);
+    transform.translation = body_transform.translation + pos.as_vec3();
+    velocity.linvel = velocities.of(body) + v.as_vec3();
+    info!(
+        "Challenge {}: from {} to {}",
+        seed.seed, challenge.start, challenge.target
+    );
+
+    commands.remove_resource::<ChallengeSeed>();
+    commands.insert_resource(challenge);
+}
+
+/// The delta-v that the ship's own engines spent in `dt` seconds, the thrusters as well as
+/// the autopilot.
+fn propulsive_dv(forces: &ExternalForceSet, mass: f32, dt: f32) -> f32 {
+    let force = forces.get::<ThrusterForce>().force.length()
+        + forces.get::<AutopilotForce>().force.length();
+    force / mass * dt
+}
+
+/// Adds up the delta-v, checks for the target orbit and draws it in gold.
+pub fn update_challenge(
+    challenge: Option<ResMut<Challenge>>,
+    time: Res<Time>,
+    mut impulses: EventReader<ImpulseBurn>,
+    query: Query<
+        (
+            &Transform,
+            &Velocity,
+            &ExternalForceSet,
+            &ReadMassProperties,
+        ),
+        With<Spaceship>,
+    >,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let Some(mut challenge) = challenge else {
+        text.sections[21].value = "-".to_owned();
+        return;
+    };
+    let Ok((transform, velocity, forces, mass)) = query.get_single() else {
+        return;
+    };
+    let Some((body, body_transform, gravity)) =
+        dominant_attractor(&body_query, transform.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    gizmos.linestrip(
+        orbit_points(&challenge.target)
+            .into_iter()
+            .map(|p| body_transform.translation + p),
+        Color::GOLD,
+    );
+
+    if let Some(grade) = challenge.completed {
+        text.sections[21].value = format!(
+            "done, grade {grade:?} (Δv {:.2} of {:.2})",
+            challenge.used_dv, challenge.ideal_dv
+        );
+        return;
+    }
+
+    let impulse: f32 = impulses.iter().map(|burn| burn.dv.length()).sum();
+    challenge.used_dv += f64::from(propulsive_dv(
+        forces,
+        mass_of(Some(mass)),
+        time.delta_seconds(),
+    ));
+    challenge.used_dv += f64::from(impulse);
+
+    let live = OrbitalElements::from_state(
+        gravity.mass,
+        (transform.translation - body_transform.translation).as_dvec3(),
+        (velocity.linvel - velocities.of(body)).as_dvec3(),
+    );
+    if matches_target(&live, &challenge.target) {
+        let efficiency = challenge.ideal_dv / challenge.used_dv.max(f64::EPSILON);
+        let grade = Grade::from_efficiency(efficiency.min(1.0));
+        info!("Challenge complete with grade {grade:?}");
+        challenge.completed = Some(grade);
+    }
+
+    text.sections[21].value = format!(
+        "a {:+.0}% / e {:+.3} / i {:+.1}° (Δv {:.2}, ideal {:.2})",
+        (live.semi_major_axis / challenge.target.semi_major_axis - 1.0) * 100.0,
+        live.eccentricity - challenge.target.eccentricity,
+        (live.inclination - challenge.target.inclination).to_degrees(),
+        challenge.used_dv,
+        challenge.ideal_dv,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::ExternalForce;
+    use fastrand::Rng;
+
+    use super::{
+        generate_challenge, matches_target, propulsive_dv, ChallengeSeed, Grade, MIN_PERIAPSIS,
+    };
+    use crate::{autopilot::AutopilotForce, forces::ExternalForceSet, GravityForce, ThrusterForce};
+
+    const RADIUS: f64 = 1000.0;
+    const MASS: f64 = 1.6e20;
+
+    #[test]
+    fn challenges_are_solvable() {
+        for seed in 0..200 {
+            for difficulty in [0.0, 0.5, 1.0] {
+                let challenge =
+                    generate_challenge(&mut Rng::with_seed(seed), difficulty, RADIUS, MASS);
+                for elements in [challenge.start, challenge.target] {
+                    assert!(elements.eccentricity < 1.0, "{elements}");
+                    let periapsis = elements.radius_at_true_anomaly(0.0);
+                    assert!(periapsis >= RADIUS * MIN_PERIAPSIS - 1e-6, "{elements}");
+                }
+                assert!(!matches_target(&challenge.start, &challenge.target));
+                assert!(challenge.ideal_dv > 0.0 && challenge.ideal_dv.is_finite());
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_challenge() {
+        let a = generate_challenge(&mut Rng::with_seed(7), 0.5, RADIUS, MASS);
+        let b = generate_challenge(&mut Rng::with_seed(7), 0.5, RADIUS, MASS);
+        assert_eq!(a.target.to_string(), b.target.to_string());
+        assert!(matches_target(&a.target, &b.target));
+    }
+
+    #[test]
+    fn parses_and_grades() {
+        assert_eq!(
+            ChallengeSeed::parse("42,0.8"),
+            Some(ChallengeSeed {
+                seed: 42,
+                difficulty: 0.8
+            })
+        );
+        assert_eq!(ChallengeSeed::parse("42").unwrap().difficulty, 0.5);
+        assert_eq!(ChallengeSeed::parse("x"), None);
+
+        assert_eq!(Grade::from_efficiency(1.0), Grade::A);
+        assert_eq!(Grade::from_efficiency(0.6), Grade::C);
+        assert_eq!(Grade::from_efficiency(0.1), Grade::D);
+    }
+
+    #[test]
+    fn autopilot_costs_delta_v_too() {
+        let mut forces = ExternalForceSet::default();
+        forces.set::<GravityForce>(ExternalForce {
+            force: Vec3::new(0.0, -50.0, 0.0),
+            torque: Vec3::ZERO,
+        });
+        assert_eq!(propulsive_dv(&forces, 2.0, 0.5), 0.0);
+
+        forces.set::<ThrusterForce>(ExternalForce {
+            force: Vec3::new(0.0, 4.0, 0.0),
+            torque: Vec3::ZERO,
+        });
+        forces.set::<AutopilotForce>(ExternalForce {
+            force: Vec3::new(3.0, 0.0, 0.0),
+            torque: Vec3::ZERO,
+        });
+        assert_eq!(propulsive_dv(&forces, 2.0, 0.5), 7.0 / 2.0 * 0.5);
+    }
+}
@@ -0,0 +1,136 @@
+//! Flying through the own predicted orbit: with `--collect-markers` the spheres are put on it
+//! and get sensors, and the ship collects every one it passes through. Once all are gone, they
+//! come back for another round.
+
+use bevy::{audio::VolumeLevel, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{audio, hud::ShipStatusText, FunnyOrbitalSphere, Spaceship};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MarkerCollection {
+    /// Off by default, the markers are only there to look at normally.
+    pub enabled: bool,
+    /// The radius of the sensors, the same as the spheres look.
+    pub radius: f32,
+}
+
+impl Default for MarkerCollection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            radius: 100.0,
+        }
+    }
+}
+
+impl MarkerCollection {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: std::env::args().any(|arg| arg == "--collect-markers"),
+            ..default()
+        }
+    }
+}
+
+/// How many markers the ship collected, over all rounds.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct MarkerScore(pub u32);
+
+/// A marker that has been collected this round.
+#[derive(Component)]
+pub struct Collected;
+
+/// Gives the markers their sensors, including the collected ones again once all of them are.
+pub fn attach_marker_colliders(
+    mut commands: Commands,
+    config: Res<MarkerCollection>,
+    query: Query<(Entity, Option<&Collected>), (With<FunnyOrbitalSphere>, Without<Collider>)>,
+    collected_query: Query<(), (With<FunnyOrbitalSphere>, Without<Collected>)>,
+    mut visibility_query: Query<&mut Visibility, With<FunnyOrbitalSphere>>,
+) {
+    if !config.enabled {
+        return;
+    }
+    let new_round = collected_query.is_empty();
+    if new_round {
+        for mut visibility in &mut visibility_query {
+            *visibility = Visibility::Inherited;
+        }
+    }
+    for (marker, collected) in &query {
+        if collected.is_some() && !new_round {
+            continue;
+        }
+        commands.entity(marker).remove::<Collected>().insert((
+            Collider::ball(config.radius),
+            Sensor,
+            ActiveEvents::COLLISION_EVENTS,
+        ));
+    }
+}
+
+/// The chime of a collected marker. Each one after it is a semitone higher, for an octave.
+const CHIME: audio::Tone = audio::Tone {
+    frequency: 880.0,
+    duration: 0.15,
+};
+
+pub fn collect_markers(
+    mut commands: Commands,
+    mut tones: Option<ResMut<Assets<audio::Tone>>>,
+    config: Res<MarkerCollection>,
+    mut score: ResMut<MarkerScore>,
+    mut collisions: EventReader<CollisionEvent>,
+    ship_query: Query<Entity, With<Spaceship>>,
+    mut marker_query: Query<&mut Visibility, (With<FunnyOrbitalSphere>, Without<Collected>)>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let (true, Ok(ship)) = (config.enabled, ship_query.get_single()) else {
+        text.sections[15].value = "-".to_owned();
+        collisions.clear();
+        return;
+    };
+
+    for collision in collisions.iter() {
+        let &CollisionEvent::Started(e1, e2, _) = collision else {
+            continue;
+        };
+        let marker = match (e1, e2) {
+            (e, marker) | (marker, e) if e == ship => marker,
+            _ => continue,
+        };
+        // hidden already if it was collected earlier this frame
+        let Ok(mut visibility) = marker_query.get_mut(marker) else {
+            continue;
+        };
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+
+        *visibility = Visibility::Hidden;
+        commands
+            .entity(marker)
+            .remove::<(Collider, Sensor, ActiveEvents)>()
+            .insert(Collected);
+        score.0 += 1;
+        let semitones = (score.0 % 12) as f32;
+        audio::play_tone(
+            &mut commands,
+            tones.as_deref_mut(),
+            audio::Tone {
+                frequency: CHIME.frequency * 2f32.powf(semitones / 12.0),
+                ..CHIME
+            },
+            PlaybackSettings {
+                volume: bevy::audio::Volume::Relative(VolumeLevel::new(0.3)),
+                ..PlaybackSettings::ONCE
+            },
+        );
+    }
+
+    text.sections[15].value = score.0.to_string();
+}
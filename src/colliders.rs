@@ -0,0 +1,180 @@
+//! Wireframes of the colliders with a label of their shape and size, to check that they
+//! match what is rendered.
+
+use bevy::{prelude::*, utils::HashSet};
+use bevy_rapier3d::prelude::{Collider, ColliderView};
+
+use crate::{input::InputBindings, units::HudUnits};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ColliderDebug {
+    pub enabled: bool,
+    pub color: Color,
+}
+
+impl Default for ColliderDebug {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: Color::CYAN,
+        }
+    }
+}
+
+/// The label of the collider on the entity it points to.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ColliderLabel(Entity);
+
+/// The size of the box around the collider, in its local frame.
+fn bounding_size(collider: &Collider) -> Vec3 {
+    collider.raw.compute_local_aabb().extents().into()
+}
+
+fn size_text(size: Vec3, length: &impl Fn(f64) -> String) -> String {
+    format!(
+        "{} x {} x {}",
+        length(size.x.into()),
+        length(size.y.into()),
+        length(size.z.into())
+    )
+}
+
+/// What the collider is and how big, with `length` formatting the distances.
+/// Balls and cuboids have their own dimensions, everything else is shown with its bounds.
+pub fn shape_label(collider: &Collider, length: impl Fn(f64) -> String) -> String {
+    match collider.as_typed_shape() {
+        ColliderView::Ball(ball) => format!("Ball r={}", length(ball.radius().into())),
+        ColliderView::Cuboid(cuboid) => {
+            format!("Cuboid {}", size_text(cuboid.half_extents() * 2.0, &length))
+        }
+        _ => format!(
+            "{:?} bounds {}",
+            collider.raw.shape_type(),
+            size_text(bounding_size(collider), &length)
+        ),
+    }
+}
+
+pub fn toggle_collider_debug(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut debug: ResMut<ColliderDebug>,
+) {
+    if keyboard_input.just_pressed(bindings.collider_debug) {
+        debug.enabled = !debug.enabled;
+    }
+}
+
+/// Balls are drawn as spheres, everything else as its bounding box.
+pub fn draw_collider_shapes(
+    debug: Res<ColliderDebug>,
+    collider_query: Query<(&Collider, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    if !debug.enabled {
+        return;
+    }
+    for (collider, transform) in &collider_query {
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        match collider.as_typed_shape() {
+            ColliderView::Ball(ball) => {
+                gizmos.sphere(translation, rotation, ball.radius(), debug.color);
+            }
+            _ => {
+                let aabb = collider.raw.compute_local_aabb();
+                let center = Vec3::from(aabb.center());
+                gizmos.cuboid(
+                    Transform {
+                        translation: translation + rotation * center,
+                        rotation,
+                        scale: bounding_size(collider),
+                    },
+                    debug.color,
+                );
+            }
+        }
+    }
+}
+
+/// Keeps one label per collider while the debug view is on, next to the collider on screen.
+pub fn update_collider_labels(
+    mut commands: Commands,
+    debug: Res<ColliderDebug>,
+    units: HudUnits,
+    collider_query: Query<(Entity, &Collider, &GlobalTransform)>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut label_query: Query<(Entity, &ColliderLabel, &mut Text, &mut Style)>,
+) {
+    let mut labelled = HashSet::new();
+    let camera = camera_query.iter().find(|(camera, _)| camera.is_active);
+    for (label_entity, &ColliderLabel(collider_entity), mut text, mut style) in &mut label_query {
+        let Some((_, collider, transform)) = collider_query
+            .get(collider_entity)
+            .ok()
+            .filter(|_| debug.enabled)
+        else {
+            commands.entity(label_entity).despawn();
+            continue;
+        };
+        labelled.insert(collider_entity);
+
+        text.sections[0].value = shape_label(collider, |d| units.distance(d));
+        let on_screen = camera.and_then(|(camera, camera_transform)| {
+            camera.world_to_viewport(camera_transform, transform.translation())
+        });
+        match on_screen {
+            Some(screen) => {
+                style.display = Display::Flex;
+                style.left = Val::Px(screen.x + 6.0);
+                style.top = Val::Px(screen.y - 8.0);
+            }
+            None => style.display = Display::None,
+        }
+    }
+
+    if !debug.enabled {
+        return;
+    }
+    for (entity, _, _) in &collider_query {
+        if !labelled.contains(&entity) {
+            // placed on the screen by the next run
+            commands.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: debug.color,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    display: Display::None,
+                    ..default()
+                }),
+                ColliderLabel(entity),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_rapier3d::prelude::Collider;
+
+    use super::shape_label;
+
+    #[test]
+    fn labels_the_shape_and_size() {
+        let length = |d: f64| format!("{d:.1}");
+        assert_eq!(shape_label(&Collider::ball(2.0), length), "Ball r=2.0");
+        assert_eq!(
+            shape_label(&Collider::cuboid(0.5, 2.0, 0.5), length),
+            "Cuboid 1.0 x 4.0 x 1.0"
+        );
+        assert_eq!(
+            shape_label(&Collider::cylinder(1.0, 0.5), length),
+            "Cylinder bounds 1.0 x 2.0 x 1.0"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Measuring how much an orbit decays with each revolution.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    orbit::Orbit,
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    units::HudUnits,
+    GravityAttractor, OrbitText, Planet, Spaceship,
+};
+
+/// The orbit at the last periapsis passes.
+#[derive(Resource, Default)]
+pub struct OrbitDecay {
+    approaching: bool,
+    last_passage: Option<Orbit>,
+    /// Change in periapsis and apoapsis since the previous pass.
+    pub last_change: Option<(f64, f64)>,
+    pub orbits: u32,
+}
+
+/// Detects periapsis passages by the radial velocity turning from inbound to outbound.
+pub fn track_orbit_decay(
+    units: HudUnits,
+    mut decay: ResMut<OrbitDecay>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    reference: Res<ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor, &Planet), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+) {
+    let (ship_transform, v) = query.single();
+    let Some((body, body_transform, gravity, planet)) = reference_attractor(
+        &reference,
+        &body_query,
+        ship_transform.translation,
+        |(_, t, g, _)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+
+    let pos = ship_transform.translation - body_transform.translation;
+    let vel = v.linvel - velocities.of(body);
+    let approaching = pos.dot(vel) < 0.0;
+
+    if decay.approaching && !approaching {
+        let orbit = Orbit::from_pos_dir_3d(gravity.mass, pos.as_dvec3(), vel.as_dvec3());
+        if let Some(last) = decay.last_passage {
+            decay.last_change = Some((
+                orbit.periapsis() - last.periapsis(),
+                orbit.apoapsis() - last.apoapsis(),
+            ));
+            decay.orbits += 1;
+        }
+        decay.last_passage = Some(orbit);
+    }
+    decay.approaching = approaching;
+
+    let mut text = text_query.single_mut();
+    text.sections[9].value = match (decay.last_change, decay.last_passage) {
+        (Some((pe, ap)), Some(orbit)) => format!(
+            "Pe {}, Ap {} per orbit ({} orbits, Pe altitude {})",
+            units.distance_change(pe),
+            units.distance_change(ap),
+            decay.orbits,
+            units.distance(orbit.periapsis() - planet.radius)
+        ),
+        _ => "-".to_owned(),
+    };
+}
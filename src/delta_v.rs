@@ -0,0 +1,127 @@
+//! An at-a-glance map of the delta-v to a few destinations from the current orbit.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    hud::{self, HudPanel},
+    orbit::{self, Orbit},
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    units::HudUnits,
+    GravityAttractor, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct DeltaVMap {
+    pub enabled: bool,
+    /// The higher circular orbit is this many times the current semi major axis.
+    pub higher_orbit_factor: f64,
+}
+
+impl Default for DeltaVMap {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            higher_orbit_factor: 2.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct DeltaVText;
+
+/// The burn that makes the orbit circular right where the ship is at `pos` relative to the
+/// attractor, moving with `vel`. It's not just prograde, as the radial velocity has to go too.
+pub fn circularize_here_dv(m: f64, pos: Vec3, vel: Vec3) -> f32 {
+    let up = pos.normalize_or_zero();
+    let horizontal = (vel - up * vel.dot(up)).normalize_or_zero();
+    let circular_speed = (orbit::G * m / f64::from(pos.length())).sqrt() as f32;
+    (horizontal * circular_speed - vel).length()
+}
+
+pub fn spawn_delta_v_panel(mut commands: Commands, map: Res<DeltaVMap>) {
+    if map.enabled {
+        hud::spawn_panel(
+            &mut commands,
+            HudPanel::DeltaV,
+            &[
+                "Circularize here",
+                "Circularize at Ap",
+                "Higher orbit",
+                "Escape",
+            ],
+            DeltaVText,
+        );
+    }
+}
+
+pub fn update_delta_v_map(
+    units: HudUnits,
+    map: Res<DeltaVMap>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    reference: Res<ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    mut text_query: Query<&mut Text, With<DeltaVText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let Ok((transform, velocity)) = query.get_single() else {
+        return;
+    };
+    let Some((body, body_transform, gravity)) = reference_attractor(
+        &reference,
+        &body_query,
+        transform.translation,
+        |(_, t, g)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+
+    let m = gravity.mass;
+    let pos = transform.translation - body_transform.translation;
+    let vel = velocity.linvel - velocities.of(body);
+    let orbit = Orbit::from_pos_dir_3d(m, pos.as_dvec3(), vel.as_dvec3());
+
+    text.sections[1].value = units.speed_change(circularize_here_dv(m, pos, vel).into());
+    if !orbit.is_closed() {
+        for section in [3, 5] {
+            text.sections[section].value = "-".to_owned();
+        }
+    } else {
+        text.sections[3].value = units.speed_change(orbit::circularization_dv(m, &orbit));
+        let radius = orbit.semi_major_axis * map.higher_orbit_factor;
+        let (transfer, circularize) = orbit::hohmann_dv(m, &orbit, radius);
+        text.sections[5].value = format!(
+            "{} {} to {}",
+            units.speed_change(transfer),
+            units.speed_change(circularize),
+            units.distance(radius)
+        );
+    }
+    text.sections[7].value = units.speed_change(orbit::escape_dv(m, &orbit));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::circularize_here_dv;
+    use crate::orbit::G;
+
+    #[test]
+    fn circular_orbit_needs_nothing() {
+        let m = 1.0e17;
+        let r = 1100.0;
+        let speed = (G * m / r).sqrt() as f32;
+        let pos = Vec3::new(r as f32, 0.0, 0.0);
+
+        let dv = circularize_here_dv(m, pos, Vec3::new(0.0, 0.0, -speed));
+        assert!(dv < 1e-3, "{dv}");
+
+        // climbing at the circular speed, only the climb has to go
+        let dv = circularize_here_dv(m, pos, Vec3::new(3.0, 0.0, -speed));
+        assert!((dv - 3.0).abs() < 1e-3, "{dv}");
+    }
+}
@@ -0,0 +1,182 @@
+//! Lining up the docking ports for docking with the selected ship: both port axes, the
+//! corridor to approach the target's port through, and how far off the approach is.
+
+use bevy::prelude::*;
+
+use crate::{hud::ShipStatusText, inspect::SelectedBody, Spaceship};
+
+/// Where on the ship the docking port is and which way it faces, in the local frame.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct DockingPort {
+    pub offset: Vec3,
+    pub axis: Vec3,
+}
+
+impl DockingPort {
+    /// The position and axis in the world.
+    pub fn world(&self, transform: &Transform) -> (Vec3, Vec3) {
+        (
+            transform.transform_point(self.offset),
+            transform.rotation * self.axis.normalize_or_zero(),
+        )
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct DockingAid {
+    pub enabled: bool,
+    /// How far out from the target's port the corridor goes.
+    pub corridor_length: f32,
+    pub corridor_radius: f32,
+    /// Up to this angle between the ports the alignment counts as good, in degrees.
+    pub max_misalignment: f32,
+}
+
+impl Default for DockingAid {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            corridor_length: 30.0,
+            corridor_radius: 0.5,
+            max_misalignment: 5.0,
+        }
+    }
+}
+
+/// How the own port is positioned relative to the target's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DockingAlignment {
+    /// The angle between the own port axis and the direction it has to face to mate, which is
+    /// against the target's axis, in degrees.
+    pub misalignment: f32,
+    /// How far out along the target's axis the own port is, negative behind the port.
+    pub range: f32,
+    /// How far the own port is from the center line of the corridor.
+    pub offset: f32,
+}
+
+impl DockingAlignment {
+    /// Whether the own port is in the corridor and facing the target's port.
+    pub fn in_corridor(&self, aid: &DockingAid) -> bool {
+        self.range >= 0.0
+            && self.range <= aid.corridor_length
+            && self.offset <= aid.corridor_radius
+            && self.misalignment <= aid.max_misalignment
+    }
+}
+
+/// The alignment of the own port at `own` with axis `own_axis` to the target's port.
+/// Both axes are normalized.
+pub fn docking_alignment(
+    (own, own_axis): (Vec3, Vec3),
+    (target, target_axis): (Vec3, Vec3),
+) -> DockingAlignment {
+    let to_own = own - target;
+    let range = to_own.dot(target_axis);
+    DockingAlignment {
+        misalignment: own_axis
+            .dot(-target_axis)
+            .clamp(-1.0, 1.0)
+            .acos()
+            .to_degrees(),
+        range,
+        offset: (to_own - target_axis * range).length(),
+    }
+}
+
+pub fn draw_docking_aid(
+    aid: Res<DockingAid>,
+    selected: Res<SelectedBody>,
+    ship_query: Query<(Entity, &Transform, &DockingPort), With<Spaceship>>,
+    target_query: Query<(&Transform, &DockingPort)>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    text.sections[13].value = "-".to_owned();
+    if !aid.enabled {
+        return;
+    }
+    let Ok((ship, ship_transform, ship_port)) = ship_query.get_single() else {
+        return;
+    };
+    let Some((target_transform, target_port)) = selected
+        .0
+        .filter(|&target| target != ship)
+        .and_then(|target| target_query.get(target).ok())
+    else {
+        return;
+    };
+
+    let (own, own_axis) = ship_port.world(ship_transform);
+    let (target, target_axis) = target_port.world(target_transform);
+    let alignment = docking_alignment((own, own_axis), (target, target_axis));
+
+    let color = if alignment.in_corridor(&aid) {
+        Color::LIME_GREEN
+    } else {
+        Color::ORANGE
+    };
+    gizmos.ray(own, own_axis * 2.0, Color::CYAN);
+    gizmos.ray(target, target_axis * 2.0, color);
+
+    let end = target + target_axis * aid.corridor_length;
+    gizmos.line(target, end, color * 0.6);
+    let side = target_axis.any_orthonormal_vector() * aid.corridor_radius;
+    let other_side = target_axis.cross(side);
+    for side in [side, -side, other_side, -other_side] {
+        gizmos.line(target + side, end + side, color * 0.4);
+    }
+    for i in 0..=3 {
+        let center = target + target_axis * (aid.corridor_length * i as f32 / 3.0);
+        gizmos.circle(center, target_axis, aid.corridor_radius, color * 0.6);
+    }
+
+    text.sections[13].value = format!(
+        "{:.1}° off, {:.2} from the axis, {:.1} out",
+        alignment.misalignment, alignment.offset, alignment.range
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{docking_alignment, DockingAid, DockingPort};
+
+    #[test]
+    fn aligned_in_front_of_the_port() {
+        let aid = DockingAid::default();
+        let target = (Vec3::new(0.0, 0.0, 0.0), Vec3::X);
+
+        let facing = docking_alignment((Vec3::new(10.0, 0.0, 0.0), -Vec3::X), target);
+        assert_eq!(facing.misalignment, 0.0);
+        assert_eq!(facing.range, 10.0);
+        assert_eq!(facing.offset, 0.0);
+        assert!(facing.in_corridor(&aid));
+
+        // same direction as the target's port, and off to the side
+        let wrong = docking_alignment((Vec3::new(10.0, 3.0, 4.0), Vec3::X), target);
+        assert_eq!(wrong.misalignment, 180.0);
+        assert_eq!(wrong.offset, 5.0);
+        assert!(!wrong.in_corridor(&aid));
+
+        let behind = docking_alignment((Vec3::new(-10.0, 0.0, 0.0), -Vec3::X), target);
+        assert!(behind.range < 0.0 && !behind.in_corridor(&aid));
+    }
+
+    #[test]
+    fn port_in_the_world() {
+        let port = DockingPort {
+            offset: Vec3::new(0.0, 1.0, 0.0),
+            axis: Vec3::Y,
+        };
+        let transform = Transform::from_xyz(5.0, 0.0, 0.0)
+            .with_rotation(Quat::from_rotation_z(-std::f32::consts::FRAC_PI_2));
+        let (pos, axis) = port.world(&transform);
+        assert!(pos.distance(Vec3::new(6.0, 0.0, 0.0)) < 1e-6, "{pos}");
+        assert!(axis.distance(Vec3::X) < 1e-6, "{axis}");
+    }
+}
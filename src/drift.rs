@@ -0,0 +1,124 @@
+//! Locking the analytic orbit at some moment, to see how far the simulated ship
+//! drifts away from where Kepler says it should be.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    orbit::OrbitalElements,
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    units::HudUnits,
+    GravityAttractor, OrbitText, Spaceship,
+};
+
+/// The orbit at the moment the prediction was locked.
+#[derive(Debug, Clone)]
+pub struct LockedPrediction {
+    pub elements: OrbitalElements,
+    pub mass: f64,
+    /// Where the attractor was. They don't move, so it's fine to keep it fixed.
+    pub attractor: Vec3,
+    /// Elapsed simulation time in seconds.
+    pub start: f64,
+    /// The actual path of the ship since then.
+    pub trail: VecDeque<Vec3>,
+}
+
+#[derive(Resource, Default, Debug, Clone)]
+pub struct PredictionLock(pub Option<LockedPrediction>);
+
+/// Only the latest points of the trail are kept, to not grow forever.
+const MAX_TRAIL_POINTS: usize = 10_000;
+/// Segments of the drawn orbit.
+const ORBIT_SEGMENTS: usize = 256;
+
+/// F12 locks the current orbit, or unlocks it again.
+pub fn toggle_prediction_lock(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut lock: ResMut<PredictionLock>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    reference: Res<ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F12) {
+        return;
+    }
+    if lock.0.take().is_some() {
+        return;
+    }
+
+    let (transform, v) = query.single();
+    let Some((body, body_transform, gravity)) = reference_attractor(
+        &reference,
+        &body_query,
+        transform.translation,
+        |(_, t, g)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+
+    let elements = OrbitalElements::from_state(
+        gravity.mass,
+        (transform.translation - body_transform.translation).as_dvec3(),
+        (v.linvel - velocities.of(body)).as_dvec3(),
+    );
+    if !(elements.semi_major_axis > 0.0 && elements.eccentricity < 1.0) {
+        info!("Can only lock the prediction of closed orbits");
+        return;
+    }
+
+    lock.0 = Some(LockedPrediction {
+        elements,
+        mass: gravity.mass,
+        attractor: body_transform.translation,
+        start: time.elapsed_seconds_f64(),
+        trail: VecDeque::new(),
+    });
+}
+
+/// Draws the locked orbit and the actual path, and shows how far apart the
+/// ship is from its predicted position.
+pub fn track_prediction_drift(
+    units: HudUnits,
+    time: Res<Time>,
+    mut lock: ResMut<PredictionLock>,
+    query: Query<&Transform, With<Spaceship>>,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+    mut gizmos: Gizmos,
+) {
+    let mut text = text_query.single_mut();
+    let Some(locked) = &mut lock.0 else {
+        text.sections[13].value = "-".to_owned();
+        return;
+    };
+
+    let actual = query.single().translation;
+    if locked.trail.len() >= MAX_TRAIL_POINTS {
+        locked.trail.pop_front();
+    }
+    locked.trail.push_back(actual);
+
+    let orbit = (0..=ORBIT_SEGMENTS).map(|i| {
+        let nu = std::f64::consts::TAU * i as f64 / ORBIT_SEGMENTS as f64;
+        let elements = OrbitalElements {
+            true_anomaly: nu,
+            ..locked.elements
+        };
+        locked.attractor + elements.to_state(locked.mass).0.as_vec3()
+    });
+    gizmos.linestrip(orbit, Color::YELLOW);
+    gizmos.linestrip(locked.trail.iter().copied(), Color::WHITE);
+
+    let dt = time.elapsed_seconds_f64() - locked.start;
+    let predicted = locked.attractor + locked.elements.position_at_time(locked.mass, dt).as_vec3();
+    gizmos.line(predicted, actual, Color::RED);
+
+    text.sections[13].value = format!(
+        "{} after {dt:.1}s",
+        units.distance(predicted.distance(actual) as f64)
+    );
+}
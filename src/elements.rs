@@ -0,0 +1,92 @@
+//! Exporting and importing the ship's orbit as classical orbital elements, see [`OrbitalElements`].
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use glam::DVec3;
+
+use crate::{
+    dominant_attractor, orbit::OrbitalElements, reference::BodyVelocities, GravityAttractor,
+    Spaceship,
+};
+
+/// Elements passed with `--orbit="<elements>"` that the ship starts on.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct ImportedOrbit(pub Option<OrbitalElements>);
+
+impl ImportedOrbit {
+    pub fn from_args() -> Self {
+        let elements = std::env::args().find_map(|arg| {
+            let elements = arg.strip_prefix("--orbit=")?;
+            match elements.parse() {
+                Ok(elements) => Some(elements),
+                Err(err) => {
+                    error!("Invalid orbital elements `{elements}`: {err}");
+                    None
+                }
+            }
+        });
+        ImportedOrbit(elements)
+    }
+}
+
+fn ship_elements(
+    ship: (&Transform, &Velocity),
+    attractor: (&Transform, &GravityAttractor),
+    attractor_velocity: Vec3,
+) -> OrbitalElements {
+    let pos = ship.0.translation - attractor.0.translation;
+    let vel = ship.1.linvel - attractor_velocity;
+    OrbitalElements::from_state(attractor.1.mass, pos.as_dvec3(), vel.as_dvec3())
+}
+
+/// Puts the ship on the imported orbit around the dominant attractor.
+pub fn import_orbital_elements(
+    imported: Res<ImportedOrbit>,
+    mut query: Query<(&mut Transform, &mut Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+) {
+    let Some(elements) = imported.0 else {
+        return;
+    };
+    let Ok((mut transform, mut velocity)) = query.get_single_mut() else {
+        warn!("No ship to put on the imported orbit");
+        return;
+    };
+    let Some((body, body_transform, body_gravity)) =
+        dominant_attractor(&body_query, transform.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let (pos, v): (DVec3, DVec3) = elements.to_state(body_gravity.mass);
+    transform.translation = body_transform.translation + pos.as_vec3();
+    velocity.linvel = velocities.of(body) + v.as_vec3();
+    info!("Starting on orbit {elements}");
+}
+
+/// F6 logs the ship's current orbit in the format accepted by `--orbit`.
+pub fn export_orbital_elements(
+    keyboard_input: Res<Input<KeyCode>>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F6) {
+        return;
+    }
+
+    let ship = query.single();
+    let Some((body, body_transform, gravity)) =
+        dominant_attractor(&body_query, ship.0.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let elements = ship_elements(ship, (body_transform, gravity), velocities.of(body));
+    info!("Orbital elements: {elements}");
+}
@@ -0,0 +1,165 @@
+//! The specific orbital energy and which way it's going, so it's visible at a glance whether a
+//! burn or the atmosphere raises or lowers the orbit.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    inspect::velocity_of,
+    orbit,
+    rails::OnRails,
+    reference::{reference_attractor, ReferenceBody},
+    GravityAttractor, OrbitText, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct EnergyTrend {
+    pub enabled: bool,
+    /// How many seconds the rate is averaged over. The energy wobbles from frame to frame
+    /// with the integration, so the raw difference jumps around too much to read.
+    pub smoothing: f64,
+    /// Rates smaller than this show as steady.
+    pub steady_below: f64,
+}
+
+impl Default for EnergyTrend {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            smoothing: 1.0,
+            steady_below: 1e-3,
+        }
+    }
+}
+
+impl EnergyTrend {
+    /// `rate` moved towards the rate between two energies `dt` seconds apart. This is an
+    /// exponential moving average, weighted by time so that it doesn't depend on the frame rate.
+    pub fn smooth(&self, rate: f64, energy_delta: f64, dt: f64) -> f64 {
+        let alpha = 1.0 - (-dt / self.smoothing).exp();
+        rate + alpha * (energy_delta / dt - rate)
+    }
+
+    /// Which way the energy goes, in ASCII because the default font has no arrows.
+    fn arrow(&self, rate: f64) -> &'static str {
+        if rate.abs() < self.steady_below {
+            "="
+        } else if rate > 0.0 {
+            "^"
+        } else {
+            "v"
+        }
+    }
+}
+
+/// The energy of the last frame and the smoothed rate, for one reference body.
+#[derive(Default)]
+pub struct EnergyHistory {
+    last: Option<(Entity, f64)>,
+    rate: f64,
+}
+
+pub fn update_energy_trend(
+    trend: Res<EnergyTrend>,
+    time: Res<Time>,
+    reference: Res<ReferenceBody>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<
+        (
+            Entity,
+            &Transform,
+            &GravityAttractor,
+            Option<&Velocity>,
+            Option<&OnRails>,
+        ),
+        Without<Spaceship>,
+    >,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+    mut history: Local<EnergyHistory>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let Ok((ship, velocity)) = query.get_single() else {
+        return;
+    };
+    let (true, Some((body, body_transform, gravity, body_velocity, body_rails))) = (
+        trend.enabled,
+        reference_attractor(
+            &reference,
+            &body_query,
+            ship.translation,
+            |(_, t, g, ..)| (t.translation, g.mass),
+        ),
+    ) else {
+        *history = default();
+        text.sections[25].value = "-".to_owned();
+        return;
+    };
+
+    let r = (ship.translation - body_transform.translation).as_dvec3();
+    let parent_mass = body_rails
+        .and_then(|rails| body_query.get(rails.parent).ok())
+        .map_or(0.0, |(_, _, g, ..)| g.mass);
+    let body_velocity = velocity_of(
+        body_velocity,
+        body_rails,
+        parent_mass,
+        time.elapsed_seconds_f64(),
+    );
+    let v = (velocity.linvel - body_velocity).as_dvec3();
+    let energy = orbit::specific_energy(gravity.mass, r.length(), v.length());
+
+    let dt = time.delta_seconds_f64();
+    match history.last {
+        // the energy around another body has nothing to do with the one before
+        Some((last_body, _)) if last_body != body => history.rate = 0.0,
+        Some((_, last)) if dt > 0.0 => {
+            history.rate = trend.smooth(history.rate, energy - last, dt);
+        }
+        _ => {}
+    }
+    history.last = Some((body, energy));
+
+    text.sections[25].value = format!(
+        "{energy:.1} {} {:+.3}/s",
+        trend.arrow(history.rate),
+        history.rate
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnergyTrend;
+
+    #[test]
+    fn smooths_out_the_noise() {
+        let trend = EnergyTrend::default();
+        // rising by 2 per second, with the frames alternating between too much and too little
+        let mut rate = 0.0;
+        for frame in 0..600 {
+            let noise = if frame % 2 == 0 { 0.02 } else { -0.02 };
+            rate = trend.smooth(rate, 2.0 / 60.0 + noise, 1.0 / 60.0);
+        }
+        assert!((rate - 2.0).abs() < 0.05, "{rate}");
+        assert_eq!(trend.arrow(rate), "^");
+        assert_eq!(trend.arrow(-rate), "v");
+        assert_eq!(trend.arrow(0.0), "=");
+    }
+
+    #[test]
+    fn independent_of_the_frame_rate() {
+        let trend = EnergyTrend::default();
+        let after = |fps: u32| {
+            let dt = 1.0 / fps as f64;
+            (0..fps / 2).fold(0.0, |rate, _| trend.smooth(rate, -3.0 * dt, dt))
+        };
+        // half a second in, the same way towards -3 no matter how many frames that took
+        let (slow, fast) = (after(30), after(240));
+        assert!((slow - fast).abs() < 1e-9, "{slow} == {fast}");
+        assert!(
+            (slow + 3.0 * (1.0 - (-0.5f64).exp())).abs() < 1e-9,
+            "{slow}"
+        );
+    }
+}
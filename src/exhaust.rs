@@ -0,0 +1,204 @@
+//! Exhaust splashing against the ground when thrusting close to it, as rings of dust
+//! spreading out from below the engine.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    autopilot::AutopilotForce, forces::ExternalForceSet, Spaceship, ThrusterForce, Thrusters,
+    ENGINE_OFFSET,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ExhaustSplash {
+    /// Above this distance from the engine to the ground, there's no dust.
+    pub max_altitude: f32,
+    /// Seconds between two rings.
+    pub interval: f32,
+    /// Seconds until a ring has faded out.
+    pub lifetime: f32,
+    /// The radius a ring at full strength grows to.
+    pub max_radius: f32,
+}
+
+impl Default for ExhaustSplash {
+    fn default() -> Self {
+        Self {
+            max_altitude: 30.0,
+            interval: 0.1,
+            lifetime: 1.0,
+            max_radius: 8.0,
+        }
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct DustRing {
+    pub age: f32,
+    pub lifetime: f32,
+    pub radius: f32,
+}
+
+/// How strong the splash is at `altitude` with the engine at `throttle` (0 to 1).
+/// It fades out linearly up to `max_altitude`.
+pub fn splash_strength(throttle: f32, altitude: f32, max_altitude: f32) -> f32 {
+    (throttle.clamp(0.0, 1.0) * (1.0 - altitude / max_altitude)).max(0.0)
+}
+
+/// Shared by all rings, only the material differs so that they can fade separately.
+#[derive(Resource)]
+pub struct DustRingMesh(Handle<Mesh>);
+
+impl FromWorld for DustRingMesh {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+        DustRingMesh(
+            meshes.add(
+                shape::Torus {
+                    radius: 1.0,
+                    ring_radius: 0.15,
+                    subdivisions_segments: 32,
+                    subdivisions_sides: 8,
+                }
+                .into(),
+            ),
+        )
+    }
+}
+
+pub fn spawn_exhaust_splash(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<ExhaustSplash>,
+    ring_mesh: Res<DustRingMesh>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    rapier_context: Res<RapierContext>,
+    query: Query<(Entity, &Transform, &Thrusters, &ExternalForceSet), With<Spaceship>>,
+    mut since_last: Local<f32>,
+) {
+    *since_last += time.delta_seconds();
+    if *since_last < config.interval {
+        return;
+    }
+
+    for (ship, transform, thrusters, forces) in &query {
+        let thrust = forces.get::<ThrusterForce>().force + forces.get::<AutopilotForce>().force;
+        let Some(exhaust_dir) = (-thrust).try_normalize() else {
+            continue;
+        };
+
+        let engine = transform.transform_point(ENGINE_OFFSET);
+        let filter = QueryFilter::default().exclude_collider(ship);
+        let Some((_, hit)) = rapier_context.cast_ray_and_get_normal(
+            engine,
+            exhaust_dir,
+            config.max_altitude,
+            true,
+            filter,
+        ) else {
+            continue;
+        };
+
+        let throttle = thrust.length() / thrusters.max_force();
+        let strength = splash_strength(throttle, hit.toi, config.max_altitude);
+        if strength <= 0.0 {
+            continue;
+        }
+
+        let material = materials.add(StandardMaterial {
+            base_color: Color::rgba(0.6, 0.5, 0.4, strength),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        });
+        commands.spawn((
+            DustRing {
+                age: 0.0,
+                lifetime: config.lifetime,
+                radius: config.max_radius * strength,
+            },
+            PbrBundle {
+                mesh: ring_mesh.0.clone(),
+                material,
+                transform: Transform::from_translation(hit.point)
+                    .with_rotation(Quat::from_rotation_arc(Vec3::Y, hit.normal))
+                    .with_scale(Vec3::ZERO),
+                ..default()
+            },
+        ));
+        *since_last = 0.0;
+    }
+}
+
+/// Spreads the rings out while they fade, and despawns them once they're gone.
+pub fn update_dust_rings(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut query: Query<(
+        Entity,
+        &mut DustRing,
+        &mut Transform,
+        &Handle<StandardMaterial>,
+    )>,
+) {
+    for (entity, mut ring, mut transform, material) in &mut query {
+        ring.age += time.delta_seconds();
+        if ring.age >= ring.lifetime {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let progress = ring.age / ring.lifetime;
+
+        let radius = ring.radius * progress.sqrt();
+        // flattened, as it's dust spreading along the ground
+        transform.scale = Vec3::new(radius, radius * 0.2, radius);
+
+        if let Some(material) = materials.get_mut(material) {
+            let alpha = material.base_color.a();
+            material.base_color.set_a(alpha.min(1.0 - progress));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{splash_strength, DustRing};
+
+    #[test]
+    fn fades_with_altitude() {
+        assert_eq!(splash_strength(1.0, 0.0, 30.0), 1.0);
+        assert!(splash_strength(1.0, 20.0, 30.0) < splash_strength(1.0, 10.0, 30.0));
+        assert!(splash_strength(0.5, 10.0, 30.0) < splash_strength(1.0, 10.0, 30.0));
+        assert_eq!(splash_strength(1.0, 40.0, 30.0), 0.0);
+    }
+
+    #[test]
+    fn rings_despawn() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_asset::<StandardMaterial>()
+            .add_systems(Update, super::update_dust_rings);
+
+        let ring = app
+            .world
+            .spawn((
+                DustRing {
+                    age: 0.0,
+                    lifetime: 0.0,
+                    radius: 1.0,
+                },
+                Transform::default(),
+                Handle::<StandardMaterial>::default(),
+            ))
+            .id();
+
+        app.update();
+        assert!(
+            app.world.get_entity(ring).is_none(),
+            "ring wasn't despawned"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! A soft fill light for every planet, from the side of the camera, so that their night side
+//! isn't pitch black against the background wherever the sun is.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use crate::Planet;
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PlanetFillLight {
+    pub enabled: bool,
+    /// The illuminance in lux at the point of the surface closest to the light.
+    /// The intensity scales with the size of the planet, so they all look alike.
+    pub illuminance: f32,
+    pub color: Color,
+}
+
+impl Default for PlanetFillLight {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            illuminance: 20.0,
+            color: Color::rgb(0.6, 0.7, 1.0),
+        }
+    }
+}
+
+/// How many planet radii from its center the light sits.
+const LIGHT_DISTANCE: f32 = 3.0;
+
+/// The light for `planet`, kept outside of it so that it doesn't turn with the planet.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FillLight {
+    planet: Entity,
+}
+
+/// The intensity in lumens to get `illuminance` lux on the surface closest to the light,
+/// with the light `distance` away from the center of a planet with `radius`.
+pub fn fill_intensity(illuminance: f32, radius: f32, distance: f32) -> f32 {
+    let gap = distance - radius;
+    illuminance * 4.0 * PI * gap * gap
+}
+
+pub fn spawn_fill_lights(
+    mut commands: Commands,
+    planet_query: Query<(Entity, &Planet), Added<Planet>>,
+) {
+    for (planet, planet_info) in &planet_query {
+        let radius = planet_info.radius as f32;
+        commands.spawn((
+            PointLightBundle {
+                point_light: PointLight {
+                    // reaching the far edge of the planet as seen from the light
+                    range: radius * LIGHT_DISTANCE * 2.0,
+                    shadows_enabled: false,
+                    ..default()
+                },
+                ..default()
+            },
+            FillLight { planet },
+        ));
+    }
+}
+
+/// Keeps each light between its planet and the camera, and despawns it with the planet.
+pub fn place_fill_lights(
+    mut commands: Commands,
+    settings: Res<PlanetFillLight>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    planet_query: Query<(&Transform, &Planet)>,
+    mut light_query: Query<
+        (
+            Entity,
+            &FillLight,
+            &mut Transform,
+            &mut PointLight,
+            &mut Visibility,
+        ),
+        Without<Planet>,
+    >,
+) {
+    let camera = camera_query
+        .iter()
+        .find(|(camera, _)| camera.is_active)
+        .map(|(_, transform)| transform.translation());
+
+    for (entity, light, mut transform, mut point_light, mut visibility) in &mut light_query {
+        let Ok((planet_transform, planet)) = planet_query.get(light.planet) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        let towards_camera = camera
+            .and_then(|camera| (camera - planet_transform.translation).try_normalize())
+            .unwrap_or(Vec3::Y);
+        let radius = planet.radius as f32;
+        let distance = radius * LIGHT_DISTANCE;
+        transform.translation = planet_transform.translation + towards_camera * distance;
+
+        point_light.intensity = fill_intensity(settings.illuminance, radius, distance);
+        point_light.color = settings.color;
+        *visibility = if settings.enabled {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::fill_intensity;
+
+    #[test]
+    fn intensity_grows_with_the_size() {
+        let small = fill_intensity(20.0, 1000.0, 3000.0);
+        assert!((small / (4.0 * PI * 2000.0 * 2000.0) - 20.0).abs() < 1e-3);
+
+        // the same light on the surface of a planet ten times as big
+        let big = fill_intensity(20.0, 10_000.0, 30_000.0);
+        assert!((big / small - 100.0).abs() < 1e-3, "{}", big / small);
+    }
+}
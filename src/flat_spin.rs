@@ -0,0 +1,166 @@
+//! An assist that damps the rotation around the minor principal axes, so that a tumbling
+//! ship ends up in a clean flat spin around its major axis instead of stopping.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{forces::ExternalForceSet, input::InputBindings, Spaceship};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct FlatSpin {
+    pub enabled: bool,
+    /// How fast the rotation around the other axes dies down, per second.
+    pub damping: f32,
+}
+
+impl Default for FlatSpin {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            damping: 0.5,
+        }
+    }
+}
+
+/// Marks the flat spin torque in the [`ExternalForceSet`].
+pub struct FlatSpinTorque;
+
+/// Moments this close to the largest one, relative to it, count as major too. A body that's
+/// symmetric around its long axis, like the ship, has two equal major moments, which only
+/// come out slightly different from the collider.
+pub const MAJOR_TOLERANCE: f32 = 1e-3;
+
+/// The torque decelerating the rotation around all but the major principal axes, for a body
+/// with the principal moments `principal_inertia` along the axes of `orientation`.
+pub fn flat_spin_torque(
+    orientation: Quat,
+    principal_inertia: Vec3,
+    angvel: Vec3,
+    damping: f32,
+) -> Vec3 {
+    let max = principal_inertia.max_element();
+    let mut off_axis = orientation.inverse() * angvel;
+    for axis in 0..3 {
+        if principal_inertia[axis] >= max * (1.0 - MAJOR_TOLERANCE) {
+            off_axis[axis] = 0.0;
+        }
+    }
+    orientation * (-damping * principal_inertia * off_axis)
+}
+
+pub fn toggle_flat_spin(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut flat_spin: ResMut<FlatSpin>,
+) {
+    if keyboard_input.just_pressed(bindings.flat_spin) {
+        flat_spin.enabled = !flat_spin.enabled;
+        info!(
+            "Flat spin assist {}",
+            if flat_spin.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+pub fn flat_spin_assist(
+    flat_spin: Res<FlatSpin>,
+    mut query: Query<
+        (
+            &mut ExternalForceSet,
+            &Transform,
+            &Velocity,
+            &ReadMassProperties,
+        ),
+        With<Spaceship>,
+    >,
+) {
+    for (mut forces, transform, velocity, mass_properties) in &mut query {
+        let torque = if flat_spin.enabled {
+            let props = mass_properties.0;
+            flat_spin_torque(
+                transform.rotation * props.principal_inertia_local_frame,
+                props.principal_inertia,
+                velocity.angvel,
+                flat_spin.damping,
+            )
+        } else {
+            Vec3::ZERO
+        };
+
+        forces.set::<FlatSpinTorque>(ExternalForce {
+            force: Vec3::ZERO,
+            torque,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::*;
+
+    use super::{flat_spin_torque, FlatSpin};
+    use crate::{headless, scenario::Scenario, Spaceship};
+
+    /// The tumbling ship after `ticks` with the assist on, and its principal moments.
+    fn tumble(ticks: u32) -> (headless::ShipState, ReadMassProperties) {
+        let mut app = headless::headless_app(Scenario::Tumble);
+        app.insert_resource(FlatSpin {
+            enabled: true,
+            ..default()
+        });
+        let state = headless::run_app(&mut app, &[], ticks);
+        let mass = *app
+            .world
+            .query_filtered::<&ReadMassProperties, With<Spaceship>>()
+            .single(&app.world);
+        (state, mass)
+    }
+
+    #[test]
+    fn damps_only_the_minor_axes() {
+        let (_, mass) = tumble(1);
+        let inertia = mass.0.principal_inertia;
+        let frame = mass.0.principal_inertia_local_frame;
+        let angvel = frame * Vec3::new(1.0, 0.5, -0.2);
+
+        let torque = frame.inverse() * flat_spin_torque(frame, inertia, angvel, 2.0);
+        let long = (0..3)
+            .min_by(|&a, &b| inertia[a].total_cmp(&inertia[b]))
+            .unwrap();
+        for axis in 0..3 {
+            let expected = if axis == long {
+                -2.0 * inertia[axis] * (frame.inverse() * angvel)[axis]
+            } else {
+                0.0
+            };
+            assert!((torque[axis] - expected).abs() < 1e-6, "{torque} {inertia}");
+        }
+
+        // the same, with the body turned
+        let orientation = Quat::from_rotation_y(0.7) * Quat::from_rotation_x(-1.2) * frame;
+        let turned = flat_spin_torque(
+            orientation,
+            inertia,
+            orientation * frame.inverse() * angvel,
+            2.0,
+        );
+        assert!(turned.abs_diff_eq(orientation * torque, 1e-6), "{turned}");
+    }
+
+    #[test]
+    fn tumbling_ship_ends_in_a_flat_spin() {
+        let (start, _) = tumble(1);
+        let (end, _) = tumble(60 * 20);
+
+        // around the long axis it stops, around the others it keeps spinning
+        let spin = end.rotation.inverse() * end.angular_velocity;
+        assert!(spin.y.abs() < 0.05, "{spin}");
+        let tumble = start.rotation.inverse() * start.angular_velocity;
+        let flat = Vec2::new(tumble.x, tumble.z).length();
+        assert!(
+            (Vec2::new(spin.x, spin.z).length() - flat).abs() < 0.05 * flat,
+            "{spin} from {tumble}"
+        );
+    }
+}
@@ -20,7 +20,7 @@ impl ExternalForceSet {
         self.forces.insert(TypeId::of::<T>(), force);
     }
 
-    fn combine(&self) -> ExternalForce {
+    pub fn combine(&self) -> ExternalForce {
         self.forces
             .values()
             .fold(ExternalForce::default(), |f1, &f2| f1 + f2)
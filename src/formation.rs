@@ -0,0 +1,144 @@
+//! Station keeping in formation with another ship.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{dominant_attractor, forces::ExternalForceSet, GravityAttractor};
+
+/// Holds this ship at `offset` from `leader`, given in the leader's LVLH frame:
+/// X points away from the attractor, Y along the track and Z along the orbit normal.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FormationFlight {
+    pub leader: Entity,
+    pub offset: Vec3,
+}
+
+/// Spring constant of the controller, per second squared.
+pub const STIFFNESS: f32 = 2.0;
+/// Damping of the controller, per second. Slightly above critical damping.
+pub const DAMPING: f32 = 3.0;
+/// The strongest acceleration the RCS thrusters can push the ship with.
+pub const MAX_RCS_ACCELERATION: f32 = 2.0;
+
+/// The LVLH basis with the radial, along-track and normal directions as its columns,
+/// for a body at `pos` relative to the attractor moving with `vel`.
+pub fn lvlh_basis(pos: Vec3, vel: Vec3) -> Mat3 {
+    let radial = pos.normalize_or_zero();
+    let normal = pos.cross(vel).normalize_or_zero();
+    let along_track = normal.cross(radial);
+    Mat3::from_cols(radial, along_track, normal)
+}
+
+/// The acceleration that moves the follower toward `offset` from the leader.
+/// All positions are relative to the attractor.
+pub fn formation_acceleration(
+    (leader_pos, leader_vel): (Vec3, Vec3),
+    (follower_pos, follower_vel): (Vec3, Vec3),
+    offset: Vec3,
+) -> Vec3 {
+    let offset = lvlh_basis(leader_pos, leader_vel) * offset;
+    // the frame rotates with the leader around the attractor
+    let angular_velocity = leader_pos.cross(leader_vel) / leader_pos.length_squared();
+
+    let target_pos = leader_pos + offset;
+    let target_vel = leader_vel + angular_velocity.cross(offset);
+
+    let acceleration =
+        STIFFNESS * (target_pos - follower_pos) + DAMPING * (target_vel - follower_vel);
+    acceleration.clamp_length_max(MAX_RCS_ACCELERATION)
+}
+
+pub fn formation_flight(
+    mut query: Query<(
+        &FormationFlight,
+        &mut ExternalForceSet,
+        &Transform,
+        &Velocity,
+        &ReadMassProperties,
+    )>,
+    leader_query: Query<(&Transform, &Velocity), Without<FormationFlight>>,
+    body_query: Query<(&Transform, &GravityAttractor)>,
+) {
+    struct FormationForce;
+
+    for (formation, mut forces, transform, v, mass) in &mut query {
+        let pos = transform.translation;
+        let attractor = dominant_attractor(&body_query, pos, |(t, g)| (t.translation, g.mass));
+
+        let (Ok((leader_transform, leader_v)), Some((body_transform, _))) =
+            (leader_query.get(formation.leader), attractor)
+        else {
+            forces.set::<FormationForce>(ExternalForce::default());
+            continue;
+        };
+
+        let body_pos = body_transform.translation;
+        let acceleration = formation_acceleration(
+            (leader_transform.translation - body_pos, leader_v.linvel),
+            (pos - body_pos, v.linvel),
+            formation.offset,
+        );
+
+        forces.set::<FormationForce>(ExternalForce {
+            force: acceleration * mass.0.mass,
+            torque: Vec3::ZERO,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use bevy_rapier3d::prelude::*;
+
+    use super::{formation_acceleration, lvlh_basis, FormationFlight};
+    use crate::{headless, scenario::Scenario, Spaceship};
+
+    #[test]
+    fn converges_to_offset() {
+        // the low orbit around the small planet of the orbit scenarios
+        let gm = 1.1e7;
+        let gravity = |pos: Vec3| -pos.normalize() * (gm / pos.length_squared());
+
+        let r = 1100.0;
+        let mut leader = (Vec3::new(r, 0.0, 0.0), Vec3::new(0.0, 0.0, (gm / r).sqrt()));
+        let mut follower = (leader.0 + Vec3::new(5.0, 3.0, -4.0), leader.1);
+        let offset = Vec3::new(0.0, -10.0, 0.0);
+
+        let dt = 1.0 / 60.0;
+        for _ in 0..(30.0 / dt) as usize {
+            let control = formation_acceleration(leader, follower, offset);
+
+            leader.1 += gravity(leader.0) * dt;
+            leader.0 += leader.1 * dt;
+            follower.1 += (gravity(follower.0) + control) * dt;
+            follower.0 += follower.1 * dt;
+        }
+
+        let target = leader.0 + lvlh_basis(leader.0, leader.1) * offset;
+        let error = follower.0.distance(target);
+        assert!(error < 0.5, "{error} away from the offset");
+    }
+
+    #[test]
+    fn wingman_takes_its_place() {
+        let mut app = headless::headless_app(Scenario::Formation);
+        headless::run_app(&mut app, &[], 30 * 60);
+
+        let (leader_pos, leader_vel) = app
+            .world
+            .query_filtered::<(&Transform, &Velocity), With<Spaceship>>()
+            .single(&app.world);
+        let (leader_pos, leader_vel) = (leader_pos.translation, leader_vel.linvel);
+        let (wingman, formation) = app
+            .world
+            .query::<(&Transform, &FormationFlight)>()
+            .single(&app.world);
+
+        // the planet is at the origin
+        let target = leader_pos + lvlh_basis(leader_pos, leader_vel) * formation.offset;
+        let error = wingman.translation.distance(target);
+        assert!(error < 0.5, "{error} away from the offset");
+    }
+}
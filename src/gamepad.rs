@@ -0,0 +1,156 @@
+//! Analog flight controls with a gamepad, on top of the keyboard ones.
+//! The left stick pitches and yaws, the right stick rolls and the right trigger fires the engine
+//! with the throttle at the trigger deflection. With `--rcs` the D-pad pushes the ship sideways.
+
+use bevy::prelude::*;
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GamepadControls {
+    /// Deflections of the sticks and triggers below this are ignored, so that a stick that
+    /// doesn't quite center doesn't keep the ship turning.
+    pub deadzone: f32,
+}
+
+impl Default for GamepadControls {
+    fn default() -> Self {
+        Self { deadzone: 0.15 }
+    }
+}
+
+/// Maps an axis value in -1..=1 to 0 inside the deadzone and rescales the rest to still
+/// reach the full range, so there is no jump at the edge of the deadzone.
+pub fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * ((value.abs() - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+/// What the gamepad asks for this frame, nothing without a connected gamepad. It's flown by
+/// [`fire_thrusters`](crate::fire_thrusters) together with the keyboard.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq)]
+pub struct GamepadInput {
+    /// How far to pitch, roll and yaw, from -1 to 1 around the local axes.
+    pub rotation: Vec3,
+    /// The throttle to fire the engine with, 0 when the trigger isn't pulled.
+    pub trigger: f32,
+    /// Which way to push the ship across its long axis, along the local X and Z axes. Only
+    /// the RCS thrusters can do that, the engine just pushes forward.
+    pub translation: Vec3,
+}
+
+pub fn read_gamepad(
+    config: Res<GamepadControls>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    buttons: Res<Axis<GamepadButton>>,
+    mut input: ResMut<GamepadInput>,
+) {
+    let Some(gamepad) = gamepads.iter().next() else {
+        *input = default();
+        return;
+    };
+    let axis = |axis_type| {
+        let value = axes.get(GamepadAxis::new(gamepad, axis_type));
+        apply_deadzone(value.unwrap_or(0.0), config.deadzone)
+    };
+
+    input.rotation = Vec3::new(
+        axis(GamepadAxisType::LeftStickY),
+        -axis(GamepadAxisType::RightStickX),
+        -axis(GamepadAxisType::LeftStickX),
+    );
+    let trigger = buttons.get(GamepadButton::new(
+        gamepad,
+        GamepadButtonType::RightTrigger2,
+    ));
+    input.trigger = apply_deadzone(trigger.unwrap_or(0.0), config.deadzone);
+
+    let button = |button_type| {
+        buttons
+            .get(GamepadButton::new(gamepad, button_type))
+            .unwrap_or(0.0)
+    };
+    input.translation = Vec3::new(
+        button(GamepadButtonType::DPadRight) - button(GamepadButtonType::DPadLeft),
+        0.0,
+        button(GamepadButtonType::DPadDown) - button(GamepadButtonType::DPadUp),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{apply_deadzone, GamepadInput};
+    use crate::{
+        burn_guard::BurnGuard, forces::ExternalForceSet, headless, rcs::RcsBalancing,
+        scenario::Scenario, Spaceship, ThrusterForce, Thrusters,
+    };
+
+    #[test]
+    fn deadzone_is_continuous() {
+        assert_eq!(apply_deadzone(0.1, 0.2), 0.0);
+        assert_eq!(apply_deadzone(-0.2, 0.2), 0.0);
+        assert!(apply_deadzone(0.21, 0.2) < 0.02);
+        assert_eq!(apply_deadzone(1.0, 0.2), 1.0);
+        assert_eq!(apply_deadzone(-1.0, 0.2), -1.0);
+        assert!((apply_deadzone(0.6, 0.2) - 0.5).abs() < 1e-6);
+    }
+
+    /// The thrust and the throttle setting after pulling the trigger to `trigger` for a tick.
+    fn thrust_with_trigger(trigger: f32, tripped: bool) -> (f32, f32) {
+        let mut app = headless::headless_app(Scenario::CircularOrbit);
+        app.insert_resource(GamepadInput {
+            trigger,
+            ..default()
+        });
+        headless::run_app(&mut app, &[], 1);
+        app.world.resource_mut::<BurnGuard>().tripped = tripped;
+        headless::run_app(&mut app, &[], 1);
+
+        let (forces, thrusters) = app
+            .world
+            .query_filtered::<(&ExternalForceSet, &Thrusters), With<Spaceship>>()
+            .single(&app.world);
+        let thrust = forces.get::<ThrusterForce>().force.length();
+        (thrust / thrusters.strength, thrusters.throttle)
+    }
+
+    #[test]
+    fn trigger_fires_at_its_deflection() {
+        let (thrust, throttle) = thrust_with_trigger(0.5, false);
+        assert!((thrust - 0.5).abs() < 1e-5, "{thrust}");
+        // the keyboard throttle stays where it was
+        assert_eq!(throttle, thrust_with_trigger(0.0, false).1);
+
+        // the burn guard cuts it like the thrust key
+        assert_eq!(thrust_with_trigger(0.5, true).0, 0.0);
+    }
+
+    #[test]
+    fn d_pad_pushes_sideways_without_turning() {
+        let mut app = headless::headless_app(Scenario::CircularOrbit);
+        app.insert_resource(RcsBalancing {
+            enabled: true,
+            ..default()
+        })
+        .insert_resource(GamepadInput {
+            translation: Vec3::new(1.0, 0.0, -1.0),
+            ..default()
+        });
+        // the thrusters are installed in the first tick
+        headless::run_app(&mut app, &[], 2);
+
+        let (forces, transform) = app
+            .world
+            .query_filtered::<(&ExternalForceSet, &Transform), With<Spaceship>>()
+            .single(&app.world);
+        let force = forces.get::<ThrusterForce>();
+        let local = transform.rotation.inverse() * force.force;
+        let expected = Vec3::new(1.0, 0.0, -1.0) * RcsBalancing::default().max_force;
+        assert!(local.distance(expected) < 1e-3, "{local} == {expected}");
+        assert!(force.torque.length() < 1e-3, "{}", force.torque);
+    }
+}
@@ -0,0 +1,114 @@
+//! The gravity gradient torque: the end of an elongated body closer to an attractor is pulled
+//! harder than the far end, which slowly turns its long axis towards the local vertical.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{forces::ExternalForceSet, orbit, GravityAttractor};
+
+/// Off by default, `--gravity-gradient` turns it on. It slowly turns a ship that's left alone,
+/// which would be surprising in normal play.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct GravityGradient {
+    pub enabled: bool,
+}
+
+impl GravityGradient {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: std::env::args().any(|arg| arg == "--gravity-gradient"),
+        }
+    }
+}
+
+/// Marks the gravity gradient torque in the [`ExternalForceSet`].
+pub struct GravityGradientTorque;
+
+/// `3 G M / r³ (r̂ × I r̂)` for a body with the principal moments `principal_inertia` along the
+/// axes of `orientation`, at `offset` from an attractor of mass `m`.
+pub fn gravity_gradient_torque(
+    m: f64,
+    offset: Vec3,
+    orientation: Quat,
+    principal_inertia: Vec3,
+) -> Vec3 {
+    let r = f64::from(offset.length());
+    if r == 0.0 {
+        return Vec3::ZERO;
+    }
+    let direction = offset / r as f32;
+
+    let local_direction = orientation.inverse() * direction;
+    let inertia_direction = orientation * (principal_inertia * local_direction);
+    let strength = 3.0 * orbit::G * m / (r * r * r);
+
+    direction.cross(inertia_direction) * strength as f32
+}
+
+pub fn apply_gravity_gradient_torque(
+    config: Res<GravityGradient>,
+    mut query: Query<(
+        Entity,
+        &mut ExternalForceSet,
+        &Transform,
+        &ReadMassProperties,
+    )>,
+    body_query: Query<(Entity, &GravityAttractor, &Transform)>,
+) {
+    for (entity, mut forces, transform, mass_properties) in &mut query {
+        let torque = if config.enabled {
+            let props = mass_properties.0;
+            let orientation = transform.rotation * props.principal_inertia_local_frame;
+            body_query
+                .iter()
+                .filter(|&(body, _, _)| body != entity)
+                .map(|(_, gravity, body_transform)| {
+                    gravity_gradient_torque(
+                        gravity.mass,
+                        transform.translation - body_transform.translation,
+                        orientation,
+                        props.principal_inertia,
+                    )
+                })
+                .sum()
+        } else {
+            Vec3::ZERO
+        };
+
+        forces.set::<GravityGradientTorque>(ExternalForce {
+            force: Vec3::ZERO,
+            torque,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::gravity_gradient_torque;
+
+    /// Long along Y, like the ship.
+    const INERTIA: Vec3 = Vec3::new(0.35, 0.04, 0.35);
+
+    #[test]
+    fn no_torque_when_aligned_with_vertical() {
+        let torque =
+            gravity_gradient_torque(1.0e12, Vec3::new(0.0, 500.0, 0.0), Quat::IDENTITY, INERTIA);
+        assert_eq!(torque, Vec3::ZERO);
+
+        let lying = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let torque = gravity_gradient_torque(1.0e12, Vec3::new(500.0, 0.0, 0.0), lying, INERTIA);
+        assert!(torque.length() < 1e-9, "{torque}");
+    }
+
+    #[test]
+    fn tilted_body_turns_towards_vertical() {
+        // tilted from the vertical Y towards X, around Z
+        let tilted = Quat::from_rotation_z(-0.3);
+        let torque = gravity_gradient_torque(1.0e12, Vec3::new(0.0, 500.0, 0.0), tilted, INERTIA);
+
+        assert!(torque.z > 0.0, "{torque}");
+        assert!(torque.x.abs() < 1e-9 && torque.y.abs() < 1e-9, "{torque}");
+    }
+}
@@ -0,0 +1,79 @@
+//! Showing the local gravitational acceleration in g's, to see how gravity weakens with altitude.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    dominant_attractor, forces::ExternalForceSet, health::STANDARD_GRAVITY, hud::ShipStatusText,
+    mass_of, orbit, units::WorldScale, GravityAttractor, GravityForce, Planet, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GravityReadout {
+    /// The acceleration of one g, in m/s².
+    pub reference: f64,
+    /// Also show the gravity as a fraction of the one on the surface of the dominant planet.
+    pub relative_to_surface: bool,
+}
+
+impl Default for GravityReadout {
+    fn default() -> Self {
+        Self {
+            reference: f64::from(STANDARD_GRAVITY),
+            relative_to_surface: true,
+        }
+    }
+}
+
+/// `G*M/r²`, the gravitational acceleration at a distance `r` from a body of mass `m`.
+pub fn gravity_at(m: f64, r: f64) -> f64 {
+    orbit::G * m / (r * r)
+}
+
+pub fn update_gravity_text(
+    config: Res<GravityReadout>,
+    scale: Res<WorldScale>,
+    query: Query<(&Transform, &ExternalForceSet, Option<&ReadMassProperties>), With<Spaceship>>,
+    body_query: Query<(&Transform, &GravityAttractor, &Planet), Without<Spaceship>>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+) {
+    let mut text = text_query.single_mut();
+    let (ship_transform, forces, mass) = query.single();
+
+    let acceleration = f64::from(forces.get::<GravityForce>().force.length() / mass_of(mass));
+    let g = acceleration * scale.meters_per_unit / config.reference;
+    text.sections[5].value = format!("{g:.5} g");
+
+    if !config.relative_to_surface {
+        return;
+    }
+    let Some((body_transform, gravity, planet)) =
+        dominant_attractor(&body_query, ship_transform.translation, |(t, g, _)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let r = f64::from(
+        ship_transform
+            .translation
+            .distance(body_transform.translation),
+    );
+    let fraction = gravity_at(gravity.mass, r) / gravity_at(gravity.mass, planet.radius);
+    text.sections[5].value += &format!(" ({:.1}% of surface)", fraction * 100.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gravity_at;
+
+    #[test]
+    fn gravity_falls_with_square_of_distance() {
+        let m = 5.0e12;
+        let surface = gravity_at(m, 1000.0);
+
+        assert!((gravity_at(m, 2000.0) / surface - 0.25).abs() < 1e-12);
+        assert!((gravity_at(m, 10_000.0) / surface - 0.01).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,243 @@
+//! The "rubber sheet" picture of gravity: a grid below the system that dips into a well
+//! around every attractor, as deep as the gravitational potential there.
+
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        render_resource::PrimitiveTopology,
+        view::NoFrustumCulling,
+    },
+};
+
+use crate::{orbit, GravityAttractor, Planet};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GravitySheet {
+    pub enabled: bool,
+    /// Edge length of the square sheet.
+    pub size: f32,
+    /// Number of grid cells along each edge.
+    pub resolution: usize,
+    /// How far the sheet dips for every unit of potential (in J/kg).
+    pub depth_scale: f32,
+    /// The deepest a well can get, so that point masses don't become bottomless.
+    pub max_depth: f32,
+    /// Distance between the lowest body and the undisturbed sheet.
+    pub offset: f32,
+}
+
+impl Default for GravitySheet {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            size: 400_000.0,
+            resolution: 128,
+            depth_scale: 200.0,
+            max_depth: 50_000.0,
+            offset: 5000.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct GravitySheetMesh;
+
+/// A body pulling on the sheet, with the position relative to the sheet center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SheetAttractor {
+    pub position: Vec2,
+    pub mass: f64,
+    pub radius: f64,
+}
+
+/// The summed gravitational potential at `point`, which is always negative.
+/// Within a body, it's the potential of a uniform sphere so that the well has a round bottom.
+pub fn potential(attractors: &[SheetAttractor], point: Vec2) -> f64 {
+    attractors
+        .iter()
+        .map(|attractor| {
+            let d = attractor.position.distance(point) as f64;
+            let r = attractor.radius;
+            if d >= r {
+                -orbit::G * attractor.mass / d
+            } else {
+                -orbit::G * attractor.mass * (3.0 * r * r - d * d) / (2.0 * r * r * r)
+            }
+        })
+        .sum()
+}
+
+/// A flat grid of lines with `resolution` cells along each edge, centered on the origin.
+fn grid_mesh(size: f32, resolution: usize) -> Mesh {
+    let vertices_per_edge = resolution + 1;
+    let cell = size / resolution as f32;
+
+    let mut positions = Vec::with_capacity(vertices_per_edge * vertices_per_edge);
+    for z in 0..vertices_per_edge {
+        for x in 0..vertices_per_edge {
+            positions.push([
+                x as f32 * cell - size / 2.0,
+                0.0,
+                z as f32 * cell - size / 2.0,
+            ]);
+        }
+    }
+
+    let mut indices = Vec::new();
+    for z in 0..vertices_per_edge {
+        for x in 0..vertices_per_edge {
+            let i = (z * vertices_per_edge + x) as u32;
+            if x + 1 < vertices_per_edge {
+                indices.extend([i, i + 1]);
+            }
+            if z + 1 < vertices_per_edge {
+                indices.extend([i, i + vertices_per_edge as u32]);
+            }
+        }
+    }
+
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+
+    let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+pub fn spawn_gravity_sheet(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    sheet: Res<GravitySheet>,
+) {
+    commands.spawn((
+        GravitySheetMesh,
+        // the bounding box is computed once for the flat grid and doesn't follow the wells
+        NoFrustumCulling,
+        PbrBundle {
+            mesh: meshes.add(grid_mesh(sheet.size, sheet.resolution)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.2, 0.6, 1.0),
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+/// F7 toggles the sheet.
+pub fn toggle_gravity_sheet(keyboard_input: Res<Input<KeyCode>>, mut sheet: ResMut<GravitySheet>) {
+    if keyboard_input.just_pressed(KeyCode::F7) {
+        sheet.enabled = !sheet.enabled;
+    }
+}
+
+/// Deforms the sheet by the potential of all attractors. The vertices are only
+/// touched again when a body moved or the sheet settings changed.
+pub fn draw_gravity_sheet(
+    sheet: Res<GravitySheet>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    body_query: Query<(&Transform, &GravityAttractor, Option<&Planet>)>,
+    mut sheet_query: Query<
+        (&mut Transform, &mut Visibility, &Handle<Mesh>),
+        (With<GravitySheetMesh>, Without<GravityAttractor>),
+    >,
+    mut last_bodies: Local<Vec<(Vec3, f64, f64)>>,
+) {
+    let Ok((mut transform, mut visibility, mesh)) = sheet_query.get_single_mut() else {
+        return;
+    };
+
+    if !sheet.enabled {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Visible;
+
+    let bodies = body_query
+        .iter()
+        .map(|(t, g, planet)| (t.translation, g.mass, planet.map_or(0.0, |p| p.radius)))
+        .collect::<Vec<_>>();
+    if bodies.is_empty() || (*last_bodies == bodies && !sheet.is_changed()) {
+        return;
+    }
+
+    let total_mass = bodies.iter().map(|&(_, m, _)| m).sum::<f64>();
+    let center = bodies
+        .iter()
+        .map(|&(pos, m, _)| pos * (m / total_mass) as f32)
+        .sum::<Vec3>();
+    let bottom = bodies
+        .iter()
+        .map(|&(pos, _, r)| pos.y - r as f32)
+        .fold(f32::INFINITY, f32::min);
+    transform.translation = Vec3::new(center.x, bottom - sheet.offset, center.z);
+
+    let attractors = bodies
+        .iter()
+        .map(|&(pos, mass, radius)| SheetAttractor {
+            position: (pos - center).xz(),
+            mass,
+            radius,
+        })
+        .collect::<Vec<_>>();
+
+    let Some(mesh) = meshes.get_mut(mesh) else {
+        return;
+    };
+    let vertex_count = (sheet.resolution + 1) * (sheet.resolution + 1);
+    if mesh.count_vertices() != vertex_count {
+        *mesh = grid_mesh(sheet.size, sheet.resolution);
+    }
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return;
+    };
+
+    for position in positions {
+        let point = Vec2::new(position[0], position[2]);
+        let depth =
+            (-potential(&attractors, point) as f32 * sheet.depth_scale).min(sheet.max_depth);
+        position[1] = -depth;
+    }
+
+    *last_bodies = bodies;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Vec2;
+
+    use super::{potential, SheetAttractor};
+
+    #[test]
+    fn dips_toward_heavier_bodies() {
+        let light = SheetAttractor {
+            position: Vec2::new(-1000.0, 0.0),
+            mass: 1.0e15,
+            radius: 100.0,
+        };
+        let heavy = SheetAttractor {
+            position: Vec2::new(1000.0, 0.0),
+            mass: 4.0e15,
+            ..light
+        };
+        let attractors = [light, heavy];
+
+        assert!(
+            potential(&attractors, Vec2::new(500.0, 0.0))
+                < potential(&attractors, Vec2::new(-500.0, 0.0))
+        );
+        // deepest at the center of a body, flattening out far away
+        assert!(
+            potential(&attractors, heavy.position) < potential(&attractors, Vec2::new(1050.0, 0.0))
+        );
+        assert!(potential(&attractors, Vec2::new(1.0e9, 0.0)).abs() < 1.0e-3);
+    }
+}
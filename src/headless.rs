@@ -0,0 +1,336 @@
+//! Running the simulation without a window for a fixed number of ticks,
+//! for deterministic regression tests.
+
+use std::{ops::Range, time::Duration};
+
+use bevy::{prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy, transform::TransformPlugin};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    audio::AudioEnabled,
+    replay::{self, Recording, Replayer},
+    scenario::Scenario,
+    simulation::SimulationPlugin,
+    Spaceship,
+};
+
+/// The duration of one tick in seconds.
+pub const TICK: f32 = 1.0 / 60.0;
+
+/// Holds down `key` for all ticks in `ticks`.
+#[derive(Debug, Clone)]
+pub struct KeyHold {
+    pub key: KeyCode,
+    pub ticks: Range<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShipState {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub velocity: Vec3,
+    pub angular_velocity: Vec3,
+}
+
+/// How many ticks to run for when `--headless=` isn't given a valid amount, ten seconds.
+pub const DEFAULT_TICKS: u32 = 600;
+
+/// `--headless=<ticks>` runs the scenario headless and prints the final state. The ticks are
+/// only parsed by [`parse_ticks`], once logging is set up to warn about them.
+pub fn ticks_from_args() -> Option<String> {
+    std::env::args().find_map(|arg| arg.strip_prefix("--headless=").map(str::to_owned))
+}
+
+pub fn parse_ticks(ticks: &str) -> u32 {
+    ticks.parse().unwrap_or_else(|_| {
+        warn!("Invalid amount of ticks `{ticks}`, running {DEFAULT_TICKS}");
+        DEFAULT_TICKS
+    })
+}
+
+/// Builds an app simulating `scenario` without a window, audio or rendering.
+/// Every update advances the physics by exactly one [`TICK`].
+pub fn headless_app(scenario: Scenario) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        HierarchyPlugin,
+        AssetPlugin::default(),
+        ScenePlugin,
+    ))
+    .add_asset::<Mesh>()
+    .add_asset::<StandardMaterial>()
+    .add_asset::<Image>()
+    // replaces the keyboard polling of the input plugin, keys are pressed by the script
+    .init_resource::<Input<KeyCode>>()
+    .insert_resource(AudioEnabled(false))
+    .add_plugins(SimulationPlugin)
+    .add_systems(
+        Startup,
+        move |mut commands: Commands,
+              mut meshes: ResMut<Assets<Mesh>>,
+              mut materials: ResMut<Assets<StandardMaterial>>,
+              asset_server: Res<AssetServer>| {
+            crate::scenario::spawn(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &asset_server,
+                scenario,
+            );
+        },
+    );
+
+    use_fixed_ticks(&mut app);
+
+    app
+}
+
+/// Makes every update advance the time and the physics by exactly one [`TICK`], no matter how
+/// long the frame took. Needs the [`SimulationPlugin`] to be added already.
+pub fn use_fixed_ticks(app: &mut App) {
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+        TICK,
+    )));
+    app.world
+        .resource_mut::<RapierConfiguration>()
+        .timestep_mode = TimestepMode::Fixed {
+        dt: TICK,
+        substeps: 1,
+    };
+}
+
+pub fn ship_state(app: &mut App) -> ShipState {
+    let (transform, velocity) = app
+        .world
+        .query_filtered::<(&Transform, &Velocity), With<Spaceship>>()
+        .single(&app.world);
+
+    ShipState {
+        position: transform.translation,
+        rotation: transform.rotation,
+        velocity: velocity.linvel,
+        angular_velocity: velocity.angvel,
+    }
+}
+
+/// Simulates `scenario` for `ticks` ticks while pressing the keys of `inputs`.
+pub fn run_headless(scenario: Scenario, inputs: &[KeyHold], ticks: u32) -> ShipState {
+    let mut app = headless_app(scenario);
+    run_app(&mut app, inputs, ticks)
+}
+
+/// Plays back `recording`, for as many ticks as it was recorded.
+pub fn run_replay(recording: Recording) -> ShipState {
+    let mut app = headless_app(recording.scenario);
+    let ticks = recording.ticks;
+    app.insert_resource(Replayer::new(recording))
+        .add_systems(PreUpdate, replay::replay_input);
+    run_app(&mut app, &[], ticks)
+}
+
+/// Runs an app made by [`headless_app`] for `ticks` ticks while pressing the keys of `inputs`.
+pub fn run_app(app: &mut App, inputs: &[KeyHold], ticks: u32) -> ShipState {
+    for tick in 0..ticks {
+        let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+        keys.clear();
+        for hold in inputs {
+            if hold.ticks.contains(&tick) {
+                keys.press(hold.key);
+            } else {
+                keys.release(hold.key);
+            }
+        }
+
+        app.update();
+    }
+
+    ship_state(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::{Restitution, Velocity};
+
+    use super::{headless_app, parse_ticks, ship_state, KeyHold, DEFAULT_TICKS, TICK};
+    use crate::{
+        orbit::Orbit,
+        precision::OrbitPrecision,
+        scenario::{
+            Scenario, LOW_ORBIT_ALTITUDE, SMALL_PLANET_DENSITY, SMALL_PLANET_RADIUS, TUMBLE_ANGVEL,
+        },
+        sphere_mass, Spaceship, SHIP_HEIGHT,
+    };
+
+    fn low_orbit_mass() -> f64 {
+        sphere_mass(SMALL_PLANET_RADIUS, SMALL_PLANET_DENSITY)
+    }
+
+    /// The low orbit of the orbit scenarios.
+    fn low_orbit() -> Orbit {
+        Orbit {
+            semi_major_axis: SMALL_PLANET_RADIUS + LOW_ORBIT_ALTITUDE,
+            eccentricity: 0.0,
+        }
+    }
+
+    /// The ticks for one revolution of the low orbit.
+    fn low_orbit_ticks() -> u32 {
+        let period = low_orbit().period(low_orbit_mass());
+        (period / f64::from(TICK)).round() as u32
+    }
+
+    #[test]
+    fn circular_orbit_holds_radius() {
+        let r = SMALL_PLANET_RADIUS + LOW_ORBIT_ALTITUDE;
+        let state = super::run_headless(Scenario::CircularOrbit, &[], low_orbit_ticks());
+
+        let radius = state.position.length() as f64;
+        assert!((radius - r).abs() < r * 0.01, "{radius} == {r}");
+    }
+
+    #[test]
+    fn runs_are_deterministic() {
+        // with a sleep in between, so wall clock time would show up in the result
+        // the throttle follows the time the key is held
+        let inputs = [
+            KeyHold {
+                key: KeyCode::ControlLeft,
+                ticks: 0..20,
+            },
+            KeyHold {
+                key: KeyCode::Space,
+                ticks: 10..40,
+            },
+        ];
+        let first = super::run_headless(Scenario::CircularOrbit, &inputs, 120);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let second = super::run_headless(Scenario::CircularOrbit, &inputs, 120);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn invalid_ticks_fall_back() {
+        assert_eq!(parse_ticks("120"), 120);
+        assert_eq!(parse_ticks("forever"), DEFAULT_TICKS);
+        assert_eq!(parse_ticks("-1"), DEFAULT_TICKS);
+    }
+
+    #[test]
+    fn circular_orbit_holds_radius_in_double_precision() {
+        let r = SMALL_PLANET_RADIUS + LOW_ORBIT_ALTITUDE;
+        let mut app = headless_app(Scenario::CircularOrbit);
+        app.insert_resource(OrbitPrecision::Double);
+        let state = super::run_app(&mut app, &[], low_orbit_ticks());
+
+        let radius = state.position.length() as f64;
+        assert!((radius - r).abs() < r * 0.01, "{radius} == {r}");
+    }
+
+    #[test]
+    fn frozen_orbit_ignores_thrust() {
+        let r = SMALL_PLANET_RADIUS + LOW_ORBIT_ALTITUDE;
+        let ticks = low_orbit_ticks();
+        let freeze = |ticks| KeyHold {
+            key: KeyCode::F2,
+            ticks,
+        };
+        let inputs = [
+            freeze(0..1),
+            KeyHold {
+                key: KeyCode::Space,
+                ticks: 10..ticks / 2,
+            },
+            freeze(ticks / 2..ticks / 2 + 1),
+        ];
+        let state = super::run_headless(Scenario::CircularOrbit, &inputs, ticks);
+
+        let radius = state.position.length() as f64;
+        assert!((radius - r).abs() < r * 0.01, "{radius} == {r}");
+        let expected_speed = low_orbit().speed_at(low_orbit_mass(), r);
+        let speed = state.velocity.length() as f64;
+        assert!(
+            (speed - expected_speed).abs() < expected_speed * 0.01,
+            "{speed} == {expected_speed}"
+        );
+    }
+
+    /// Drops the ship straight down onto the small planet at `impact_speed`,
+    /// returning the speed it bounces back up with.
+    fn rebound_speed(restitution: f32, impact_speed: f32) -> (f32, f32) {
+        let mut app = headless_app(Scenario::CircularOrbit);
+        app.update();
+
+        let (ship, mut transform, mut velocity) = app
+            .world
+            .query_filtered::<(Entity, &mut Transform, &mut Velocity), With<Spaceship>>()
+            .single_mut(&mut app.world);
+        // upright a bit above the surface, the planet is at the origin
+        *transform = Transform::from_xyz(
+            SMALL_PLANET_RADIUS as f32 + SHIP_HEIGHT / 2.0 + 0.5,
+            0.0,
+            0.0,
+        )
+        .with_rotation(Quat::from_rotation_arc(Vec3::Y, Vec3::X));
+        *velocity = Velocity::linear(Vec3::new(-impact_speed, 0.0, 0.0));
+        // keeping how it combines with the planet's
+        app.world.get_mut::<Restitution>(ship).unwrap().coefficient = restitution;
+
+        let mut fastest_down = 0.0f32;
+        for _ in 0..120 {
+            app.update();
+            let radial = ship_state(&mut app).velocity.x;
+            if radial > 0.0 {
+                return (fastest_down, radial);
+            }
+            fastest_down = fastest_down.max(-radial);
+        }
+        panic!("the ship didn't bounce");
+    }
+
+    #[test]
+    fn bounce_follows_restitution() {
+        for restitution in [0.4, 0.8] {
+            let (impact, rebound) = rebound_speed(restitution, 10.0);
+            let expected = restitution * impact;
+            assert!(
+                (rebound - expected).abs() < expected * 0.05,
+                "restitution {restitution}: {rebound} == {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn tumble_starts_with_angular_velocity() {
+        let mut app = headless_app(Scenario::Tumble);
+        app.update();
+
+        let angvel = ship_state(&mut app).angular_velocity;
+        assert!(
+            angvel.abs_diff_eq(TUMBLE_ANGVEL, 1e-3),
+            "{angvel} == {TUMBLE_ANGVEL}"
+        );
+    }
+
+    #[test]
+    fn orbital_plane_is_stable() {
+        let mut app = headless_app(Scenario::CircularOrbit);
+        app.update();
+
+        // the planet is at the origin
+        let normal_of = |app: &mut bevy::app::App| {
+            let state = ship_state(app);
+            state.velocity.cross(state.position).normalize()
+        };
+        let initial = normal_of(&mut app);
+
+        for tick in 0..low_orbit_ticks() {
+            app.update();
+            let normal = normal_of(&mut app);
+            let tilt = normal.angle_between(initial);
+            assert!(tilt < 1e-3, "orbit tilted by {tilt} rad after {tick} ticks");
+        }
+    }
+}
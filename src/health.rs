@@ -0,0 +1,253 @@
+//! Structural integrity of the ship, damaged by hard impacts and high g-forces.
+
+use bevy::{audio::VolumeLevel, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    audio::{self, AudioEnabled},
+    forces::ExternalForceSet,
+    hud::ShipStatusText,
+    GravityForce, Spaceship, ThrusterForce, ThrusterSound,
+};
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health { current: max, max }
+    }
+
+    pub fn damage(&mut self, amount: f32) {
+        self.current = (self.current - amount).max(0.0);
+    }
+}
+
+/// The ship has been destroyed and can't be controlled anymore.
+#[derive(Component)]
+pub struct Destroyed;
+
+/// Acceleration of one g.
+pub const STANDARD_GRAVITY: f32 = 9.81;
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct DamageConfig {
+    /// The highest sustained acceleration that's harmless, in g.
+    pub g_limit: f32,
+    /// Damage per second for every g above the limit.
+    pub g_damage_rate: f32,
+    /// The highest impact speed that's harmless.
+    pub crash_speed: f32,
+    /// Damage per unit of impact speed above `crash_speed`.
+    pub crash_damage: f32,
+}
+
+impl Default for DamageConfig {
+    fn default() -> Self {
+        Self {
+            g_limit: 8.0,
+            g_damage_rate: 5.0,
+            crash_speed: 5.0,
+            crash_damage: 10.0,
+        }
+    }
+}
+
+impl DamageConfig {
+    /// Damage from sustaining `acceleration` for `dt` seconds.
+    pub fn g_force_damage(&self, acceleration: f32, dt: f32) -> f32 {
+        let excess_g = acceleration / STANDARD_GRAVITY - self.g_limit;
+        excess_g.max(0.0) * self.g_damage_rate * dt
+    }
+
+    pub fn impact_damage(&self, impact_speed: f32) -> f32 {
+        (impact_speed - self.crash_speed).max(0.0) * self.crash_damage
+    }
+}
+
+/// The acceleration felt on board, in g. Gravity alone doesn't strain the ship as it pulls
+/// on everything equally, so only the other forces count.
+pub fn g_load(forces: &ExternalForceSet, mass: f32) -> f32 {
+    let force = forces.combine().force - forces.get::<GravityForce>().force;
+    force.length() / mass / STANDARD_GRAVITY
+}
+
+pub fn damage_from_g_forces(
+    time: Res<Time>,
+    config: Res<DamageConfig>,
+    mut query: Query<(&mut Health, &ExternalForceSet, &ReadMassProperties), Without<Destroyed>>,
+) {
+    for (mut health, forces, mass) in &mut query {
+        if mass.0.mass <= 0.0 {
+            // rapier hasn't computed the mass yet
+            continue;
+        }
+
+        let acceleration = g_load(forces, mass.0.mass) * STANDARD_GRAVITY;
+        health.damage(config.g_force_damage(acceleration, time.delta_seconds()));
+    }
+}
+
+pub fn damage_from_impacts(
+    config: Res<DamageConfig>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut query: Query<(Entity, &mut Health, &Velocity), (With<Spaceship>, Without<Destroyed>)>,
+    other_query: Query<Option<&Velocity>>,
+    // the velocity before this frame's collisions, as the contact already slowed the ship down
+    mut last_velocity: Local<Option<Vec3>>,
+) {
+    let Ok((ship, mut health, velocity)) = query.get_single_mut() else {
+        collisions.clear();
+        *last_velocity = None;
+        return;
+    };
+    let impact_velocity = last_velocity
+        .replace(velocity.linvel)
+        .unwrap_or(velocity.linvel);
+
+    for collision in collisions.iter() {
+        let &CollisionEvent::Started(e1, e2, flags) = collision else {
+            continue;
+        };
+        // flying through a sensor doesn't hit anything
+        if flags.contains(bevy_rapier3d::rapier::geometry::CollisionEventFlags::SENSOR) {
+            continue;
+        }
+        let other = match (e1, e2) {
+            (e, other) | (other, e) if e == ship => other,
+            _ => continue,
+        };
+
+        let other_velocity = other_query
+            .get(other)
+            .ok()
+            .flatten()
+            .map_or(Vec3::ZERO, |v| v.linvel);
+        let impact_speed = (impact_velocity - other_velocity).length();
+
+        let damage = config.impact_damage(impact_speed);
+        if damage > 0.0 {
+            info!("Crashed at {impact_speed:.2}, taking {damage:.1} damage");
+            health.damage(damage);
+        }
+    }
+}
+
+pub fn destroy_ship(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    audio: Res<AudioEnabled>,
+    mut query: Query<(Entity, &Health, &mut ExternalForceSet), Without<Destroyed>>,
+    sound_query: Query<&AudioSink, With<ThrusterSound>>,
+) {
+    for (entity, health, mut forces) in &mut query {
+        if health.current > 0.0 {
+            continue;
+        }
+
+        info!("Ship destroyed");
+        commands.entity(entity).insert(Destroyed);
+        forces.set::<ThrusterForce>(ExternalForce::default());
+        if let Ok(sound) = sound_query.get_single() {
+            sound.pause();
+        }
+
+        // There's no explosion sound yet, a slowed down thruster makes a decent rumble.
+        audio::play_one_shot(
+            &mut commands,
+            &asset_server,
+            *audio,
+            "thrusters_loop.ogg",
+            PlaybackSettings {
+                volume: bevy::audio::Volume::Relative(VolumeLevel::new(2.0)),
+                speed: 0.3,
+                ..PlaybackSettings::ONCE
+            },
+        );
+    }
+}
+
+pub fn update_health_text(
+    query: Query<(&Health, Option<&Destroyed>), With<Spaceship>>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+) {
+    let (health, destroyed) = query.single();
+    let mut text = text_query.single_mut();
+
+    text.sections[1].value = if destroyed.is_some() {
+        "DESTROYED".to_owned()
+    } else {
+        format!("{:.0}/{:.0}", health.current, health.max)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{DamageConfig, Health, STANDARD_GRAVITY};
+    use crate::{
+        headless::{self, KeyHold},
+        scenario::Scenario,
+        Spaceship,
+    };
+
+    #[test]
+    fn sustained_high_g_reduces_health() {
+        let config = DamageConfig::default();
+        let mut health = Health::new(100.0);
+        let acceleration = (config.g_limit + 4.0) * STANDARD_GRAVITY;
+
+        let dt = 1.0 / 60.0;
+        let mut last = health.current;
+        for _ in 0..60 {
+            health.damage(config.g_force_damage(acceleration, dt));
+            assert!(health.current < last);
+            last = health.current;
+        }
+
+        // one second at 4g above the limit
+        let expected = 100.0 - 4.0 * config.g_damage_rate;
+        assert!(
+            (health.current - expected).abs() < 0.01,
+            "{}",
+            health.current
+        );
+    }
+
+    #[test]
+    fn below_limit_is_harmless() {
+        let config = DamageConfig::default();
+        let acceleration = config.g_limit * STANDARD_GRAVITY * 0.9;
+        assert_eq!(config.g_force_damage(acceleration, 1.0), 0.0);
+        assert_eq!(config.impact_damage(config.crash_speed * 0.5), 0.0);
+    }
+
+    /// The health of the ship after `ticks` in the circular orbit, where any thrust is too much.
+    fn health_after_thrusting(ticks: u32) -> f32 {
+        let mut app = headless::headless_app(Scenario::CircularOrbit);
+        app.insert_resource(DamageConfig {
+            g_limit: 0.0,
+            ..default()
+        });
+        let thrust = KeyHold {
+            key: KeyCode::Space,
+            ticks: 0..ticks,
+        };
+        headless::run_app(&mut app, &[thrust], 60);
+        app.world
+            .query_filtered::<&Health, With<Spaceship>>()
+            .single(&app.world)
+            .current
+    }
+
+    #[test]
+    fn thrusting_damages_the_ship() {
+        // only the thrust strains the ship, not gravity
+        assert_eq!(health_after_thrusting(0), 100.0);
+        assert!(health_after_thrusting(60) < 100.0);
+    }
+}
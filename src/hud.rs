@@ -0,0 +1,222 @@
+//! The HUD readouts, grouped into panels anchored to the edges of the screen.
+
+use bevy::prelude::*;
+
+use crate::OrbitText;
+
+/// The panel with the ship's health, time warp, gravity and attitude readouts.
+#[derive(Component)]
+pub struct ShipStatusText;
+
+/// The panel with the current alert.
+#[derive(Component)]
+pub struct WarningText;
+
+/// Where on the screen a panel sits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudAnchor {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    /// Centered on the left edge.
+    Left,
+    /// Centered at the top.
+    Top,
+    /// Centered at the bottom, above the scale bar.
+    Bottom,
+}
+
+impl HudAnchor {
+    fn style(self) -> Style {
+        const MARGIN: Val = Val::Px(10.0);
+        let mut style = Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        };
+        match self {
+            HudAnchor::TopLeft => {
+                style.left = MARGIN;
+                style.top = MARGIN;
+            }
+            HudAnchor::TopRight => {
+                style.right = MARGIN;
+                style.top = MARGIN;
+            }
+            HudAnchor::BottomRight => {
+                style.right = MARGIN;
+                style.bottom = MARGIN;
+            }
+            HudAnchor::Left => {
+                style.left = MARGIN;
+                style.top = Val::Px(0.0);
+                style.bottom = Val::Px(0.0);
+                style.align_items = AlignItems::Center;
+            }
+            HudAnchor::Top => {
+                style.left = Val::Px(0.0);
+                style.right = Val::Px(0.0);
+                style.top = MARGIN;
+                style.justify_content = JustifyContent::Center;
+            }
+            HudAnchor::Bottom => {
+                style.left = Val::Px(0.0);
+                style.right = Val::Px(0.0);
+                style.bottom = Val::Px(50.0);
+                style.justify_content = JustifyContent::Center;
+            }
+        }
+        style
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudPanel {
+    Orbit,
+    Ship,
+    Warnings,
+    Tutorial,
+    DeltaV,
+    Inspector,
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct HudLayout {
+    pub orbit: HudAnchor,
+    pub ship: HudAnchor,
+    pub warnings: HudAnchor,
+    pub tutorial: HudAnchor,
+    pub delta_v: HudAnchor,
+    pub inspector: HudAnchor,
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        Self {
+            orbit: HudAnchor::TopLeft,
+            ship: HudAnchor::TopRight,
+            warnings: HudAnchor::Bottom,
+            tutorial: HudAnchor::Top,
+            delta_v: HudAnchor::BottomRight,
+            inspector: HudAnchor::Left,
+        }
+    }
+}
+
+impl HudLayout {
+    pub fn anchor(&self, panel: HudPanel) -> HudAnchor {
+        match panel {
+            HudPanel::Orbit => self.orbit,
+            HudPanel::Ship => self.ship,
+            HudPanel::Warnings => self.warnings,
+            HudPanel::Tutorial => self.tutorial,
+            HudPanel::DeltaV => self.delta_v,
+            HudPanel::Inspector => self.inspector,
+        }
+    }
+}
+
+/// Alternating label and value sections, one line per label. The values start out empty,
+/// the readouts write them by their index, `2 * i + 1` for the `i`th label.
+pub fn readout_sections(labels: &[&str]) -> Vec<TextSection> {
+    let style = TextStyle {
+        font_size: 20.0,
+        color: Color::GRAY,
+        ..default()
+    };
+    labels
+        .iter()
+        .enumerate()
+        .flat_map(|(i, label)| {
+            let label = if i == 0 {
+                format!("{label}: ")
+            } else {
+                format!("\n{label}: ")
+            };
+            [
+                TextSection::new(label, style.clone()),
+                TextSection::from_style(style.clone()),
+            ]
+        })
+        .collect()
+}
+
+pub fn spawn_panel(
+    commands: &mut Commands,
+    panel: HudPanel,
+    labels: &[&str],
+    marker: impl Component,
+) {
+    commands
+        .spawn((NodeBundle::default(), panel))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_sections(readout_sections(labels)), marker));
+        });
+}
+
+pub fn spawn_hud(mut commands: Commands) {
+    spawn_panel(
+        &mut commands,
+        HudPanel::Orbit,
+        &[
+            "Semi Major Axis",
+            "Apoapsis",
+            "Periapsis",
+            "Resonance",
+            "Decay",
+            "Phasing",
+            "Drift",
+            "Velocity",
+            "Snapshot",
+            "SOI",
+            "Challenge",
+            "Plane",
+            "Energy",
+            "Phase",
+            "Methods",
+        ],
+        OrbitText,
+    );
+    spawn_panel(
+        &mut commands,
+        HudPanel::Ship,
+        &[
+            "Health",
+            "Warp",
+            "Gravity",
+            "Snap",
+            "Reference",
+            "Thrust",
+            "Docking",
+            "Collected",
+        ],
+        ShipStatusText,
+    );
+    spawn_panel(
+        &mut commands,
+        HudPanel::Warnings,
+        &["Alert", "Burn", "Orbit", "Message"],
+        WarningText,
+    );
+}
+
+/// Moves the panels to their anchors whenever the layout changes.
+pub fn apply_hud_layout(layout: Res<HudLayout>, mut query: Query<(&mut Style, &HudPanel)>) {
+    if !layout.is_changed() {
+        return;
+    }
+    for (mut style, &panel) in &mut query {
+        *style = layout.anchor(panel).style();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::readout_sections;
+
+    #[test]
+    fn one_line_per_label() {
+        let sections = readout_sections(&["Health", "Warp"]);
+        let text: Vec<_> = sections.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(text, ["Health: ", "", "\nWarp: ", ""]);
+    }
+}
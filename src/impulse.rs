@@ -0,0 +1,298 @@
+//! Instantaneous burns, changing the velocity directly instead of integrating a force
+//! over several frames. This matches how maneuvers are planned with patched conics.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    dominant_attractor,
+    input::InputBindings,
+    orbit::{self, Orbit, OrbitalElements},
+    reference::BodyVelocities,
+    GravityAttractor, Spaceship,
+};
+
+/// Requests an instantaneous change of the ship's velocity by `dv`.
+/// Send it from anything that wants to execute a planned maneuver.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ImpulseBurn {
+    pub dv: Vec3,
+}
+
+/// Changes the velocity by `dv`. This doesn't depend on the mass of the body,
+/// the impulse that's needed for it would be `mass * dv`.
+pub fn apply_impulse_burn(velocity: &mut Velocity, dv: Vec3) {
+    velocity.linvel += dv;
+}
+
+pub fn execute_impulse_burns(
+    mut burns: EventReader<ImpulseBurn>,
+    mut query: Query<&mut Velocity, With<Spaceship>>,
+) {
+    let mut velocity = query.single_mut();
+    for burn in burns.iter() {
+        apply_impulse_burn(&mut velocity, burn.dv);
+    }
+}
+
+/// How fast scheduled burns change the velocity, in units/s². They are applied as a small
+/// impulse every frame, so the burn takes a while like one with the main engine would.
+pub const SCHEDULED_BURN_ACCELERATION: f32 = 2.0;
+
+/// A prograde burn centered on the simulation time `at`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScheduledBurn {
+    /// Elapsed simulation time in seconds.
+    pub at: f64,
+    pub prograde_dv: f32,
+}
+
+impl ScheduledBurn {
+    /// When the burn starts, half of it before `at` and half after.
+    pub fn ignition(&self) -> f64 {
+        self.at - f64::from(self.prograde_dv.abs() / SCHEDULED_BURN_ACCELERATION) / 2.0
+    }
+}
+
+/// The upcoming burns, ordered by time.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct ScheduledBurns(pub Vec<ScheduledBurn>);
+
+impl ScheduledBurns {
+    pub fn schedule(&mut self, burn: ScheduledBurn) {
+        let index = self.0.partition_point(|b| b.at <= burn.at);
+        self.0.insert(index, burn);
+    }
+
+    pub fn next(&self) -> Option<&ScheduledBurn> {
+        self.0.first()
+    }
+}
+
+/// How much of the planned delta-v of a scheduled burn was applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExecutingBurn {
+    pub planned: f32,
+    pub applied: f32,
+    /// -1 for a retrograde burn.
+    pub direction: f32,
+    /// Elapsed simulation time in seconds of the last step, the end once it's complete.
+    pub updated: f64,
+}
+
+impl ExecutingBurn {
+    pub fn remaining(&self) -> f32 {
+        self.planned - self.applied
+    }
+}
+
+/// The last scheduled burn that was started.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct BurnProgress(pub Option<ExecutingBurn>);
+
+pub fn execute_scheduled_burns(
+    time: Res<Time>,
+    mut schedule: ResMut<ScheduledBurns>,
+    mut progress: ResMut<BurnProgress>,
+    query: Query<&Velocity, With<Spaceship>>,
+    mut burns: EventWriter<ImpulseBurn>,
+) {
+    let now = time.elapsed_seconds_f64();
+    let executing = progress.0.is_some_and(|burn| burn.remaining() > 0.0);
+    if let (false, Some(&burn)) = (executing, schedule.next()) {
+        if burn.ignition() <= now {
+            schedule.0.remove(0);
+            progress.0 = Some(ExecutingBurn {
+                planned: burn.prograde_dv.abs(),
+                applied: 0.0,
+                direction: burn.prograde_dv.signum(),
+                updated: now,
+            });
+            info!("Executing scheduled burn of {} prograde", burn.prograde_dv);
+        }
+    }
+
+    let Some(burn) = progress.0.as_mut().filter(|burn| burn.remaining() > 0.0) else {
+        return;
+    };
+    let step = (SCHEDULED_BURN_ACCELERATION * time.delta_seconds()).min(burn.remaining());
+    burn.applied += step;
+    burn.updated = now;
+    let prograde = query.single().linvel.normalize_or_zero();
+    burns.send(ImpulseBurn {
+        dv: prograde * burn.direction * step,
+    });
+}
+
+/// How far ahead [`schedule_debug_burn`] plans its burn, in seconds.
+const DEBUG_BURN_LEAD: f64 = 60.0;
+
+/// F9 schedules a burn of 1 unit/s prograde in a minute.
+pub fn schedule_debug_burn(
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut schedule: ResMut<ScheduledBurns>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        let at = time.elapsed_seconds_f64() + DEBUG_BURN_LEAD;
+        schedule.schedule(ScheduledBurn {
+            at,
+            prograde_dv: 1.0,
+        });
+        info!("Scheduled a burn at {at:.1}s");
+    }
+}
+
+/// Burns 1 unit/s prograde when pressing the debug burn key, B by default.
+pub fn debug_impulse_burn(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    query: Query<&Velocity, With<Spaceship>>,
+    mut burns: EventWriter<ImpulseBurn>,
+) {
+    if keyboard_input.just_pressed(bindings.debug_burn) {
+        let prograde = query.single().linvel.normalize_or_zero();
+        burns.send(ImpulseBurn { dv: prograde });
+    }
+}
+
+/// Circularizes the orbit with the circularize key, C by default, by scheduling a prograde
+/// burn at the next apoapsis up to the circular speed there.
+pub fn auto_circularize(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    time: Res<Time>,
+    mut schedule: ResMut<ScheduledBurns>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+) {
+    if !keyboard_input.just_pressed(bindings.circularize) {
+        return;
+    }
+
+    let (transform, v) = query.single();
+    let Some((body, body_transform, gravity)) =
+        dominant_attractor(&body_query, transform.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let m = gravity.mass;
+    let elements = OrbitalElements::from_state(
+        m,
+        (transform.translation - body_transform.translation).as_dvec3(),
+        (v.linvel - velocities.of(body)).as_dvec3(),
+    );
+    let orbit = Orbit {
+        semi_major_axis: elements.semi_major_axis,
+        eccentricity: elements.eccentricity,
+    };
+    if !orbit.is_closed() {
+        info!("Can only circularize closed orbits");
+        return;
+    }
+
+    let at = time.elapsed_seconds_f64() + elements.time_until(m, std::f64::consts::PI);
+    let prograde_dv = orbit::circularization_dv(m, &orbit) as f32;
+    schedule.schedule(ScheduledBurn { at, prograde_dv });
+    info!("Scheduled a circularization burn of {prograde_dv:.2} at {at:.1}s");
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::*;
+
+    use super::{BurnProgress, ImpulseBurn, ScheduledBurn, ScheduledBurns};
+    use crate::{
+        headless::{self, KeyHold, ShipState},
+        orbit::Orbit,
+        scenario::{Scenario, SMALL_PLANET_DENSITY, SMALL_PLANET_RADIUS},
+        sphere_mass, Spaceship,
+    };
+
+    #[test]
+    fn impulse_changes_velocity_by_dv() {
+        let mut app = App::new();
+        app.add_event::<ImpulseBurn>()
+            .add_systems(Update, super::execute_impulse_burns);
+
+        // the mass must not matter, the change in velocity is given directly
+        let ship = app
+            .world
+            .spawn((
+                Spaceship,
+                Velocity::linear(Vec3::new(1.0, 2.0, 3.0)),
+                AdditionalMassProperties::Mass(250.0),
+            ))
+            .id();
+
+        app.world.send_event(ImpulseBurn {
+            dv: Vec3::new(2.0, 0.0, -1.0),
+        });
+        app.update();
+
+        let velocity = app.world.get::<Velocity>(ship).unwrap();
+        assert_eq!(velocity.linvel, Vec3::new(3.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn scheduled_burn_takes_a_while() {
+        let mut app = headless::headless_app(Scenario::CircularOrbit);
+        app.update();
+        let now = app.world.resource::<Time>().elapsed_seconds_f64();
+        app.world
+            .resource_mut::<ScheduledBurns>()
+            .schedule(ScheduledBurn {
+                at: now,
+                prograde_dv: 1.0,
+            });
+
+        let progress = |app: &mut App, ticks| {
+            headless::run_app(app, &[], ticks);
+            app.world.resource::<BurnProgress>().0.unwrap()
+        };
+        // half a second at 2 units/s², half of it done after a quarter of a second
+        let burn = progress(&mut app, 15);
+        assert!((burn.applied - 0.5).abs() < 0.05, "{burn:?}");
+        let burn = progress(&mut app, 30);
+        assert_eq!(burn.remaining(), 0.0);
+        assert_eq!(burn.applied, 1.0);
+    }
+
+    #[test]
+    fn circularize_at_apoapsis() {
+        let mass = sphere_mass(SMALL_PLANET_RADIUS, SMALL_PLANET_DENSITY);
+        let eccentricity = |state: ShipState| {
+            Orbit::from_pos_dir_3d(mass, state.position.as_dvec3(), state.velocity.as_dvec3())
+                .eccentricity
+        };
+
+        let burn = |key, tick| KeyHold {
+            key,
+            ticks: tick..tick + 1,
+        };
+        // half a revolution of the low orbit is reached after about 2070 ticks
+        let inputs = [
+            burn(KeyCode::B, 1),
+            burn(KeyCode::B, 2),
+            burn(KeyCode::C, 4),
+        ];
+        let eccentric = headless::run_headless(Scenario::CircularOrbit, &inputs[..2], 4);
+        let circularized = headless::run_headless(Scenario::CircularOrbit, &inputs, 2400);
+
+        assert!(
+            eccentricity(eccentric) > 0.02,
+            "{}",
+            eccentricity(eccentric)
+        );
+        assert!(
+            eccentricity(circularized) < 0.002,
+            "{}",
+            eccentricity(circularized)
+        );
+    }
+}
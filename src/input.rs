@@ -0,0 +1,299 @@
+//! Which keys control the ship, with presets for common keyboard layouts.
+
+use bevy::prelude::*;
+
+/// The keys for every ship control. Bevy key codes follow the logical layout, so the
+/// letter keys move around on layouts other than QWERTY, see [`LayoutProfile`].
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputBindings {
+    pub thrust: KeyCode,
+    pub throttle_up: KeyCode,
+    pub throttle_down: KeyCode,
+    pub pitch_up: KeyCode,
+    pub pitch_down: KeyCode,
+    pub roll_left: KeyCode,
+    pub roll_right: KeyCode,
+    pub yaw_left: KeyCode,
+    pub yaw_right: KeyCode,
+    pub gimbal_up: KeyCode,
+    pub gimbal_down: KeyCode,
+    pub gimbal_left: KeyCode,
+    pub gimbal_right: KeyCode,
+    pub liftoff: KeyCode,
+    pub debug_burn: KeyCode,
+    pub circularize: KeyCode,
+    pub snap: KeyCode,
+    pub snap_frame: KeyCode,
+    pub stage: KeyCode,
+    pub warp_up: KeyCode,
+    pub warp_down: KeyCode,
+    pub freeze_rotation: KeyCode,
+    pub orbit_frame: KeyCode,
+    pub ascent: KeyCode,
+    pub snapshot: KeyCode,
+    pub export_svg: KeyCode,
+    pub camera_frame: KeyCode,
+    pub collider_debug: KeyCode,
+    pub flat_spin: KeyCode,
+    pub reference_body: KeyCode,
+    pub screenshot: KeyCode,
+    pub mass_up: KeyCode,
+    pub mass_down: KeyCode,
+    pub spiral_prediction: KeyCode,
+    pub orbit_method: KeyCode,
+}
+
+impl InputBindings {
+    /// Every bound key, in the order of the fields.
+    pub fn keys(&self) -> [KeyCode; 35] {
+        [
+            self.thrust,
+            self.throttle_up,
+            self.throttle_down,
+            self.pitch_up,
+            self.pitch_down,
+            self.roll_left,
+            self.roll_right,
+            self.yaw_left,
+            self.yaw_right,
+            self.gimbal_up,
+            self.gimbal_down,
+            self.gimbal_left,
+            self.gimbal_right,
+            self.liftoff,
+            self.debug_burn,
+            self.circularize,
+            self.snap,
+            self.snap_frame,
+            self.stage,
+            self.warp_up,
+            self.warp_down,
+            self.freeze_rotation,
+            self.orbit_frame,
+            self.ascent,
+            self.snapshot,
+            self.export_svg,
+            self.camera_frame,
+            self.collider_debug,
+            self.flat_spin,
+            self.reference_body,
+            self.screenshot,
+            self.mass_up,
+            self.mass_down,
+            self.spiral_prediction,
+            self.orbit_method,
+        ]
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        LayoutProfile::Qwerty.bindings().unwrap()
+    }
+}
+
+/// A preset for [`InputBindings`] that keeps the controls in the same physical place.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutProfile {
+    #[default]
+    Qwerty,
+    /// WASD becomes ZQSD.
+    Azerty,
+    Dvorak,
+    /// The current bindings, whatever they are.
+    Custom,
+}
+
+impl LayoutProfile {
+    /// The bindings of the preset, `None` for [`LayoutProfile::Custom`].
+    pub fn bindings(self) -> Option<InputBindings> {
+        let qwerty = InputBindings {
+            thrust: KeyCode::Space,
+            throttle_up: KeyCode::ShiftLeft,
+            throttle_down: KeyCode::ControlLeft,
+            pitch_up: KeyCode::W,
+            pitch_down: KeyCode::S,
+            roll_left: KeyCode::Q,
+            roll_right: KeyCode::E,
+            yaw_left: KeyCode::A,
+            yaw_right: KeyCode::D,
+            gimbal_up: KeyCode::Up,
+            gimbal_down: KeyCode::Down,
+            gimbal_left: KeyCode::Left,
+            gimbal_right: KeyCode::Right,
+            liftoff: KeyCode::L,
+            debug_burn: KeyCode::B,
+            circularize: KeyCode::C,
+            snap: KeyCode::R,
+            snap_frame: KeyCode::T,
+            stage: KeyCode::G,
+            warp_up: KeyCode::Period,
+            warp_down: KeyCode::Comma,
+            freeze_rotation: KeyCode::K,
+            orbit_frame: KeyCode::O,
+            ascent: KeyCode::U,
+            snapshot: KeyCode::M,
+            export_svg: KeyCode::X,
+            camera_frame: KeyCode::V,
+            collider_debug: KeyCode::H,
+            flat_spin: KeyCode::F,
+            reference_body: KeyCode::N,
+            screenshot: KeyCode::P,
+            mass_up: KeyCode::BracketRight,
+            mass_down: KeyCode::BracketLeft,
+            spiral_prediction: KeyCode::J,
+            orbit_method: KeyCode::I,
+        };
+
+        match self {
+            LayoutProfile::Qwerty => Some(qwerty),
+            LayoutProfile::Azerty => Some(InputBindings {
+                pitch_up: KeyCode::Z,
+                roll_left: KeyCode::A,
+                yaw_left: KeyCode::Q,
+                warp_up: KeyCode::Colon,
+                warp_down: KeyCode::Semicolon,
+                // the brackets need AltGr, these are unshifted next to Backspace and Enter
+                mass_up: KeyCode::Equals,
+                mass_down: KeyCode::Asterisk,
+                ..qwerty
+            }),
+            LayoutProfile::Dvorak => Some(InputBindings {
+                pitch_up: KeyCode::Comma,
+                pitch_down: KeyCode::O,
+                roll_left: KeyCode::Apostrophe,
+                roll_right: KeyCode::Period,
+                yaw_left: KeyCode::A,
+                yaw_right: KeyCode::E,
+                liftoff: KeyCode::N,
+                debug_burn: KeyCode::X,
+                circularize: KeyCode::J,
+                snap: KeyCode::P,
+                snap_frame: KeyCode::Y,
+                stage: KeyCode::I,
+                warp_up: KeyCode::V,
+                warp_down: KeyCode::W,
+                freeze_rotation: KeyCode::T,
+                orbit_frame: KeyCode::R,
+                ascent: KeyCode::G,
+                export_svg: KeyCode::Q,
+                camera_frame: KeyCode::K,
+                collider_debug: KeyCode::D,
+                flat_spin: KeyCode::U,
+                reference_body: KeyCode::B,
+                screenshot: KeyCode::L,
+                mass_up: KeyCode::Equals,
+                mass_down: KeyCode::Slash,
+                spiral_prediction: KeyCode::H,
+                orbit_method: KeyCode::C,
+                ..qwerty
+            }),
+            LayoutProfile::Custom => None,
+        }
+    }
+
+    /// `--layout=<qwerty|azerty|dvorak>` picks the preset to start with.
+    pub fn from_args() -> Self {
+        let arg = std::env::args().find_map(|arg| arg.strip_prefix("--layout=").map(str::to_owned));
+        match arg.as_deref() {
+            None | Some("qwerty") => LayoutProfile::Qwerty,
+            Some("azerty") => LayoutProfile::Azerty,
+            Some("dvorak") => LayoutProfile::Dvorak,
+            Some(other) => {
+                warn!("Unknown keyboard layout `{other}`, using QWERTY");
+                LayoutProfile::Qwerty
+            }
+        }
+    }
+
+    /// All presets, without [`LayoutProfile::Custom`].
+    pub const PRESETS: [LayoutProfile; 3] = [
+        LayoutProfile::Qwerty,
+        LayoutProfile::Azerty,
+        LayoutProfile::Dvorak,
+    ];
+
+    /// The next preset, skipping [`LayoutProfile::Custom`].
+    pub fn next(self) -> Self {
+        match self {
+            LayoutProfile::Qwerty => LayoutProfile::Azerty,
+            LayoutProfile::Azerty => LayoutProfile::Dvorak,
+            LayoutProfile::Dvorak | LayoutProfile::Custom => LayoutProfile::Qwerty,
+        }
+    }
+}
+
+/// F8 cycles through the layout presets.
+pub fn cycle_layout_profile(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut profile: ResMut<LayoutProfile>,
+    mut bindings: ResMut<InputBindings>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F8) {
+        return;
+    }
+
+    *profile = profile.next();
+    if let Some(preset) = profile.bindings() {
+        *bindings = preset;
+    }
+    info!("Keyboard layout: {:?}", *profile);
+}
+
+/// Bindings that don't match the preset anymore have been changed by hand.
+pub fn detect_custom_bindings(bindings: Res<InputBindings>, mut profile: ResMut<LayoutProfile>) {
+    if !bindings.is_changed() {
+        return;
+    }
+    if let Some(preset) = profile.bindings() {
+        if preset != *bindings {
+            *profile = LayoutProfile::Custom;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::KeyCode;
+
+    use super::{InputBindings, LayoutProfile};
+
+    #[test]
+    fn azerty_uses_zqsd() {
+        let bindings = LayoutProfile::Azerty.bindings().unwrap();
+        assert_eq!(
+            [
+                bindings.pitch_up,
+                bindings.yaw_left,
+                bindings.pitch_down,
+                bindings.yaw_right
+            ],
+            [KeyCode::Z, KeyCode::Q, KeyCode::S, KeyCode::D]
+        );
+        assert_eq!(bindings.roll_left, KeyCode::A);
+        assert_eq!(bindings.thrust, KeyCode::Space);
+    }
+
+    #[test]
+    fn dvorak_keeps_physical_positions() {
+        let bindings = LayoutProfile::Dvorak.bindings().unwrap();
+        assert_eq!(
+            [
+                bindings.pitch_up,
+                bindings.yaw_left,
+                bindings.pitch_down,
+                bindings.yaw_right
+            ],
+            [KeyCode::Comma, KeyCode::A, KeyCode::O, KeyCode::E]
+        );
+    }
+
+    #[test]
+    fn custom_keeps_bindings() {
+        assert_eq!(LayoutProfile::Custom.bindings(), None);
+        assert_eq!(
+            LayoutProfile::Qwerty.bindings(),
+            Some(InputBindings::default())
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Clicking on any body to inspect it: its mass, size, orbit, velocity and sphere of
+//! influence, live in a panel until something else or empty space is clicked.
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    dominant_attractor,
+    hud::{self, HudPanel},
+    mass_of,
+    orbit::OrbitalElements,
+    rails::OnRails,
+    soi::soi_radius,
+    units::HudUnits,
+    GravityAttractor, Planet, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct BodyInspector {
+    pub enabled: bool,
+    /// The left button already picks landing targets.
+    pub button: MouseButton,
+}
+
+impl Default for BodyInspector {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            button: MouseButton::Right,
+        }
+    }
+}
+
+/// The body shown in the inspector panel.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectedBody(pub Option<Entity>);
+
+#[derive(Component)]
+pub struct InspectorText;
+
+type BodyItem<'a> = (
+    Entity,
+    &'a Transform,
+    Option<&'a Velocity>,
+    Option<&'a GravityAttractor>,
+    Option<&'a ReadMassProperties>,
+    Option<&'a Planet>,
+    Option<&'a OnRails>,
+    Option<&'a Spaceship>,
+);
+
+pub fn spawn_inspector_panel(mut commands: Commands, inspector: Res<BodyInspector>) {
+    if inspector.enabled {
+        hud::spawn_panel(
+            &mut commands,
+            HudPanel::Inspector,
+            &["Body", "Mass", "Radius", "Orbit", "Velocity", "SOI"],
+            InspectorText,
+        );
+    }
+}
+
+/// Selects the body under the cursor, or nothing when clicking into empty space.
+pub fn pick_body(
+    inspector: Res<BodyInspector>,
+    mouse_input: Res<Input<MouseButton>>,
+    rapier_context: Res<RapierContext>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut selected: ResMut<SelectedBody>,
+) {
+    if !inspector.enabled || !mouse_input.just_pressed(inspector.button) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) =
+        (window_query.get_single(), camera_query.get_single())
+    else {
+        return;
+    };
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    let hit = rapier_context.cast_ray(
+        ray.origin,
+        ray.direction,
+        f32::MAX,
+        true,
+        QueryFilter::default(),
+    );
+    selected.0 = hit.map(|(entity, _)| entity);
+}
+
+/// The velocity of a body, from its rigid body or from its rails around `parent_mass`.
+pub fn velocity_of(
+    velocity: Option<&Velocity>,
+    rails: Option<&OnRails>,
+    parent_mass: f64,
+    now: f64,
+) -> Vec3 {
+    match (rails, velocity) {
+        (Some(rails), _) => rails.state(parent_mass, now).1.as_vec3(),
+        (None, Some(velocity)) => velocity.linvel,
+        (None, None) => Vec3::ZERO,
+    }
+}
+
+pub fn update_inspector(
+    units: HudUnits,
+    time: Res<Time>,
+    mut selected: ResMut<SelectedBody>,
+    body_query: Query<BodyItem<'_>>,
+    mut text_query: Query<&mut Text, With<InspectorText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    if selected
+        .0
+        .is_some_and(|entity| !body_query.contains(entity))
+    {
+        selected.0 = None;
+    }
+    let Some((entity, transform, velocity, gravity, mass, planet, rails, ship)) =
+        selected.0.and_then(|entity| body_query.get(entity).ok())
+    else {
+        text.sections[1].value = "none".to_owned();
+        for section in [3, 5, 7, 9, 11] {
+            text.sections[section].value = "-".to_owned();
+        }
+        return;
+    };
+
+    let now = time.elapsed_seconds_f64();
+    let own_mass = gravity.map_or_else(|| f64::from(mass_of(mass)), |g| g.mass);
+    let mass_of_body = |entity| {
+        body_query
+            .get(entity)
+            .ok()
+            .and_then(|(_, _, _, g, ..)| g)
+            .map_or(0.0, |g| g.mass)
+    };
+
+    // on rails they orbit their parent, everything else the body pulling the hardest on it
+    let parent = match rails {
+        Some(rails) => body_query.get(rails.parent).ok(),
+        None => dominant_attractor(
+            body_query
+                .iter()
+                .filter(|&(other, _, _, g, ..)| other != entity && g.is_some()),
+            transform.translation,
+            |&(_, t, _, g, ..)| (t.translation, g.map_or(0.0, |g| g.mass)),
+        ),
+    };
+    let own_velocity = velocity_of(
+        velocity,
+        rails,
+        rails.map_or(0.0, |r| mass_of_body(r.parent)),
+        now,
+    );
+
+    text.sections[1].value = match (ship, planet) {
+        (Some(_), _) => format!("ship {entity:?}"),
+        (None, Some(_)) => format!("planet {entity:?}"),
+        (None, None) => format!("{entity:?}"),
+    };
+    text.sections[3].value = format!("{own_mass:.3e}");
+    text.sections[5].value = planet.map_or_else(|| "-".to_owned(), |p| units.distance(p.radius));
+    text.sections[9].value = units.speed(f64::from(own_velocity.length()));
+
+    let Some((
+        parent,
+        parent_transform,
+        parent_velocity,
+        Some(parent_gravity),
+        _,
+        _,
+        parent_rails,
+        _,
+    )) = parent
+    else {
+        text.sections[7].value = "-".to_owned();
+        text.sections[11].value = if gravity.is_some() {
+            "unbounded".to_owned()
+        } else {
+            "-".to_owned()
+        };
+        return;
+    };
+
+    let parent_velocity = velocity_of(
+        parent_velocity,
+        parent_rails,
+        parent_rails.map_or(0.0, |r| mass_of_body(r.parent)),
+        now,
+    );
+    let elements = OrbitalElements::from_state(
+        parent_gravity.mass,
+        (transform.translation - parent_transform.translation).as_dvec3(),
+        (own_velocity - parent_velocity).as_dvec3(),
+    );
+    text.sections[7].value = format!(
+        "a {} e {:.3} i {:.1}° around {parent:?}",
+        units.distance(elements.semi_major_axis),
+        elements.eccentricity,
+        elements.inclination.to_degrees(),
+    );
+    text.sections[11].value = if gravity.is_some() && elements.eccentricity < 1.0 {
+        units.distance(soi_radius(
+            elements.semi_major_axis,
+            own_mass,
+            parent_gravity.mass,
+        ))
+    } else {
+        "-".to_owned()
+    };
+}
@@ -0,0 +1,194 @@
+//! Sticky landings: touching down slowly clamps the ship to the surface instead of
+//! letting it bounce around on the restitution.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{input::InputBindings, GravityAttractor, Spaceship};
+
+#[derive(Resource)]
+pub struct StickyLanding {
+    pub enabled: bool,
+    /// The highest speed relative to the surface that still counts as a landing.
+    pub safe_speed: f32,
+}
+
+impl Default for StickyLanding {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            safe_speed: 2.0,
+        }
+    }
+}
+
+/// The ship is clamped to the body it landed on by a fixed joint.
+#[derive(Component)]
+pub struct Landed;
+
+pub fn toggle_sticky_landing(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<StickyLanding>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F4) {
+        config.enabled = !config.enabled;
+        info!("Sticky landing: {}", config.enabled);
+    }
+}
+
+pub fn sticky_landing(
+    mut commands: Commands,
+    config: Res<StickyLanding>,
+    mut collisions: EventReader<CollisionEvent>,
+    ship_query: Query<(Entity, &Transform, &Velocity), (With<Spaceship>, Without<Landed>)>,
+    body_query: Query<(&Transform, Option<&Velocity>), With<GravityAttractor>>,
+    // the velocity before this frame's collisions, as the contact already slowed the ship down
+    mut last_velocity: Local<Option<Velocity>>,
+) {
+    let Ok((ship, ship_transform, &velocity)) = ship_query.get_single() else {
+        collisions.clear();
+        *last_velocity = None;
+        return;
+    };
+    let impact_velocity = last_velocity.replace(velocity).unwrap_or(velocity);
+
+    if !config.enabled {
+        collisions.clear();
+        return;
+    }
+
+    for collision in collisions.iter() {
+        let &CollisionEvent::Started(e1, e2, _) = collision else {
+            continue;
+        };
+        let body = match (e1, e2) {
+            (e, body) | (body, e) if e == ship => body,
+            _ => continue,
+        };
+        let Ok((body_transform, body_velocity)) = body_query.get(body) else {
+            continue;
+        };
+
+        // a spinning body drags its surface along
+        let surface_velocity = body_velocity.map_or(Vec3::ZERO, |v| {
+            v.linvel
+                + v.angvel
+                    .cross(ship_transform.translation - body_transform.translation)
+        });
+        let speed = (impact_velocity.linvel - surface_velocity).length();
+        if speed > config.safe_speed {
+            info!("Touched down too fast to land: {speed:.2}");
+            continue;
+        }
+
+        // freeze the current pose relative to the body
+        let inverse_body_rotation = body_transform.rotation.inverse();
+        let joint = FixedJointBuilder::new()
+            .local_anchor1(
+                inverse_body_rotation * (ship_transform.translation - body_transform.translation),
+            )
+            .local_basis1(inverse_body_rotation * ship_transform.rotation);
+
+        commands
+            .entity(ship)
+            .insert((ImpulseJoint::new(body, joint), Landed));
+        info!("Landed at {speed:.2}");
+        break;
+    }
+}
+
+pub fn liftoff(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    query: Query<Entity, (With<Spaceship>, With<Landed>)>,
+) {
+    if !keyboard_input.just_pressed(bindings.liftoff) {
+        return;
+    }
+
+    if let Ok(ship) = query.get_single() {
+        commands.entity(ship).remove::<(ImpulseJoint, Landed)>();
+        info!("Liftoff");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{prelude::*, scene::ScenePlugin, transform::TransformPlugin};
+    use bevy_rapier3d::prelude::*;
+
+    use super::{Landed, StickyLanding};
+    use crate::{GravityAttractor, Spaceship};
+
+    #[test]
+    fn landed_ship_corotates() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+            RapierPhysicsPlugin::<NoUserData>::default(),
+        ))
+        .add_asset::<Mesh>()
+        .insert_resource(RapierConfiguration {
+            gravity: Vec3::ZERO,
+            timestep_mode: TimestepMode::Fixed {
+                dt: 1.0 / 60.0,
+                substeps: 1,
+            },
+            ..default()
+        })
+        .insert_resource(StickyLanding {
+            enabled: true,
+            ..default()
+        })
+        .add_systems(Update, super::sticky_landing);
+
+        let planet = app
+            .world
+            .spawn((
+                TransformBundle::default(),
+                RigidBody::KinematicPositionBased,
+                Collider::ball(10.0),
+                GravityAttractor { mass: 1.0 },
+            ))
+            .id();
+        let ship = app
+            .world
+            .spawn((
+                Spaceship,
+                TransformBundle::from_transform(Transform::from_xyz(0.0, 10.6, 0.0)),
+                RigidBody::Dynamic,
+                Collider::cuboid(0.5, 0.5, 0.5),
+                ActiveEvents::COLLISION_EVENTS,
+                Velocity::linear(Vec3::new(0.0, -1.0, 0.0)),
+            ))
+            .id();
+
+        for _ in 0..30 {
+            app.update();
+        }
+        assert!(app.world.get::<Landed>(ship).is_some(), "ship didn't land");
+
+        // turn the planet by a quarter around the Z axis
+        let steps = 60;
+        for i in 1..=steps {
+            let angle = std::f32::consts::FRAC_PI_2 * (i as f32) / (steps as f32);
+            app.world.get_mut::<Transform>(planet).unwrap().rotation = Quat::from_rotation_z(angle);
+            app.update();
+        }
+        for _ in 0..30 {
+            app.update();
+        }
+
+        let ship_pos = app.world.get::<Transform>(ship).unwrap().translation;
+        let direction = ship_pos.normalize();
+        assert!(
+            direction.distance(Vec3::new(-1.0, 0.0, 0.0)) < 0.05,
+            "ship at {ship_pos} didn't rotate with the planet"
+        );
+    }
+}
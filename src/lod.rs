@@ -0,0 +1,80 @@
+//! Swapping planet meshes for less detailed ones when they are far away from the camera.
+
+use bevy::prelude::*;
+
+/// Meshes of different detail, starting with the most detailed one. Each level is used
+/// from its distance to the camera onwards, until the distance of the next level.
+#[derive(Component)]
+pub struct PlanetLod {
+    pub levels: Vec<(f32, Handle<Mesh>)>,
+    pub current: usize,
+}
+
+/// How far past a level boundary the camera has to go before switching, relative to
+/// the boundary distance. Without this, the mesh flickers when sitting right at it.
+pub const LOD_HYSTERESIS: f32 = 0.1;
+
+/// Picks the level for `distance`, sticking with `current` if it's still within the band.
+pub fn select_lod_level(thresholds: &[f32], current: usize, distance: f32) -> usize {
+    let mut level = current.min(thresholds.len().saturating_sub(1));
+
+    while level + 1 < thresholds.len() && distance > thresholds[level + 1] * (1.0 + LOD_HYSTERESIS)
+    {
+        level += 1;
+    }
+    while level > 0 && distance < thresholds[level] * (1.0 - LOD_HYSTERESIS) {
+        level -= 1;
+    }
+
+    level
+}
+
+pub fn update_planet_lod(
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+    mut planet_query: Query<(&GlobalTransform, &mut PlanetLod, &mut Handle<Mesh>)>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+
+    for (transform, mut lod, mut mesh) in &mut planet_query {
+        let distance = camera.translation().distance(transform.translation());
+        let thresholds = lod.levels.iter().map(|&(d, _)| d).collect::<Vec<_>>();
+
+        let level = select_lod_level(&thresholds, lod.current, distance);
+        if level != lod.current {
+            lod.current = level;
+            *mesh = lod.levels[level].1.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select_lod_level;
+
+    const THRESHOLDS: &[f32] = &[0.0, 1000.0, 5000.0];
+
+    #[test]
+    fn picks_level_by_distance() {
+        assert_eq!(select_lod_level(THRESHOLDS, 0, 500.0), 0);
+        assert_eq!(select_lod_level(THRESHOLDS, 0, 2000.0), 1);
+        assert_eq!(select_lod_level(THRESHOLDS, 0, 10000.0), 2);
+        assert_eq!(select_lod_level(THRESHOLDS, 2, 10.0), 0);
+    }
+
+    #[test]
+    fn hysteresis() {
+        // just past the boundary, but within the band
+        assert_eq!(select_lod_level(THRESHOLDS, 0, 1050.0), 0);
+        assert_eq!(select_lod_level(THRESHOLDS, 1, 950.0), 1);
+        // through the band
+        assert_eq!(select_lod_level(THRESHOLDS, 0, 1150.0), 1);
+        assert_eq!(select_lod_level(THRESHOLDS, 1, 850.0), 0);
+    }
+
+    #[test]
+    fn single_level() {
+        assert_eq!(select_lod_level(&[0.0], 0, 1e9), 0);
+    }
+}
@@ -0,0 +1,135 @@
+//! The ship's velocity split up in the local vertical, local horizontal (LVLH) frame:
+//! radially away from the attractor, along the track and across the orbital plane.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    forces::ExternalForceSet,
+    formation::lvlh_basis,
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    units::HudUnits,
+    GravityAttractor, OrbitText, Spaceship, ThrusterForce,
+};
+
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct LvlhReadout {
+    /// Also draws the components at the ship, radial in blue, along-track in green and
+    /// cross-track in pink. Turned on with `--lvlh-gizmos`.
+    pub gizmos: bool,
+    /// The normal of the orbital plane from the last time the engine was off.
+    /// The plane follows the velocity, so measuring against the current one would always
+    /// give no cross-track velocity at all. Against this one, it's what a burn added.
+    pub reference_normal: Option<Vec3>,
+}
+
+impl LvlhReadout {
+    pub fn from_args() -> Self {
+        Self {
+            gizmos: std::env::args().any(|arg| arg == "--lvlh-gizmos"),
+            ..default()
+        }
+    }
+}
+
+/// `vel` in the [`lvlh_basis`], as radial, along-track and cross-track speed.
+pub fn lvlh_components(basis: Mat3, vel: Vec3) -> Vec3 {
+    basis.transpose() * vel
+}
+
+pub fn update_lvlh_readout(
+    units: HudUnits,
+    mut readout: ResMut<LvlhReadout>,
+    query: Query<(&Transform, &Velocity, &ExternalForceSet), With<Spaceship>>,
+    reference: Res<ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let Ok((transform, velocity, forces)) = query.get_single() else {
+        return;
+    };
+    let Some((body, body_transform, _)) = reference_attractor(
+        &reference,
+        &body_query,
+        transform.translation,
+        |(_, t, g)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+
+    let pos = transform.translation - body_transform.translation;
+    let vel = velocity.linvel - velocities.of(body);
+    let normal = pos.cross(vel).try_normalize();
+    if forces.get::<ThrusterForce>().force == Vec3::ZERO || readout.reference_normal.is_none() {
+        readout.reference_normal = normal;
+    }
+
+    let Some(basis) = readout
+        .reference_normal
+        // any velocity along the track of that plane spans it
+        .map(|normal| lvlh_basis(pos, normal.cross(pos)))
+        .filter(|basis| basis.z_axis != Vec3::ZERO)
+    else {
+        text.sections[15].value = "-".to_owned();
+        return;
+    };
+    let components = lvlh_components(basis, vel);
+
+    text.sections[15].value = format!(
+        "R {} / T {} / N {}",
+        units.speed_change(f64::from(components.x)),
+        units.speed_change(f64::from(components.y)),
+        units.speed_change(f64::from(components.z)),
+    );
+
+    if readout.gizmos {
+        let colors = [Color::BLUE, Color::GREEN, Color::PINK];
+        let axes = [basis.x_axis, basis.y_axis, basis.z_axis];
+        for ((axis, color), component) in axes.into_iter().zip(colors).zip(components.to_array()) {
+            gizmos.ray(transform.translation, axis * component, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::lvlh_components;
+    use crate::formation::lvlh_basis;
+
+    #[test]
+    fn circular_orbit_is_all_along_track() {
+        let pos = Vec3::new(100.0, 0.0, 0.0);
+        let vel = Vec3::new(0.0, 0.0, -10.0);
+        let basis = lvlh_basis(pos, vel);
+
+        let components = lvlh_components(basis, vel);
+        assert!(
+            components.abs_diff_eq(Vec3::new(0.0, 10.0, 0.0), 1e-5),
+            "{components}"
+        );
+    }
+
+    #[test]
+    fn components_against_old_plane() {
+        let pos = Vec3::new(100.0, 0.0, 0.0);
+        let normal = Vec3::Y;
+        // climbing and pushed out of the equatorial plane by a normal burn
+        let vel = Vec3::new(2.0, 3.0, -10.0);
+        // along the track of the old plane, the current velocity would give the new one
+        let basis = lvlh_basis(pos, normal.cross(pos));
+
+        let components = lvlh_components(basis, vel);
+        assert!(
+            components.abs_diff_eq(Vec3::new(2.0, 10.0, 3.0), 1e-5),
+            "{components}"
+        );
+        assert!((components.length() - vel.length()).abs() < 1e-4);
+    }
+}
@@ -1,36 +1,370 @@
+// Bevy systems and queries are complex by design.
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+mod alerts;
+mod ascent;
+mod atmosphere;
+mod audio;
+mod autopilot;
+mod biomes;
+mod blackout;
+mod burn_guard;
+mod burn_indicator;
+mod camera;
+mod challenge;
+mod collect;
+mod colliders;
+mod decay;
+mod delta_v;
+mod docking;
+mod drift;
+mod elements;
+mod energy;
+mod exhaust;
+mod fill_light;
+mod flat_spin;
 mod forces;
+mod formation;
+mod gamepad;
+mod gravity_gradient;
+mod gravity_readout;
+mod gravity_sheet;
+mod headless;
+mod health;
+mod hud;
+mod impulse;
+mod input;
+mod inspect;
+mod landing;
+mod lod;
+mod lvlh;
+mod nan_guard;
 mod orbit;
+mod orbit_class;
+mod orbit_frame;
+mod orbit_line;
+mod orbit_method;
+mod pause;
+mod phase;
+mod plane_match;
+mod planet_mass;
+mod precision;
+mod predict;
+mod rails;
+mod rcs;
+mod reference;
+mod render_debug;
+mod replay;
+mod resonance;
+mod rotation;
+mod scale_bar;
+mod scenario;
+mod screenshot;
+mod ship_marker;
+mod simulation;
+mod snap;
+mod snapshot;
+mod soi;
+mod stages;
+mod svg;
+mod thrust_vector;
+mod timeline;
+mod tutorial;
+mod units;
+mod warp;
 
 use bevy::{
-    audio::PlaybackMode,
+    asset::LoadState,
+    audio::{AddAudioSource, PlaybackMode},
     input::mouse::{MouseMotion, MouseWheel},
     prelude::*,
+    utils::HashMap,
     window::PrimaryWindow,
 };
 use bevy_rapier3d::prelude::*;
 use forces::ExternalForceSet;
-use glam::DVec2;
+use glam::DVec3;
 
-use crate::forces::update_external_forces;
+use crate::{lod::PlanetLod, scenario::Scenario, simulation::SimulationPlugin};
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+    if let Some(ticks) = headless::ticks_from_args() {
+        // headless apps leave out logging so that the tests can run many of them, it's set up
+        // once here instead, before the warnings about the arguments
+        bevy::log::LogPlugin::default().build(&mut App::new());
+        let ticks = headless::parse_ticks(&ticks);
+        let (scenario, replay) = scenario_from_args();
+        let state = match replay {
+            Some(recording) => headless::run_replay(recording),
+            None => headless::run_headless(scenario, &[], ticks),
+        };
+        println!("{state:#?}");
+        return;
+    }
+
+    let audio = audio::AudioEnabled::from_env();
+    let mut plugins = DefaultPlugins.build();
+    if !audio.0 {
+        // don't even try to open an audio device
+        plugins = plugins.disable::<bevy::audio::AudioPlugin>();
+    }
+
+    let mut app = App::new();
+    // the arguments are only parsed after this, warnings about them would be lost before the
+    // log plugin is set up
+    app.add_plugins(plugins);
+    if audio.0 {
+        app.add_audio_source::<audio::Tone>();
+    }
+
+    let (scenario, replay) = scenario_from_args();
+    let layout = input::LayoutProfile::from_args();
+    let record_path = replay::record_path_from_args();
+    // the keys are recorded per frame, so every frame has to be the same tick when played back
+    let fixed_ticks = replay.is_some() || record_path.is_some();
+    if let Some(recording) = replay {
+        app.insert_resource(replay::Replayer::new(recording))
+            .add_systems(
+                PreUpdate,
+                replay::replay_input.after(bevy::input::InputSystem),
+            );
+    } else if let Some(path) = record_path {
+        app.insert_resource(replay::Recorder {
+            recording: replay::Recording::new(scenario),
+            path: Some(path),
+        })
+        .add_systems(
+            PreUpdate,
+            replay::record_input.after(bevy::input::InputSystem),
+        )
+        .add_systems(Last, replay::save_recording);
+    }
+
+    if let Some(tutorial) = tutorial::Tutorial::from_args() {
+        app.insert_resource(tutorial);
+    }
+    if let Some(sequence) = screenshot::ScreenshotSequence::from_args() {
+        app.insert_resource(sequence);
+    }
+    if let Some(seed) = challenge::ChallengeSeed::from_args() {
+        app.insert_resource(seed);
+    }
+    if let Some(timeline) = timeline::Timeline::from_args() {
+        app.insert_resource(timeline);
+    }
+
+    app.insert_resource(audio)
+        .insert_resource(nan_guard::NanGuard::from_args())
+        .insert_resource(rcs::RcsBalancing::from_args())
+        .insert_resource(collect::MarkerCollection::from_args())
+        .insert_resource(orbit_method::OrbitComputation::from_args())
+        .insert_resource(burn_guard::BurnGuard::from_args())
+        .insert_resource(precision::OrbitPrecision::from_args())
+        .insert_resource(pause::PauseOnFocusLoss::from_args())
+        .insert_resource(biomes::PlanetBiomes::from_args())
+        .insert_resource(gravity_gradient::GravityGradient::from_args())
+        .insert_resource(lvlh::LvlhReadout::from_args())
+        .insert_resource(orbit_line::OrbitLine::from_args())
+        .add_plugins(SimulationPlugin)
         // .add_plugins(RapierDebugRenderPlugin::default())
-        .add_systems(Startup, setup)
+        .add_plugins(MaterialPlugin::<render_debug::NormalsMaterial>::default())
+        .init_resource::<predict::Rk4Prediction>()
+        .init_resource::<predict::PredictionLimits>()
+        .init_resource::<render_debug::RenderMode>()
+        .init_resource::<render_debug::GizmoStyle>()
+        .init_resource::<alerts::Alerts>()
+        .init_resource::<blackout::Blackout>()
+        .init_resource::<scale_bar::ScaleBar>()
+        .init_resource::<hud::HudLayout>()
+        .init_resource::<gamepad::GamepadControls>()
+        .add_event::<alerts::RaiseAlert>()
+        .init_resource::<decay::OrbitDecay>()
+        .init_resource::<CameraSettings>()
+        .init_resource::<OrbitBreadcrumb>()
+        .init_resource::<orbit_frame::OrbitFrame>()
+        .init_resource::<delta_v::DeltaVMap>()
+        .init_resource::<ship_marker::ShipMarker>()
+        .init_resource::<colliders::ColliderDebug>()
+        .init_resource::<soi::SoiCountdown>()
+        .init_resource::<fill_light::PlanetFillLight>()
+        .init_resource::<reference::ReferenceBody>()
+        .init_resource::<thrust_vector::ThrustVector>()
+        .init_resource::<burn_indicator::BurnIndicator>()
+        .init_resource::<planet_mass::MassControl>()
+        .init_resource::<inspect::BodyInspector>()
+        .init_resource::<inspect::SelectedBody>()
+        .init_resource::<plane_match::PlaneMatchHelper>()
+        .init_resource::<orbit_class::OrbitClassifier>()
+        .init_resource::<docking::DockingAid>()
+        .init_resource::<energy::EnergyTrend>()
+        .init_resource::<phase::PhaseAngle>()
+        .init_resource::<collect::MarkerScore>()
+        .init_resource::<camera::CameraFrame>()
+        .init_resource::<camera::CameraMode>()
+        .init_resource::<drift::PredictionLock>()
+        .init_resource::<units::DisplayUnits>()
+        .init_resource::<units::WorldScale>()
+        .init_resource::<gravity_sheet::GravitySheet>()
+        .init_resource::<gravity_readout::GravityReadout>()
+        .init_resource::<exhaust::ExhaustSplash>()
+        .init_resource::<exhaust::DustRingMesh>()
+        .init_resource::<warp::TimeWarp>()
+        .init_resource::<warp::AutoWarp>()
+        .insert_resource(layout)
+        .insert_resource(layout.bindings().unwrap())
+        .insert_resource(scenario)
+        .insert_resource(elements::ImportedOrbit::from_args())
+        .add_systems(
+            Startup,
+            (
+                setup,
+                gravity_sheet::spawn_gravity_sheet,
+                blackout::spawn_blackout_overlay,
+                scale_bar::spawn_scale_bar,
+                hud::spawn_hud,
+                tutorial::spawn_tutorial_panel,
+                orbit_frame::spawn_frame_labels,
+                delta_v::spawn_delta_v_panel,
+                inspect::spawn_inspector_panel,
+            ),
+        )
+        // the ship spawned in `setup` only exists once its commands are applied after Startup
+        .add_systems(PostStartup, elements::import_orbital_elements)
+        .add_systems(
+            Update,
+            (
+                gamepad::read_gamepad.before(fire_thrusters),
+                tutorial::update_tutorial,
+                lvlh::update_lvlh_readout,
+                orbit_line::draw_orbit_line,
+                delta_v::update_delta_v_map,
+                ship_marker::draw_ship_marker,
+                svg::export_orbit_svg_on_key,
+                soi::update_soi_countdown,
+                flat_spin::toggle_flat_spin,
+                reference::select_reference_body,
+                thrust_vector::draw_thrust_vector,
+                burn_indicator::update_burn_indicator,
+                // the same frame, before anything uses the mass
+                planet_mass::adjust_mass.before(rails::move_on_rails),
+                (
+                    (challenge::start_challenge, challenge::update_challenge).chain(),
+                    timeline::run_timeline,
+                    (collect::attach_marker_colliders, collect::collect_markers).chain(),
+                ),
+                (
+                    screenshot::take_screenshot_on_key,
+                    screenshot::take_screenshot_sequence,
+                ),
+                (fill_light::spawn_fill_lights, fill_light::place_fill_lights).chain(),
+                (
+                    colliders::toggle_collider_debug,
+                    colliders::draw_collider_shapes,
+                    colliders::update_collider_labels,
+                )
+                    .chain(),
+                (snapshot::take_orbit_snapshot, snapshot::draw_orbit_snapshot).chain(),
+                (
+                    orbit_frame::toggle_orbit_frame,
+                    orbit_frame::draw_orbit_frame,
+                )
+                    .chain(),
+            ),
+        )
         .add_systems(
             Update,
             (
-                update_external_forces,
-                fire_thrusters,
-                orbit_camera,
-                apply_gravity.before(update_external_forces),
-                debug_spaceship_orbit,
+                (
+                    camera::toggle_camera_mode,
+                    camera::toggle_camera_frame,
+                    orbit_camera,
+                    camera::fixed_camera,
+                )
+                    .chain(),
+                (
+                    predict::toggle_thrust_prediction,
+                    predict::draw_rk4_prediction,
+                )
+                    .chain(),
+                predict::cycle_prediction_orbits,
+                render_debug::cycle_render_mode,
+                (render_debug::toggle_ship_axes, render_debug::draw_ship_axes).chain(),
+                elements::export_orbital_elements,
+                gravity_sheet::toggle_gravity_sheet,
+                gravity_sheet::draw_gravity_sheet.after(gravity_sheet::toggle_gravity_sheet),
+                input::cycle_layout_profile,
+                input::detect_custom_bindings.after(input::cycle_layout_profile),
+                autopilot::pick_landing_target,
+                (warp::change_time_warp, warp::apply_time_warp).chain(),
+                update_thruster_flame,
+                (exhaust::spawn_exhaust_splash, exhaust::update_dust_rings),
+                lod::update_planet_lod,
+                biomes::bake_planet_biomes,
+                units::toggle_display_units,
+                drift::toggle_prediction_lock,
+                pause::pause_on_focus_loss,
                 bevy::window::close_on_esc,
             ),
         )
-        .run();
+        // the HUD
+        .add_systems(
+            Update,
+            (
+                (
+                    orbit_method::toggle_orbit_method,
+                    debug_spaceship_orbit,
+                    orbit_method::update_orbit_comparison,
+                )
+                    .chain(),
+                debug_resonance,
+                debug_phasing,
+                gravity_readout::update_gravity_text.after(apply_gravity),
+                snap::update_snap_text,
+                (alerts::check_orbit_alerts, alerts::check_proximity)
+                    .before(alerts::process_alerts),
+                alerts::process_alerts,
+                blackout::update_blackout,
+                hud::apply_hud_layout,
+                scale_bar::draw_scale_bar,
+                scale_bar::draw_surface_axes,
+                decay::track_orbit_decay,
+                orbit_class::update_orbit_class.after(decay::track_orbit_decay),
+                energy::update_energy_trend,
+                health::update_health_text,
+                warp::update_warp_text.after(warp::apply_time_warp),
+                drift::track_prediction_drift.after(drift::toggle_prediction_lock),
+                (
+                    inspect::pick_body,
+                    inspect::update_inspector,
+                    plane_match::draw_plane_match,
+                    docking::draw_docking_aid,
+                    phase::update_phase_angle,
+                )
+                    .chain(),
+            ),
+        );
+
+    if fixed_ticks {
+        headless::use_fixed_ticks(&mut app);
+    }
+    app.run();
+}
+
+/// The scenario to start, and the replay to play back in it if there is one. A replay
+/// always plays in the scenario it was recorded in.
+fn scenario_from_args() -> (Scenario, Option<replay::Recording>) {
+    let mut scenario = Scenario::from_args();
+    let replay = replay::replay_from_args();
+    if let Some(recording) = &replay {
+        if Scenario::given_in_args() && recording.scenario != scenario {
+            warn!(
+                "The replay was recorded in the `{}` scenario, using that instead",
+                recording.scenario.name()
+            );
+        }
+        scenario = recording.scenario;
+    }
+    (scenario, replay)
 }
 
 #[derive(Bundle)]
@@ -40,11 +374,15 @@ struct SpaceshipBundle {
     vel: Velocity,
     body: RigidBody,
     collider: Collider,
+    collision_events: ActiveEvents,
     restitution: Restitution,
     thrusters: Thrusters,
     thruster_force: ExternalForce,
     forces: ExternalForceSet,
+    mass_properties: ReadMassProperties,
+    health: health::Health,
     light: PointLight,
+    docking_port: docking::DockingPort,
 }
 
 #[derive(Component)]
@@ -54,52 +392,190 @@ struct Spaceship;
 struct Thrusters {
     /// Strength in some units
     strength: f32,
+    /// How much of the strength is used, from 0 to 1.
+    throttle: f32,
+    /// Hard limit for the thrust of the engine, whatever the strength and throttle.
+    max_thrust: f32,
+    /// Angles of the engine around the local X and Z axis.
+    gimbal: Vec2,
+    max_gimbal: f32,
+}
+
+impl Thrusters {
+    fn gimbal_rotation(&self) -> Quat {
+        Quat::from_rotation_x(self.gimbal.x) * Quat::from_rotation_z(self.gimbal.y)
+    }
+
+    /// The strongest thrust the engine can make at full throttle.
+    fn max_force(&self) -> f32 {
+        self.strength.min(self.max_thrust)
+    }
+
+    /// The thrust at the current throttle and gimbal in the local frame of the ship.
+    fn local_thrust(&self) -> Vec3 {
+        self.local_thrust_at(self.throttle)
+    }
+
+    /// The thrust at `throttle` and the current gimbal in the local frame of the ship.
+    fn local_thrust_at(&self, throttle: f32) -> Vec3 {
+        let thrust = self.gimbal_rotation() * Vec3::new(0.0, self.strength * throttle, 0.0);
+        thrust.clamp_length_max(self.max_thrust)
+    }
 }
 
+/// Marks the thruster force in the [`ExternalForceSet`].
+struct ThrusterForce;
+
+/// Marks the gravity force in the [`ExternalForceSet`].
+struct GravityForce;
+
+const SHIP_HEIGHT: f32 = 4.0;
+/// Where the engine is mounted, at the bottom of the ship.
+const ENGINE_OFFSET: Vec3 = Vec3::new(0.0, -SHIP_HEIGHT / 2.0, 0.0);
+
+#[derive(Component)]
+struct ThrusterFlame;
+
 #[derive(Component)]
 struct GravityAttractor {
     mass: f64,
 }
 
+#[derive(Component)]
+struct Planet {
+    radius: f64,
+}
+
 #[derive(Component)]
 struct OrbitCamera {
     radius: f32,
 }
 
+#[derive(Resource)]
+struct CameraSettings {
+    /// Multiplier for how far the camera turns for a mouse movement.
+    sensitivity: f32,
+    invert_y: bool,
+    /// Pulls the camera in when the planet or something else that isn't dynamic is between it
+    /// and the ship.
+    avoid_collisions: bool,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 1.0,
+            invert_y: false,
+            avoid_collisions: true,
+        }
+    }
+}
+
 #[derive(Component)]
 struct ThrusterSound;
 
 const AMOUNT_OF_FUNNY_ORBIT_SPHERES: u32 = 1000;
 
+/// The torque of the attitude thrusters.
+const ROTATION_TORQUE: f32 = 0.2;
+
+/// How fast the throttle changes while holding the throttle keys, per second.
+const THROTTLE_RATE: f32 = 0.5;
+
 fn fire_thrusters(
     mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut ExternalForceSet, &Transform, &Thrusters)>,
+    bindings: Res<input::InputBindings>,
+    gamepad: Res<gamepad::GamepadInput>,
+    mut query: Query<
+        (
+            &mut ExternalForceSet,
+            &Transform,
+            &mut Thrusters,
+            Option<&rcs::RcsThrusters>,
+            Option<&ReadMassProperties>,
+        ),
+        (With<Spaceship>, Without<health::Destroyed>),
+    >,
+    rcs_balancing: Res<rcs::RcsBalancing>,
+    mut burn_guard: ResMut<burn_guard::BurnGuard>,
     sound_query: Query<&AudioSink, With<ThrusterSound>>,
+    source_query: Query<(Entity, &Handle<AudioSource>), With<ThrusterSound>>,
     asset_server: Res<AssetServer>,
+    audio: Res<audio::AudioEnabled>,
+    sound_settings: Res<audio::ThrusterSoundSettings>,
+    time: Res<Time>,
+    paused: Res<pause::SimulationPaused>,
+    // set once the sound failed to load, so it isn't tried again on every key press
+    mut sound_missing: Local<bool>,
+    mut was_thrusting: Local<bool>,
 ) {
-    struct ThrusterForce;
+    let Ok((mut force_set, transform, mut thrusters, rcs_thrusters, mass_properties)) =
+        query.get_single_mut()
+    else {
+        return;
+    };
 
-    let (mut force_set, transform, thrusters) = query.single_mut();
+    let throttle_change = THROTTLE_RATE * time.delta_seconds();
+    if keyboard_input.pressed(bindings.throttle_up) {
+        thrusters.throttle = (thrusters.throttle + throttle_change).min(1.0);
+    }
+    if keyboard_input.pressed(bindings.throttle_down) {
+        thrusters.throttle = (thrusters.throttle - throttle_change).max(0.0);
+    }
 
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        if let Ok(sound) = sound_query.get_single() {
-            sound.play();
-        } else {
-            commands.spawn((
-                AudioBundle {
-                    source: asset_server.load("thrusters_loop.ogg"),
-                    settings: PlaybackSettings {
-                        mode: PlaybackMode::Loop,
-                        ..default()
+    // the thrust key fires at the set throttle, the trigger at its own deflection
+    let throttle = if keyboard_input.pressed(bindings.thrust) {
+        Some(thrusters.throttle)
+    } else {
+        Some(gamepad.trigger).filter(|&trigger| trigger > 0.0)
+    };
+    // once the burn guard cut the engine, it stays off until it's let go
+    if throttle.is_none() {
+        burn_guard.tripped = false;
+    }
+    let throttle = throttle.filter(|_| !burn_guard.tripped);
+    let thrusting = throttle.is_some();
+    let started = thrusting && !*was_thrusting;
+    let stopped = !thrusting && *was_thrusting;
+    *was_thrusting = thrusting;
+
+    for (entity, source) in &source_query {
+        if asset_server.get_load_state(source) == LoadState::Failed {
+            warn!("Couldn't load the thruster sound, continuing without it");
+            commands.entity(entity).despawn();
+            *sound_missing = true;
+        }
+    }
+
+    if audio.0 && !paused.0 && !*sound_missing {
+        if started {
+            if let Ok(sound) = sound_query.get_single() {
+                sound.play();
+            } else {
+                commands.spawn((
+                    AudioBundle {
+                        source: asset_server.load("thrusters_loop.ogg"),
+                        settings: PlaybackSettings {
+                            mode: PlaybackMode::Loop,
+                            ..default()
+                        },
                     },
-                },
-                ThrusterSound,
-            ));
+                    ThrusterSound,
+                ));
+            }
+        } else if stopped {
+            if let Ok(sound) = sound_query.get_single() {
+                sound.pause();
+            }
         }
-    } else if keyboard_input.just_released(KeyCode::Space) {
-        if let Ok(sound) = sound_query.get_single() {
-            sound.pause();
+
+        if let Some(throttle) = throttle {
+            if let Ok(sound) = sound_query.get_single() {
+                let (speed, volume) = sound_settings.at_throttle(throttle);
+                sound.set_speed(speed);
+                sound.set_volume(volume);
+            }
         }
     }
 
@@ -107,71 +583,181 @@ fn fire_thrusters(
 
     let mut force = force_set.get::<ThrusterForce>();
 
-    if keyboard_input.pressed(KeyCode::Space) {
-        force.force = rotation.mul_vec3(Vec3::new(0.0, thrusters.strength, 0.0));
-    } else {
-        force.force = Vec3::ZERO;
-    }
+    let gimbal_keybinds = [
+        (bindings.gimbal_up, Vec2::new(1.0, 0.0)),
+        (bindings.gimbal_down, Vec2::new(-1.0, 0.0)),
+        (bindings.gimbal_left, Vec2::new(0.0, 1.0)),
+        (bindings.gimbal_right, Vec2::new(0.0, -1.0)),
+    ];
+    thrusters.gimbal = gimbal_keybinds
+        .into_iter()
+        .filter(|&(bind, _)| keyboard_input.pressed(bind))
+        .map(|(_, dir)| dir * thrusters.max_gimbal)
+        .sum();
+
+    force.force = throttle.map_or(Vec3::ZERO, |throttle| {
+        rotation.mul_vec3(thrusters.local_thrust_at(throttle))
+    });
+    // a gimbaled engine pushes the bottom of the ship sideways
+    let gimbal_torque = rotation.mul_vec3(ENGINE_OFFSET).cross(force.force);
 
-    let torque = 0.2;
+    let torque = ROTATION_TORQUE;
     let keybinds = [
-        (KeyCode::W, Vec3::new(torque, 0.0, 0.0)),
-        (KeyCode::S, Vec3::new(-torque, -0.0, 0.0)),
-        (KeyCode::Q, Vec3::new(0.0, torque, 0.0)),
-        (KeyCode::E, Vec3::new(0.0, -torque, 0.0)),
-        (KeyCode::A, Vec3::new(0.0, 0.0, torque)),
-        (KeyCode::D, Vec3::new(0.0, -0.0, -torque)),
+        (bindings.pitch_up, Vec3::new(torque, 0.0, 0.0)),
+        (bindings.pitch_down, Vec3::new(-torque, -0.0, 0.0)),
+        (bindings.roll_left, Vec3::new(0.0, torque, 0.0)),
+        (bindings.roll_right, Vec3::new(0.0, -torque, 0.0)),
+        (bindings.yaw_left, Vec3::new(0.0, 0.0, torque)),
+        (bindings.yaw_right, Vec3::new(0.0, -0.0, -torque)),
     ];
 
-    let mut any_pressed = false;
-    for (bind, vec) in keybinds {
-        if keyboard_input.pressed(bind) {
-            any_pressed = true;
-            force.torque = rotation.mul_vec3(vec);
+    let local_torque = keybinds
+        .into_iter()
+        .rev()
+        .find(|&(bind, _)| keyboard_input.pressed(bind))
+        .map_or(Vec3::ZERO, |(_, vec)| vec)
+        + gamepad.rotation * ROTATION_TORQUE;
+    let local_translation = gamepad.translation * rcs_balancing.max_force;
+    match rcs_thrusters.filter(|_| rcs_balancing.enabled) {
+        // the thrusters don't sit symmetrically around the center of mass, so they are
+        // balanced to not push the ship while turning it, and to not turn it while pushing it
+        Some(rcs_thrusters) if local_torque != Vec3::ZERO || local_translation != Vec3::ZERO => {
+            let center_of_mass = mass_properties.map_or(Vec3::ZERO, |m| m.0.local_center_of_mass);
+            let levels = rcs::allocate_thrusters(
+                local_translation,
+                local_torque,
+                rcs_thrusters,
+                center_of_mass,
+                rcs_balancing.torque_weight,
+            );
+            let (rcs_force, rcs_torque) = rcs_thrusters.net(&levels, center_of_mass);
+            force.force += rotation.mul_vec3(rcs_force);
+            force.torque = rotation.mul_vec3(rcs_torque);
         }
+        _ => force.torque = rotation.mul_vec3(local_torque),
     }
-    if !any_pressed {
-        force.torque = Vec3::ZERO;
-    }
+    force.torque += gimbal_torque;
 
     force_set.set::<ThrusterForce>(force);
 }
 
-fn apply_gravity(
-    mut query: Query<(&mut ExternalForceSet, &Transform), With<Spaceship>>,
-    body_query: Query<(&GravityAttractor, &Transform), Without<Spaceship>>,
-) {
-    struct GravityForce;
+/// The sum of the pull of all `bodies` on a point mass of 1 at `pos`.
+///
+/// Floating point addition isn't associative, so the contributions are sorted before
+/// summing them up. This makes the result independent of the order of `bodies`, which
+/// depends on the order they were spawned in.
+fn gravity_acceleration(pos: Vec3, bodies: impl IntoIterator<Item = (Vec3, f64)>) -> Vec3 {
+    let bodies = bodies
+        .into_iter()
+        .map(|(body_pos, mass)| (body_pos.as_dvec3(), mass));
+    precise_gravity_acceleration(pos.as_dvec3(), bodies).as_vec3()
+}
+
+/// [`gravity_acceleration`] without ever going through `f32`.
+fn precise_gravity_acceleration(
+    pos: DVec3,
+    bodies: impl IntoIterator<Item = (DVec3, f64)>,
+) -> DVec3 {
+    let mut contributions = bodies
+        .into_iter()
+        .filter_map(|(body_pos, mass)| {
+            let offset = body_pos - pos;
+            let distance = offset.length();
+            if distance == 0.0 {
+                return None;
+            }
 
-    let (mut ship_forces, ship_transform) = query.single_mut();
+            Some(offset / distance * (orbit::G * mass) / (distance * distance))
+        })
+        .collect::<Vec<_>>();
 
-    for (gravity, body_transform) in &body_query {
-        let distance = ship_transform
-            .translation
-            .distance(body_transform.translation) as f64;
+    contributions.sort_by(|a, b| {
+        a.x.total_cmp(&b.x)
+            .then(a.y.total_cmp(&b.y))
+            .then(a.z.total_cmp(&b.z))
+    });
+    contributions.into_iter().sum::<DVec3>()
+}
 
-        let fg = (orbit::G * gravity.mass) / (distance * distance);
-        let direction = (body_transform.translation - ship_transform.translation).normalize();
+/// The mass of a body, or 1 if rapier hasn't computed it yet.
+fn mass_of(mass: Option<&ReadMassProperties>) -> f32 {
+    match mass {
+        Some(mass) if mass.0.mass > 0.0 => mass.0.mass,
+        _ => 1.0,
+    }
+}
 
-        let fg = ExternalForce {
-            force: direction * (fg as f32),
-            torque: Vec3::ZERO,
-        };
+fn apply_gravity(
+    mut query: Query<(
+        Entity,
+        &mut ExternalForceSet,
+        &Transform,
+        Option<&ReadMassProperties>,
+    )>,
+    body_query: Query<(Entity, &GravityAttractor, &Transform)>,
+) {
+    for (entity, mut forces, transform, mass) in &mut query {
+        let bodies = body_query
+            .iter()
+            .filter(|&(body, _, _)| body != entity)
+            .map(|(_, gravity, body_transform)| (body_transform.translation, gravity.mass));
 
-        ship_forces.set::<GravityForce>(fg);
+        forces.set::<GravityForce>(ExternalForce {
+            force: gravity_acceleration(transform.translation, bodies) * mass_of(mass),
+            torque: Vec3::ZERO,
+        });
     }
 }
 
+/// Picks the body that pulls the hardest on something at `pos`,
+/// given the position and mass of every body.
+fn dominant_attractor<T>(
+    bodies: impl IntoIterator<Item = T>,
+    pos: Vec3,
+    body: impl Fn(&T) -> (Vec3, f64),
+) -> Option<T> {
+    let pull = |item: &T| {
+        let (body_pos, mass) = body(item);
+        mass / f64::from(body_pos.distance_squared(pos))
+    };
+    bodies
+        .into_iter()
+        .max_by(|a, b| pull(a).total_cmp(&pull(b)))
+}
+
 #[derive(Component)]
 struct OrbitText;
 
+/// A marker on the orbit where the ship currently is according to its orbital elements,
+/// to see where on the drawn orbit the ship is.
+#[derive(Resource)]
+struct OrbitBreadcrumb {
+    enabled: bool,
+    color: Color,
+}
+
+impl Default for OrbitBreadcrumb {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            color: Color::LIME_GREEN,
+        }
+    }
+}
+
 fn debug_spaceship_orbit(
+    units: units::HudUnits,
+    breadcrumb: Res<OrbitBreadcrumb>,
+    collection: Res<collect::MarkerCollection>,
+    computation: Res<orbit_method::OrbitComputation>,
     query: Query<(&Transform, &Velocity), With<Spaceship>>,
-    body_query: Query<(&Transform, &GravityAttractor), Without<Spaceship>>,
+    reference: Res<reference::ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: reference::BodyVelocities,
     mut text_query: Query<&mut Text, With<OrbitText>>,
     mut gizmos: Gizmos,
     mut query_sphere: Query<
-        &mut Transform,
+        (Entity, &mut Transform),
         (
             With<FunnyOrbitalSphere>,
             Without<OrbitText>,
@@ -184,7 +770,13 @@ fn debug_spaceship_orbit(
     let (ship_transform, &v) = query.single();
 
     let ship_pos = ship_transform.translation;
-    let (body_transform, body_gravity) = body_query.single();
+    let Some((body, body_transform, body_gravity)) =
+        reference::reference_attractor(&reference, &body_query, ship_pos, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
     let body_pos = body_transform.translation;
 
     let body_rotation = body_transform.rotation;
@@ -193,7 +785,7 @@ fn debug_spaceship_orbit(
     gizmos.ray(body_pos, body_axis * 150.0, Color::GOLD);
     gizmos.ray(body_pos, -body_axis * 150.0, Color::GOLD);
 
-    let velocity = v.linvel;
+    let velocity = v.linvel - velocities.of(body);
     let translation = ship_pos - body_pos;
 
     let orbital_plane_normal = velocity.cross(translation).normalize_or_zero() * 10.0;
@@ -214,23 +806,71 @@ fn debug_spaceship_orbit(
         Color::OLIVE,
     );
 
-    let orbit = orbit::Orbit::from_pos_dir(
+    let orbit = computation.method.orbit(
         body_gravity.mass,
-        DVec2::new(rotated_pos.x.into(), rotated_pos.z.into()),
-        DVec2::new(rotated_vel.x.into(), rotated_vel.z.into()),
+        translation.as_dvec3(),
+        velocity.as_dvec3(),
     );
-    text.sections[1].value = format!("{:.2}", orbit.semi_major_axis);
-    text.sections[3].value = format!("{:.2}", orbit.apoapsis());
-    text.sections[5].value = format!("{:.2}", orbit.periapsis());
+    text.sections[1].value = units.distance(orbit.semi_major_axis);
+    text.sections[3].value = units.distance(orbit.apoapsis());
+    text.sections[5].value = units.distance(orbit.periapsis());
 
     gizmos.ray_gradient(ship_pos, velocity, Color::RED, Color::GREEN);
     gizmos.ray_gradient(ship_pos, translation, Color::BLUE, Color::GREEN);
 
     gizmos.line(body_transform.translation, ship_pos, Color::WHITE);
 
+    let elements = orbit::OrbitalElements::from_state(
+        body_gravity.mass,
+        translation.as_dvec3(),
+        velocity.as_dvec3(),
+    );
+    let marker_radius = (orbit.semi_major_axis.abs() as f32 * 0.02).max(1.0);
+    gizmos.sphere(
+        body_pos + elements.periapsis_position().as_vec3(),
+        Quat::IDENTITY,
+        marker_radius,
+        Color::ORANGE_RED,
+    );
+    if orbit.is_closed() {
+        gizmos.sphere(
+            body_pos + elements.apoapsis_position().as_vec3(),
+            Quat::IDENTITY,
+            marker_radius,
+            Color::AQUAMARINE,
+        );
+    }
+
+    if breadcrumb.enabled {
+        let on_orbit = elements.position_at_true_anomaly(elements.true_anomaly);
+        gizmos.sphere(
+            body_pos + on_orbit.as_vec3(),
+            Quat::IDENTITY,
+            marker_radius,
+            breadcrumb.color,
+        );
+    }
+
+    let mut spheres: Vec<_> = query_sphere.iter_mut().collect();
+    // the query order changes when a marker is collected, each has to keep its place
+    spheres.sort_by_key(|(entity, _)| *entity);
+    if collection.enabled {
+        // at fixed points of the orbit, so that coasting along it goes through them
+        let max_radius = elements.radius_at_true_anomaly(0.0) * snapshot::OPEN_ORBIT_EXTENT;
+        for (i, (_, sphere)) in spheres.iter_mut().enumerate() {
+            let nu = std::f64::consts::TAU / f64::from(AMOUNT_OF_FUNNY_ORBIT_SPHERES) * i as f64;
+            let r = elements.radius_at_true_anomaly(nu);
+            // an open orbit doesn't get around to all of them
+            if orbit.is_closed() || (r > 0.0 && r < max_radius) {
+                sphere.translation = body_pos + elements.position_at_true_anomaly(nu).as_vec3();
+            }
+        }
+        return;
+    }
+
     let base_pos = body_pos;
     let distance = (orbit.semi_major_axis as f32) * 2.0;
-    for (i, mut sphere) in query_sphere.iter_mut().enumerate() {
+    for (i, (_, sphere)) in spheres.iter_mut().enumerate() {
         let angle = std::f32::consts::TAU / (AMOUNT_OF_FUNNY_ORBIT_SPHERES as f32) * (i as f32);
 
         let pos = Vec3::new(angle.cos(), 0.0, angle.sin()) * distance;
@@ -240,6 +880,99 @@ fn debug_spaceship_orbit(
     }
 }
 
+/// Compares the ship's orbital period to every other body orbiting the attractor
+/// and shows any resonance between them.
+fn debug_resonance(
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    others: Query<(&Transform, &Velocity), (Without<Spaceship>, Without<GravityAttractor>)>,
+    reference: Res<reference::ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: reference::BodyVelocities,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+) {
+    let mut text = text_query.single_mut();
+    let (ship_transform, ship_v) = query.single();
+    let Some((body, body_transform, body_gravity)) = reference::reference_attractor(
+        &reference,
+        &body_query,
+        ship_transform.translation,
+        |(_, t, g)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+    let body_velocity = velocities.of(body);
+
+    let orbit_of = |transform: &Transform, v: &Velocity| {
+        orbit::Orbit::from_pos_dir_3d(
+            body_gravity.mass,
+            (transform.translation - body_transform.translation).as_dvec3(),
+            (v.linvel - body_velocity).as_dvec3(),
+        )
+    };
+
+    let ship_orbit = orbit_of(ship_transform, ship_v);
+
+    let resonances = others
+        .iter()
+        .filter_map(|(transform, v)| {
+            let ratio =
+                resonance::period_ratio(body_gravity.mass, &ship_orbit, &orbit_of(transform, v));
+            resonance::classify_resonance(ratio, resonance::RESONANCE_TOLERANCE)
+                .map(|res| format!("{res} ({ratio:.3})"))
+        })
+        .collect::<Vec<_>>();
+
+    text.sections[7].value = if resonances.is_empty() {
+        "none".to_owned()
+    } else {
+        resonances.join(", ")
+    };
+}
+
+/// How much the planning readout changes the period by, relative to the current one.
+const PHASING_PERIOD_FRACTION: f64 = 0.1;
+
+fn debug_phasing(
+    units: units::HudUnits,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    reference: Res<reference::ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: reference::BodyVelocities,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+) {
+    let mut text = text_query.single_mut();
+    let (ship_transform, ship_v) = query.single();
+    let Some((body, body_transform, body_gravity)) = reference::reference_attractor(
+        &reference,
+        &body_query,
+        ship_transform.translation,
+        |(_, t, g)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+    let body_velocity = velocities.of(body);
+
+    let m = body_gravity.mass;
+    let orbit = orbit::Orbit::from_pos_dir_3d(
+        m,
+        (ship_transform.translation - body_transform.translation).as_dvec3(),
+        (ship_v.linvel - body_velocity).as_dvec3(),
+    );
+    if !orbit.is_closed() {
+        text.sections[11].value = "-".to_owned();
+        return;
+    }
+
+    let period_delta = orbit.period(m) * PHASING_PERIOD_FRACTION;
+    let fall_back = orbit::phasing_orbit_dv(m, &orbit, period_delta);
+    let catch_up = orbit::phasing_orbit_dv(m, &orbit, -period_delta);
+    text.sections[11].value = format!(
+        "±{period_delta:.1}s: {} / {} at Pe",
+        units.speed_change(fall_back),
+        units.speed_change(catch_up)
+    );
+}
+
 // adapted from https://bevy-cheatbook.github.io/cookbook/pan-orbit-camera.html
 fn orbit_camera(
     mut ev_motion: EventReader<MouseMotion>,
@@ -247,16 +980,54 @@ fn orbit_camera(
     mut query: Query<(&mut OrbitCamera, &mut Transform), Without<Spaceship>>,
     spaceship_query: Query<&Transform, With<Spaceship>>,
     window_query: Query<&Window, With<PrimaryWindow>>,
+    reference: Res<reference::ReferenceBody>,
+    body_query: Query<
+        (Entity, &Transform, &GravityAttractor),
+        (Without<Spaceship>, Without<OrbitCamera>),
+    >,
+    settings: Res<CameraSettings>,
+    mode: Res<camera::CameraMode>,
+    frame: Res<camera::CameraFrame>,
+    rapier_context: Res<RapierContext>,
+    // the attractor and the ship relative to it last frame, for turning with the orbit
+    mut last_radial: Local<Option<(Entity, Vec3)>>,
 ) {
     let window = window_query.single();
     let rotation_move: Vec2 = ev_motion.iter().map(|ev| ev.delta).sum();
     let scroll: f32 = ev_scroll.iter().map(|ev| ev.y).sum();
 
+    if *mode != camera::CameraMode::Follow {
+        // the ship moved on in the meantime, turning all of that at once would jump
+        *last_radial = None;
+        return;
+    }
+
+    let ship = spaceship_query.single().translation;
+    let radial = reference::reference_attractor(&reference, &body_query, ship, |(_, t, g)| {
+        (t.translation, g.mass)
+    })
+    .map(|(entity, body, _)| (entity, ship - body.translation));
+    let co_rotation = match (*frame, *last_radial, radial) {
+        (camera::CameraFrame::Orbital, Some((last_body, previous)), Some((body, current)))
+            if last_body == body =>
+        {
+            camera::orbital_co_rotation(previous, current)
+        }
+        _ => Quat::IDENTITY,
+    };
+    *last_radial = radial;
+
     for (mut orbit, mut transform) in &mut query {
+        transform.rotation = co_rotation * transform.rotation;
         if rotation_move.length_squared() > 0.0 {
             let window = Vec2::new(window.width(), window.height());
-            let delta_x = rotation_move.x / window.x * std::f32::consts::PI * 2.0;
-            let delta_y = rotation_move.y / window.y * std::f32::consts::PI;
+            let delta_x =
+                rotation_move.x / window.x * std::f32::consts::PI * 2.0 * settings.sensitivity;
+            let mut delta_y =
+                rotation_move.y / window.y * std::f32::consts::PI * settings.sensitivity;
+            if settings.invert_y {
+                delta_y = -delta_y;
+            }
             let yaw = Quat::from_rotation_y(-delta_x);
             let pitch = Quat::from_rotation_x(-delta_y);
             transform.rotation *= yaw;
@@ -268,9 +1039,20 @@ fn orbit_camera(
             orbit.radius = f32::max(orbit.radius, 0.05);
         }
 
-        let rot_matrix = Mat3::from_quat(transform.rotation);
-        transform.translation = spaceship_query.single().translation
-            + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, orbit.radius));
+        let direction = transform.rotation * Vec3::Z;
+        let radius = if settings.avoid_collisions {
+            let hit = rapier_context.cast_ray(
+                ship,
+                direction,
+                orbit.radius,
+                true,
+                QueryFilter::exclude_dynamic(),
+            );
+            camera::unobstructed_radius(orbit.radius, hit.map(|(_, toi)| toi))
+        } else {
+            orbit.radius
+        };
+        transform.translation = ship + direction * radius;
     }
 }
 
@@ -284,26 +1066,15 @@ fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     asset_server: Res<AssetServer>,
+    scenario: Res<Scenario>,
 ) {
-    // We ain't a normal game, we do our own gravity.
-    commands.insert_resource(RapierConfiguration {
-        gravity: Vec3::ZERO,
-        ..default()
-    });
-
-    commands.spawn(PlanetBundle::new(
+    scenario::spawn(
+        &mut commands,
         &mut meshes,
         &mut materials,
         &asset_server,
-        Transform::from_xyz(0.0, -100.0, 0.0),
-        10000.0,
-    ));
-
-    commands.spawn(SpaceshipBundle::new(
-        &mut meshes,
-        &mut materials,
-        Vec3::new(0.0, 100.0, 0.0),
-    ));
+        *scenario,
+    );
 
     for _ in 0..AMOUNT_OF_FUNNY_ORBIT_SPHERES {
         commands.spawn((
@@ -341,59 +1112,19 @@ fn setup(
         },
     ));
 
-    commands.spawn((
-        TextBundle::from_sections([
-            TextSection::new(
-                "Semi Major Axis: ",
-                TextStyle {
-                    font_size: 20.0,
-                    color: Color::GRAY,
-                    ..default()
-                },
-            ),
-            TextSection::from_style(TextStyle {
-                font_size: 20.0,
-                color: Color::GRAY,
-                ..default()
-            }),
-            TextSection::new(
-                "\nApoapsis: ",
-                TextStyle {
-                    font_size: 20.0,
-                    color: Color::GRAY,
-                    ..default()
-                },
-            ),
-            TextSection::from_style(TextStyle {
-                font_size: 20.0,
-                color: Color::GRAY,
-                ..default()
-            }),
-            TextSection::new(
-                "\nPeriapsis: ",
-                TextStyle {
-                    font_size: 20.0,
-                    color: Color::GRAY,
-                    ..default()
-                },
-            ),
-            TextSection::from_style(TextStyle {
-                font_size: 20.0,
-                color: Color::GRAY,
-                ..default()
-            }),
-        ]),
-        OrbitText,
-    ));
-
     // let mut window = windows.single_mut();
     // window.cursor.visible = false;
     // window.cursor.grab_mode = CursorGrabMode::Locked;
 }
 
 impl SpaceshipBundle {
-    fn new(meshes: &mut Assets<Mesh>, materials: &mut Assets<StandardMaterial>, pos: Vec3) -> Self {
-        let height = 4.0;
+    fn new(
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        pos: Vec3,
+        initial_angvel: Vec3,
+    ) -> Self {
+        let height = SHIP_HEIGHT;
         let width = 0.5;
 
         SpaceshipBundle {
@@ -406,71 +1137,379 @@ impl SpaceshipBundle {
             },
             vel: Velocity {
                 linvel: Vec3::ZERO,
-                angvel: Vec3::ZERO,
+                angvel: initial_angvel,
             },
             body: RigidBody::Dynamic,
             collider: Collider::cuboid(width / 2.0, height / 2.0, width / 2.0),
-            restitution: Restitution::coefficient(0.1),
-            thrusters: Thrusters { strength: 1.0 },
+            collision_events: ActiveEvents::COLLISION_EVENTS,
+            // the planets have none, averaging with them would only leave half of it
+            restitution: Restitution {
+                coefficient: 0.1,
+                combine_rule: CoefficientCombineRule::Max,
+            },
+            thrusters: Thrusters {
+                strength: 1.0,
+                throttle: 1.0,
+                max_thrust: f32::INFINITY,
+                gimbal: Vec2::ZERO,
+                max_gimbal: 5.0f32.to_radians(),
+            },
             thruster_force: ExternalForce {
                 force: Vec3::new(0.0, -0.5, 0.0), // gravity
                 torque: Vec3::ZERO,
             },
             forces: ExternalForceSet::default(),
+            mass_properties: ReadMassProperties::default(),
+            health: health::Health::new(100.0),
             light: PointLight {
                 intensity: 1500.0,
                 shadows_enabled: true,
                 ..default()
             },
+            // in the nose
+            docking_port: docking::DockingPort {
+                offset: Vec3::new(0.0, height / 2.0, 0.0),
+                axis: Vec3::Y,
+            },
+        }
+    }
+}
+
+/// Spawns the ship together with the flame of its engine.
+fn spawn_spaceship(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    ship: SpaceshipBundle,
+) -> Entity {
+    commands
+        .spawn(ship)
+        .with_children(|ship| {
+            // the flame is pivoted at the engine, so rotating it follows the gimbal
+            ship.spawn((
+                ThrusterFlame,
+                SpatialBundle {
+                    transform: Transform::from_translation(ENGINE_OFFSET),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+            ))
+            .with_children(|flame| {
+                let length = 1.5;
+                flame.spawn(PbrBundle {
+                    mesh: meshes.add(
+                        shape::Capsule {
+                            radius: 0.15,
+                            depth: length,
+                            ..default()
+                        }
+                        .into(),
+                    ),
+                    material: materials.add(StandardMaterial {
+                        base_color: Color::ORANGE,
+                        emissive: Color::ORANGE_RED,
+                        unlit: true,
+                        ..default()
+                    }),
+                    transform: Transform::from_xyz(0.0, -length / 2.0, 0.0),
+                    ..default()
+                });
+            });
+        })
+        .id()
+}
+
+fn update_thruster_flame(
+    query: Query<(&Transform, &Thrusters, &ExternalForceSet)>,
+    mut flame_query: Query<
+        (&Parent, &mut Transform, &mut Visibility),
+        (With<ThrusterFlame>, Without<Thrusters>),
+    >,
+    mut gizmos: Gizmos,
+) {
+    for (ship, mut flame_transform, mut visibility) in &mut flame_query {
+        let Ok((transform, thrusters, forces)) = query.get(ship.get()) else {
+            continue;
+        };
+
+        let thrust = forces.get::<ThrusterForce>().force;
+        let thrusting = thrust != Vec3::ZERO;
+
+        flame_transform.rotation = thrusters.gimbal_rotation();
+        *visibility = if thrusting {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+
+        if thrusting {
+            let engine = transform.transform_point(ENGINE_OFFSET);
+            gizmos.ray(engine, thrust.normalize() * 5.0, Color::ORANGE);
         }
     }
 }
 
+/// in kg/m^3
+const MOON_DENSITY: f64 = 2000.0;
+
+/// Sectors and stacks of the planet sphere mesh, detailed enough for a planet right in front
+/// of the camera. Small or distant planets can get away with a lot less.
+const PLANET_TESSELLATION: usize = 100;
+
 #[derive(Bundle)]
 struct PlanetBundle {
+    name: Name,
+    planet: Planet,
     mesh: PbrBundle,
+    // kinematic so that things can be attached to it with joints, and it can be moved later.
+    body: RigidBody,
     coll: Collider,
     gravity: GravityAttractor,
+    lod: PlanetLod,
+}
+
+fn planet_mesh(radius: f64, sectors: usize, stacks: usize) -> Mesh {
+    shape::UVSphere {
+        radius: radius as f32,
+        sectors,
+        stacks,
+    }
+    .into()
+}
+
+/// The sectors and stacks of the LOD `level`, halved for each level but never so few that the
+/// sphere falls apart.
+fn lod_tessellation(sectors: usize, stacks: usize, level: usize) -> (usize, usize) {
+    ((sectors >> level).max(3), (stacks >> level).max(2))
+}
+
+/// The mass of a uniform sphere.
+fn sphere_mass(radius: f64, density: f64) -> f64 {
+    use std::f64::consts::PI;
+
+    (4.0 / 3.0) * PI * radius * radius * radius * density
+}
+
+/// A planet to spawn with [`spawn_planet_system`].
+#[derive(Debug, Clone)]
+struct PlanetConfig {
+    /// What the HUD calls it.
+    name: String,
+    position: Transform,
+    radius: f64,
+    mass: f64,
+    texture: &'static str,
+    /// Sectors and stacks of the most detailed mesh.
+    sectors: usize,
+    stacks: usize,
+    /// Levels of detail, given as the camera distance from which on they are used, starting
+    /// with the most detailed one. See [`PlanetBundle::new`].
+    lod_distances: Vec<f32>,
+}
+
+impl PlanetConfig {
+    fn new(position: Transform, radius: f64, density: f64) -> Self {
+        PlanetConfig {
+            position,
+            radius,
+            mass: sphere_mass(radius, density),
+            ..default()
+        }
+    }
+}
+
+impl Default for PlanetConfig {
+    fn default() -> Self {
+        let radius = 10000.0;
+        PlanetConfig {
+            name: "Planet".to_owned(),
+            position: Transform::IDENTITY,
+            radius,
+            mass: sphere_mass(radius, MOON_DENSITY),
+            texture: "2k_moon.png",
+            sectors: PLANET_TESSELLATION,
+            stacks: PLANET_TESSELLATION,
+            lod_distances: vec![0.0],
+        }
+    }
+}
+
+/// Spawns all `planets`, returning their entities in the same order. Planets with the same
+/// radius and tessellation share their meshes, and ones with the same texture their material.
+fn spawn_planet_system(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    asset_server: &AssetServer,
+    planets: &[PlanetConfig],
+) -> Vec<Entity> {
+    let mut mesh_cache = HashMap::new();
+    let mut material_cache = HashMap::new();
+
+    planets
+        .iter()
+        .map(|config| {
+            let material = material_cache
+                .entry(config.texture)
+                .or_insert_with(|| {
+                    materials.add(StandardMaterial {
+                        base_color_texture: Some(asset_server.load(config.texture)),
+                        alpha_mode: AlphaMode::Blend,
+                        unlit: false,
+                        ..default()
+                    })
+                })
+                .clone();
+
+            commands
+                .spawn(PlanetBundle::new(
+                    meshes,
+                    &mut mesh_cache,
+                    &config.name,
+                    config.position,
+                    config.radius,
+                    config.mass,
+                    config.sectors,
+                    config.stacks,
+                    &config.lod_distances,
+                    material,
+                ))
+                .id()
+        })
+        .collect()
 }
 
+/// Planet meshes by radius and tessellation, so that planets of the same size can share them.
+type PlanetMeshes = HashMap<(u64, usize, usize), Handle<Mesh>>;
+
 impl PlanetBundle {
+    /// A planet with a mesh for each of the `lod_distances`, the camera distances from which on
+    /// they are used. The first level has `sectors` and `stacks`, each one after it half as many.
     fn new(
         meshes: &mut Assets<Mesh>,
-        materials: &mut Assets<StandardMaterial>,
-        assert_server: &AssetServer,
+        mesh_cache: &mut PlanetMeshes,
+        name: &str,
         position: Transform,
         radius: f64,
+        mass: f64,
+        sectors: usize,
+        stacks: usize,
+        lod_distances: &[f32],
+        material: Handle<StandardMaterial>,
     ) -> Self {
-        use std::f64::consts::PI;
-
-        let density = 2000.0 /* kg*m^3 */;
-
-        let mass = (4.0 / 3.0) * PI * radius * radius * radius * density;
-
-        let texture_handle = assert_server.load("2k_moon.png");
-        let material = materials.add(StandardMaterial {
-            base_color_texture: Some(texture_handle),
-            alpha_mode: AlphaMode::Blend,
-            unlit: false,
-            ..default()
-        });
+        let levels = lod_distances
+            .iter()
+            .enumerate()
+            .map(|(level, &distance)| {
+                let (sectors, stacks) = lod_tessellation(sectors, stacks, level);
+                let mesh = mesh_cache
+                    .entry((radius.to_bits(), sectors, stacks))
+                    .or_insert_with(|| meshes.add(planet_mesh(radius, sectors, stacks)));
+                (distance, mesh.clone())
+            })
+            .collect();
+        let lod = PlanetLod { levels, current: 0 };
 
         PlanetBundle {
+            name: Name::new(name.to_owned()),
+            planet: Planet { radius },
             mesh: PbrBundle {
-                mesh: meshes.add(
-                    shape::UVSphere {
-                        radius: radius as f32,
-                        sectors: 100,
-                        stacks: 100,
-                    }
-                    .into(),
-                ),
+                mesh: lod.levels[0].1.clone(),
                 material,
                 transform: position,
                 ..default()
             },
+            body: RigidBody::KinematicPositionBased,
             coll: Collider::ball(radius as f32),
             gravity: GravityAttractor { mass },
+            lod,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::{
+        apply_gravity, forces::ExternalForceSet, lod_tessellation, GravityAttractor, GravityForce,
+        Thrusters,
+    };
+
+    fn gravity_with_spawn_order(order: &[usize]) -> Vec3 {
+        let bodies = [
+            (Vec3::new(1000.0, 0.0, 0.0), 3.0e15),
+            (Vec3::new(-700.0, 300.0, 0.0), 1.0e14),
+            (Vec3::new(0.0, -2000.0, 500.0), 7.0e15),
+            (Vec3::new(10.0, 20.0, -30000.0), 2.0e16),
+        ];
+
+        let mut app = App::new();
+        app.add_systems(Update, apply_gravity);
+        for &i in order {
+            let (pos, mass) = bodies[i];
+            app.world
+                .spawn((GravityAttractor { mass }, Transform::from_translation(pos)));
+        }
+        let ship = app
+            .world
+            .spawn((
+                ExternalForceSet::default(),
+                Transform::from_xyz(1.0, 2.0, 3.0),
+            ))
+            .id();
+
+        app.update();
+        app.world
+            .get::<ExternalForceSet>(ship)
+            .unwrap()
+            .get::<GravityForce>()
+            .force
+    }
+
+    #[test]
+    fn gravity_is_independent_of_spawn_order() {
+        let force = gravity_with_spawn_order(&[0, 1, 2, 3]);
+        for order in [[3, 2, 1, 0], [1, 3, 0, 2], [2, 0, 3, 1]] {
+            assert_eq!(gravity_with_spawn_order(&order), force, "{order:?}");
         }
     }
+
+    #[test]
+    fn gravity_sums_all_bodies() {
+        let force = gravity_with_spawn_order(&[0, 1, 2, 3]);
+        let strongest_alone = gravity_with_spawn_order(&[2]);
+        assert_ne!(force, strongest_alone);
+        assert!(force.x > 0.0, "the body at +X should pull, {force}");
+    }
+
+    #[test]
+    fn thrust_is_clamped_to_max() {
+        let mut thrusters = Thrusters {
+            strength: 10.0,
+            throttle: 1.0,
+            max_thrust: 4.0,
+            gimbal: Vec2::new(0.05, -0.02),
+            max_gimbal: 0.1,
+        };
+        let thrust = thrusters.local_thrust();
+        assert!((thrust.length() - 4.0).abs() < 1e-5, "{thrust}");
+        // still along the gimbal
+        let direction = thrusters.gimbal_rotation() * Vec3::Y;
+        assert!(thrust.normalize().angle_between(direction) < 1e-5);
+
+        thrusters.throttle = 0.2;
+        assert!((thrusters.local_thrust().length() - 2.0).abs() < 1e-5);
+
+        thrusters.max_thrust = f32::INFINITY;
+        thrusters.throttle = 1.0;
+        assert!((thrusters.local_thrust().length() - 10.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn lod_levels_halve_the_tessellation() {
+        assert_eq!(lod_tessellation(100, 100, 0), (100, 100));
+        assert_eq!(lod_tessellation(64, 32, 2), (16, 8));
+        assert_eq!(lod_tessellation(16, 16, 4), (3, 2));
+    }
 }
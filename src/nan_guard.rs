@@ -0,0 +1,139 @@
+//! A debug safety net reporting non-finite values in the ship's state and orbit
+//! as soon as they appear, instead of letting them silently spread.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{dominant_attractor, orbit::Orbit, GravityAttractor, Spaceship};
+
+/// Enabled with `--nan-guard`, or `--nan-guard=halt` to also pause the simulation.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NanGuard {
+    pub enabled: bool,
+    /// Pause the physics and time when something was found.
+    pub halt: bool,
+}
+
+impl NanGuard {
+    pub fn from_args() -> Self {
+        let arg = std::env::args().find(|arg| arg.starts_with("--nan-guard"));
+        match arg.as_deref() {
+            None => NanGuard::default(),
+            Some("--nan-guard=halt") => NanGuard {
+                enabled: true,
+                halt: true,
+            },
+            Some(_) => NanGuard {
+                enabled: true,
+                halt: false,
+            },
+        }
+    }
+}
+
+/// Names every field of the ship state and the orbit computed from it that isn't finite.
+pub fn non_finite_fields(
+    transform: &Transform,
+    velocity: &Velocity,
+    orbit: Option<&Orbit>,
+) -> Vec<&'static str> {
+    let checks = [
+        ("translation", transform.translation.is_finite()),
+        ("rotation", transform.rotation.is_finite()),
+        ("linear velocity", velocity.linvel.is_finite()),
+        ("angular velocity", velocity.angvel.is_finite()),
+        // infinite for a parabolic orbit, which is fine
+        (
+            "semi major axis",
+            orbit.is_none_or(|o| !o.semi_major_axis.is_nan()),
+        ),
+        (
+            "eccentricity",
+            orbit.is_none_or(|o| o.eccentricity.is_finite()),
+        ),
+    ];
+
+    checks
+        .into_iter()
+        .filter(|&(_, finite)| !finite)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+pub fn nan_guard(
+    guard: Res<NanGuard>,
+    mut time: ResMut<Time>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(&Transform, &GravityAttractor), Without<Spaceship>>,
+    // only report once, everything after the first NaN is garbage anyway
+    mut reported: Local<bool>,
+) {
+    if !guard.enabled || *reported {
+        return;
+    }
+
+    let (transform, velocity) = query.single();
+    let attractor = dominant_attractor(&body_query, transform.translation, |(t, g)| {
+        (t.translation, g.mass)
+    });
+    let orbit_inputs = attractor.map(|(body_transform, gravity)| {
+        (
+            gravity.mass,
+            transform.translation - body_transform.translation,
+        )
+    });
+    let orbit = orbit_inputs
+        .map(|(m, pos)| Orbit::from_pos_dir_3d(m, pos.as_dvec3(), velocity.linvel.as_dvec3()));
+
+    let fields = non_finite_fields(transform, velocity, orbit.as_ref());
+    if fields.is_empty() {
+        return;
+    }
+
+    *reported = true;
+    error!(
+        "Non-finite {} after {:.2}s\n\
+         transform: {transform:?}\n\
+         velocity: {velocity:?}\n\
+         orbit: {orbit:?} from (mass, position relative to attractor) {orbit_inputs:?}",
+        fields.join(", "),
+        time.elapsed_seconds_f64(),
+    );
+
+    if guard.halt {
+        rapier_config.physics_pipeline_active = false;
+        time.pause();
+        error!("Simulation halted");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::*;
+
+    use super::non_finite_fields;
+    use crate::orbit::Orbit;
+
+    #[test]
+    fn reports_non_finite_fields() {
+        let transform = Transform::from_xyz(1.0, 2.0, 3.0);
+        let velocity = Velocity::linear(Vec3::X);
+        let orbit = Orbit {
+            semi_major_axis: 100.0,
+            eccentricity: 0.1,
+        };
+        assert!(non_finite_fields(&transform, &velocity, Some(&orbit)).is_empty());
+
+        let velocity = Velocity::linear(Vec3::new(f32::NAN, 0.0, 0.0));
+        let orbit = Orbit {
+            eccentricity: f64::NAN,
+            ..orbit
+        };
+        assert_eq!(
+            non_finite_fields(&transform, &velocity, Some(&orbit)),
+            ["linear velocity", "eccentricity"]
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use glam::DVec2;
+use std::{f64::consts::TAU, fmt, str::FromStr};
+
+use glam::{DMat3, DQuat, DVec2, DVec3};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Orbit {
@@ -21,6 +23,8 @@ impl Orbit {
 
         // semi major axis, 9.5.31
         // a = (GMr)/(2GM-v^2r)
+        // At escape speed the denominator is zero, so a is infinite for a parabola
+        // and negative for a hyperbola beyond that.
         let a = (G * m * r) / ((2.0 * G * m) - (v * v * r));
 
         // eccentricity, 9.9.3
@@ -36,18 +40,461 @@ impl Orbit {
         }
     }
 
+    /// Like [`Orbit::from_pos_dir`], but for a full 3D state relative to the attractor.
+    /// The state is rotated into its orbital plane first.
+    pub fn from_pos_dir_3d(m: f64, pos: DVec3, v: DVec3) -> Orbit {
+        let normal = v.cross(pos).try_normalize().unwrap_or(DVec3::X);
+        let plane_rot = DQuat::from_rotation_arc(normal, DVec3::Y);
+
+        let pos = plane_rot * pos;
+        let v = plane_rot * v;
+
+        Orbit::from_pos_dir(m, DVec2::new(pos.x, pos.z), DVec2::new(v.x, v.z))
+    }
+
+    /// Whether the orbit is an ellipse, so the body comes back around.
+    pub fn is_closed(&self) -> bool {
+        self.semi_major_axis > 0.0 && self.eccentricity < 1.0
+    }
+
+    /// The time for one full revolution, from Kepler's third law.
+    /// Only meaningful for closed orbits.
+    pub fn period(&self, m: f64) -> f64 {
+        let a = self.semi_major_axis;
+        std::f64::consts::TAU * f64::sqrt((a * a * a) / (G * m))
+    }
+
+    /// Also correct for hyperbolic orbits, where both the semi major axis and `1 - e` are negative.
+    /// A parabolic orbit at exactly escape speed has an infinite semi major axis though,
+    /// so its periapsis can't be recovered and is NaN.
     pub fn periapsis(&self) -> f64 {
         self.semi_major_axis * (1.0 - self.eccentricity)
     }
 
+    /// Infinite for orbits that aren't closed, instead of the negative (hyperbolic)
+    /// or NaN (parabolic) product of the elements.
     pub fn apoapsis(&self) -> f64 {
+        if !self.is_closed() {
+            return f64::INFINITY;
+        }
         self.semi_major_axis * (1.0 + self.eccentricity)
     }
+
+    /// The speed at distance `r` from the attractor, from the vis-viva equation.
+    pub fn speed_at(&self, m: f64, r: f64) -> f64 {
+        f64::sqrt(G * m * (2.0 / r - 1.0 / self.semi_major_axis))
+    }
+}
+
+/// The energy per unit of mass of a body `r` from the attractor going with `speed`, the
+/// kinetic plus the (negative) potential energy. Negative for closed orbits, and for those
+/// it's `-GM/2a`.
+pub fn specific_energy(m: f64, r: f64, speed: f64) -> f64 {
+    speed * speed / 2.0 - G * m / r
+}
+
+/// The semi major axis of a closed orbit taking `period` seconds, from Kepler's third law.
+pub fn semi_major_axis_for_period(m: f64, period: f64) -> f64 {
+    f64::cbrt(G * m * period * period / (TAU * TAU))
+}
+
+/// The burn at periapsis that changes the period of `orbit` by `period_delta` seconds,
+/// to catch up with (negative) or fall back behind (positive) a target in the same orbit.
+/// Positive results are prograde, negative ones retrograde.
+pub fn phasing_orbit_dv(m: f64, orbit: &Orbit, period_delta: f64) -> f64 {
+    let r = orbit.periapsis();
+    let phasing = Orbit {
+        semi_major_axis: semi_major_axis_for_period(m, orbit.period(m) + period_delta),
+        ..*orbit
+    };
+    phasing.speed_at(m, r) - orbit.speed_at(m, r)
+}
+
+/// The prograde burn at apoapsis that raises the periapsis to the apoapsis, circularizing `orbit`.
+/// Only meaningful for closed orbits.
+pub fn circularization_dv(m: f64, orbit: &Orbit) -> f64 {
+    let r = orbit.apoapsis();
+    let circular = Orbit {
+        semi_major_axis: r,
+        eccentricity: 0.0,
+    };
+    circular.speed_at(m, r) - orbit.speed_at(m, r)
+}
+
+/// The two burns of a Hohmann transfer from the periapsis of `orbit` to a circular orbit
+/// `target_radius` away from the attractor: one at the periapsis to put the apoapsis at the
+/// target, and one there to circularize. Positive results are prograde, negative ones retrograde.
+/// Only meaningful for closed orbits.
+pub fn hohmann_dv(m: f64, orbit: &Orbit, target_radius: f64) -> (f64, f64) {
+    let r = orbit.periapsis();
+    let transfer = Orbit {
+        semi_major_axis: (r + target_radius) / 2.0,
+        eccentricity: (target_radius - r).abs() / (r + target_radius),
+    };
+    let circular = Orbit {
+        semi_major_axis: target_radius,
+        eccentricity: 0.0,
+    };
+    (
+        transfer.speed_at(m, r) - orbit.speed_at(m, r),
+        circular.speed_at(m, target_radius) - transfer.speed_at(m, target_radius),
+    )
+}
+
+/// The prograde burn at the periapsis of `orbit` that reaches escape speed,
+/// 0 for orbits that aren't closed.
+pub fn escape_dv(m: f64, orbit: &Orbit) -> f64 {
+    if !orbit.is_closed() {
+        return 0.0;
+    }
+    let r = orbit.periapsis();
+    f64::sqrt(2.0 * G * m / r) - orbit.speed_at(m, r)
+}
+
+/// The burn that turns a velocity with `speed` by `angle` radians without changing its length,
+/// like for tilting the orbital plane at a node.
+pub fn plane_change_dv(speed: f64, angle: f64) -> f64 {
+    2.0 * speed * (angle / 2.0).sin()
+}
+
+/// The six classical orbital elements, a full description of a state relative to the attractor.
+///
+/// Angles are in radians and refer to the game frame with +Y as the pole and +X as the
+/// reference direction, so an equatorial prograde orbit goes from +X towards -Z.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub longitude_of_ascending_node: f64,
+    pub argument_of_periapsis: f64,
+    pub true_anomaly: f64,
+}
+
+/// Below this, the orbit counts as circular or equatorial and the
+/// angles measured from the periapsis or the node start at a fallback direction.
+const DEGENERATE_EPSILON: f64 = 1e-9;
+
+/// The game is Y-up, the usual convention for orbital elements is Z-up.
+fn to_reference_frame(v: DVec3) -> DVec3 {
+    DVec3::new(v.x, -v.z, v.y)
+}
+
+fn from_reference_frame(v: DVec3) -> DVec3 {
+    DVec3::new(v.x, v.z, -v.y)
+}
+
+/// The angle from `from` to `to` around `axis`, in `0..TAU`.
+fn angle_around(from: DVec3, to: DVec3, axis: DVec3) -> f64 {
+    f64::atan2(axis.dot(from.cross(to)), from.dot(to)).rem_euclid(TAU)
+}
+
+impl OrbitalElements {
+    /// Computes the elements from a position and velocity relative to the attractor.
+    pub fn from_state(m: f64, pos: DVec3, v: DVec3) -> OrbitalElements {
+        let mu = G * m;
+        let pos = to_reference_frame(pos);
+        let v = to_reference_frame(v);
+        let r = pos.length();
+
+        // https://en.wikipedia.org/wiki/Orbital_elements#Euler_angle_transformations
+        let h = pos.cross(v);
+        let h_dir = h.try_normalize().unwrap_or(DVec3::Z);
+        let node = DVec3::Z.cross(h);
+        let node_dir = if node.length() > DEGENERATE_EPSILON * h.length() {
+            node.normalize()
+        } else {
+            DVec3::X
+        };
+
+        let eccentricity_vector = ((v.length_squared() - mu / r) * pos - pos.dot(v) * v) / mu;
+        let e = eccentricity_vector.length();
+        let periapsis_dir = if e > DEGENERATE_EPSILON {
+            eccentricity_vector / e
+        } else {
+            node_dir
+        };
+
+        OrbitalElements {
+            semi_major_axis: 1.0 / (2.0 / r - v.length_squared() / mu),
+            eccentricity: e,
+            inclination: f64::atan2(h_dir.truncate().length(), h_dir.z),
+            longitude_of_ascending_node: f64::atan2(node_dir.y, node_dir.x).rem_euclid(TAU),
+            argument_of_periapsis: angle_around(node_dir, periapsis_dir, h_dir),
+            true_anomaly: angle_around(periapsis_dir, pos / r, h_dir),
+        }
+    }
+
+    /// Rotates from the perifocal frame, with X towards the periapsis and Z along the
+    /// orbit normal, to the reference frame.
+    fn perifocal_rotation(&self) -> DMat3 {
+        DMat3::from_rotation_z(self.longitude_of_ascending_node)
+            * DMat3::from_rotation_x(self.inclination)
+            * DMat3::from_rotation_z(self.argument_of_periapsis)
+    }
+
+    /// The point `r` away from the attractor where the body is at `true_anomaly`.
+    fn position_at(&self, true_anomaly: f64, r: f64) -> DVec3 {
+        let pos = DVec3::new(r * true_anomaly.cos(), r * true_anomaly.sin(), 0.0);
+        from_reference_frame(self.perifocal_rotation() * pos)
+    }
+
+    fn semi_latus_rectum(&self) -> f64 {
+        self.semi_major_axis * (1.0 - self.eccentricity * self.eccentricity)
+    }
+
+    /// The distance from the attractor at the true anomaly `nu`.
+    pub fn radius_at_true_anomaly(&self, nu: f64) -> f64 {
+        self.semi_latus_rectum() / (1.0 + self.eccentricity * nu.cos())
+    }
+
+    /// The point on the orbit at the true anomaly `nu`, relative to the attractor.
+    pub fn position_at_true_anomaly(&self, nu: f64) -> DVec3 {
+        self.position_at(nu, self.radius_at_true_anomaly(nu))
+    }
+
+    /// The position and velocity relative to the attractor, the inverse of [`OrbitalElements::from_state`].
+    pub fn to_state(self, m: f64) -> (DVec3, DVec3) {
+        let mu = G * m;
+        let e = self.eccentricity;
+        let nu = self.true_anomaly;
+        let p = self.semi_latus_rectum();
+        let v = f64::sqrt(mu / p) * DVec3::new(-nu.sin(), e + nu.cos(), 0.0);
+
+        (
+            self.position_at_true_anomaly(nu),
+            from_reference_frame(self.perifocal_rotation() * v),
+        )
+    }
+
+    /// The true anomaly `dt` seconds later, from solving Kepler's equation.
+    /// Only meaningful for closed orbits.
+    pub fn true_anomaly_at_time(&self, m: f64, dt: f64) -> f64 {
+        let e = self.eccentricity;
+        let mean_anomaly =
+            (self.mean_anomaly(self.true_anomaly) + self.mean_motion(m) * dt).rem_euclid(TAU);
+
+        // Newton's method on M = E - e sin E, starting at M is good enough below e = 0.8
+        let mut eccentric_anomaly = if e < 0.8 {
+            mean_anomaly
+        } else {
+            std::f64::consts::PI
+        };
+        for _ in 0..50 {
+            let step = (eccentric_anomaly - e * eccentric_anomaly.sin() - mean_anomaly)
+                / (1.0 - e * eccentric_anomaly.cos());
+            eccentric_anomaly -= step;
+            if step.abs() < 1e-12 {
+                break;
+            }
+        }
+
+        (2.0 * f64::atan2(
+            f64::sqrt(1.0 + e) * (eccentric_anomaly / 2.0).sin(),
+            f64::sqrt(1.0 - e) * (eccentric_anomaly / 2.0).cos(),
+        ))
+        .rem_euclid(TAU)
+    }
+
+    /// The seconds until the body next passes the true anomaly `nu`.
+    /// Only meaningful for closed orbits.
+    pub fn time_until(&self, m: f64, nu: f64) -> f64 {
+        let delta = self.mean_anomaly(nu) - self.mean_anomaly(self.true_anomaly);
+        delta.rem_euclid(TAU) / self.mean_motion(m)
+    }
+
+    /// The seconds until the body next passes the true anomaly `nu`, on open orbits as well.
+    /// `None` if an open orbit has already passed `nu` or never gets there.
+    pub fn time_of_flight(&self, m: f64, nu: f64) -> Option<f64> {
+        let e = self.eccentricity;
+        if e < 1.0 {
+            return Some(self.time_until(m, nu));
+        }
+
+        // along a hyperbola, the true anomaly only goes from one asymptote to the other
+        let asymptote = f64::acos(-1.0 / e);
+        let wrap = |nu: f64| (nu + std::f64::consts::PI).rem_euclid(TAU) - std::f64::consts::PI;
+        let (from, to) = (wrap(self.true_anomaly), wrap(nu));
+        if to.abs() >= asymptote || to < from {
+            return None;
+        }
+
+        // the hyperbolic counterparts of the eccentric and mean anomaly
+        let mean_anomaly = |nu: f64| {
+            let f = 2.0 * f64::atanh(f64::sqrt((e - 1.0) / (e + 1.0)) * (nu / 2.0).tan());
+            e * f.sinh() - f
+        };
+        let a = -self.semi_major_axis;
+        let mean_motion = f64::sqrt(G * m / (a * a * a));
+        Some((mean_anomaly(to) - mean_anomaly(from)) / mean_motion).filter(|t| t.is_finite())
+    }
+
+    /// The average angular speed over an orbit, in radians per second.
+    fn mean_motion(&self, m: f64) -> f64 {
+        let a = self.semi_major_axis;
+        f64::sqrt(G * m / (a * a * a))
+    }
+
+    /// The mean anomaly at the true anomaly `nu`.
+    /// https://en.wikipedia.org/wiki/Kepler%27s_equation
+    fn mean_anomaly(&self, nu: f64) -> f64 {
+        let e = self.eccentricity;
+        let eccentric_anomaly =
+            2.0 * f64::atan(f64::sqrt((1.0 - e) / (1.0 + e)) * (nu / 2.0).tan());
+        eccentric_anomaly - e * eccentric_anomaly.sin()
+    }
+
+    /// The position relative to the attractor `dt` seconds later.
+    /// Only meaningful for closed orbits.
+    pub fn position_at_time(&self, m: f64, dt: f64) -> DVec3 {
+        OrbitalElements {
+            true_anomaly: self.true_anomaly_at_time(m, dt),
+            ..*self
+        }
+        .to_state(m)
+        .0
+    }
+
+    /// The closest point to the attractor, relative to it.
+    pub fn periapsis_position(&self) -> DVec3 {
+        let r = self.semi_major_axis * (1.0 - self.eccentricity);
+        self.position_at(0.0, r)
+    }
+
+    /// The furthest point from the attractor, relative to it. Only meaningful for closed orbits.
+    pub fn apoapsis_position(&self) -> DVec3 {
+        let r = self.semi_major_axis * (1.0 + self.eccentricity);
+        self.position_at(std::f64::consts::PI, r)
+    }
+}
+
+/// Formats the elements as a single line of `key=value` pairs:
+///
+/// ```text
+/// a=<semi major axis> e=<eccentricity> i=<inclination> raan=<longitude of ascending node> argp=<argument of periapsis> nu=<true anomaly>
+/// ```
+///
+/// Distances are in meters and angles in degrees.
+impl fmt::Display for OrbitalElements {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a={} e={} i={} raan={} argp={} nu={}",
+            self.semi_major_axis,
+            self.eccentricity,
+            self.inclination.to_degrees(),
+            self.longitude_of_ascending_node.to_degrees(),
+            self.argument_of_periapsis.to_degrees(),
+            self.true_anomaly.to_degrees(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseElementsError {
+    /// A pair without a `=`.
+    MissingValue(String),
+    UnknownKey(String),
+    DuplicateKey(String),
+    MissingKey(&'static str),
+    InvalidNumber(String),
+    /// The semi major axis and eccentricity don't describe an orbit.
+    InvalidShape,
+    /// The true anomaly is at or beyond the asymptotes of the hyperbola, `acos(-1/e)`,
+    /// where the body would be infinitely far away.
+    UnreachableTrueAnomaly,
+}
+
+impl fmt::Display for ParseElementsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseElementsError::MissingValue(pair) => write!(f, "`{pair}` is not a key=value pair"),
+            ParseElementsError::UnknownKey(key) => write!(f, "unknown element `{key}`"),
+            ParseElementsError::DuplicateKey(key) => write!(f, "element `{key}` is given twice"),
+            ParseElementsError::MissingKey(key) => write!(f, "element `{key}` is missing"),
+            ParseElementsError::InvalidNumber(value) => write!(f, "`{value}` is not a number"),
+            ParseElementsError::InvalidShape => write!(
+                f,
+                "the semi major axis must be positive for ellipses and negative for hyperbolas"
+            ),
+            ParseElementsError::UnreachableTrueAnomaly => {
+                write!(
+                    f,
+                    "the true anomaly is beyond the asymptotes of the hyperbola"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseElementsError {}
+
+/// Parses the format written by the [`fmt::Display`] impl. The pairs may come in any order.
+impl FromStr for OrbitalElements {
+    type Err = ParseElementsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const KEYS: [&str; 6] = ["a", "e", "i", "raan", "argp", "nu"];
+        let mut values = [None; 6];
+
+        for pair in s.split_whitespace() {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| ParseElementsError::MissingValue(pair.to_owned()))?;
+            let index = KEYS
+                .iter()
+                .position(|&k| k == key)
+                .ok_or_else(|| ParseElementsError::UnknownKey(key.to_owned()))?;
+            let value = value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| ParseElementsError::InvalidNumber(value.to_owned()))?;
+
+            if values[index].replace(value).is_some() {
+                return Err(ParseElementsError::DuplicateKey(key.to_owned()));
+            }
+        }
+
+        let mut values = KEYS
+            .iter()
+            .zip(values)
+            .map(|(&key, value)| value.ok_or(ParseElementsError::MissingKey(key)));
+        let mut next = || values.next().unwrap();
+
+        let elements = OrbitalElements {
+            semi_major_axis: next()?,
+            eccentricity: next()?,
+            inclination: next()?.to_radians(),
+            longitude_of_ascending_node: next()?.to_radians(),
+            argument_of_periapsis: next()?.to_radians(),
+            true_anomaly: next()?.to_radians(),
+        };
+
+        let a = elements.semi_major_axis;
+        let e = elements.eccentricity;
+        let ellipse = a > 0.0 && (0.0..1.0).contains(&e);
+        let hyperbola = a < 0.0 && e > 1.0;
+        if !ellipse && !hyperbola {
+            return Err(ParseElementsError::InvalidShape);
+        }
+        // the radius is only positive and finite between the asymptotes
+        if hyperbola && 1.0 + e * elements.true_anomaly.cos() <= 0.0 {
+            return Err(ParseElementsError::UnreachableTrueAnomaly);
+        }
+
+        Ok(elements)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use glam::DVec2;
+    use glam::{DVec2, DVec3};
+
+    use super::{
+        circularization_dv, escape_dv, hohmann_dv, phasing_orbit_dv, plane_change_dv,
+        specific_energy, Orbit, OrbitalElements, ParseElementsError, G,
+    };
 
     #[test]
     fn geostationary() {
@@ -67,4 +514,364 @@ mod tests {
             orbit.eccentricity
         );
     }
+
+    const EARTH_MASS: f64 = 5.972e24;
+
+    #[test]
+    fn time_until_apoapsis() {
+        let elements: OrbitalElements =
+            "a=8000000 e=0.3 i=10 raan=20 argp=30 nu=0".parse().unwrap();
+        let period = Orbit {
+            semi_major_axis: elements.semi_major_axis,
+            eccentricity: elements.eccentricity,
+        }
+        .period(EARTH_MASS);
+
+        let to_apoapsis = elements.time_until(EARTH_MASS, std::f64::consts::PI);
+        assert!((to_apoapsis - period / 2.0).abs() < 1e-6, "{to_apoapsis}");
+
+        let later = OrbitalElements {
+            true_anomaly: elements.true_anomaly_at_time(EARTH_MASS, 100.0),
+            ..elements
+        };
+        assert!((later.time_until(EARTH_MASS, 0.0) - (period - 100.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn current_true_anomaly_is_on_the_ship() {
+        let pos = DVec3::new(7.0e6, 1.0e6, -2.0e5);
+        let v = DVec3::new(500.0, 300.0, -7800.0);
+        let elements = OrbitalElements::from_state(EARTH_MASS, pos, v);
+
+        let on_orbit = elements.position_at_true_anomaly(elements.true_anomaly);
+        assert!(on_orbit.distance(pos) < 1e-3, "{on_orbit} == {pos}");
+        assert!(
+            (elements.radius_at_true_anomaly(0.0) - elements.periapsis_position().length()).abs()
+                < 1e-3
+        );
+    }
+
+    #[test]
+    fn circularizing_reaches_circular_speed() {
+        let orbit = Orbit {
+            semi_major_axis: 8.0e6,
+            eccentricity: 0.2,
+        };
+        let r = orbit.apoapsis();
+
+        let dv = circularization_dv(EARTH_MASS, &orbit);
+        let circular_speed = f64::sqrt(G * EARTH_MASS / r);
+        assert!(dv > 0.0);
+        assert!((orbit.speed_at(EARTH_MASS, r) + dv - circular_speed).abs() < 1e-9);
+    }
+
+    #[test]
+    fn hohmann_to_geostationary() {
+        let leo = Orbit {
+            semi_major_axis: 6.678e6,
+            eccentricity: 0.0,
+        };
+        let (first, second) = hohmann_dv(EARTH_MASS, &leo, 42.164e6);
+
+        // about the textbook 2.43 + 1.47 km/s, G is a bit rounded
+        assert!((first - 2425.0).abs() < 25.0, "{first}");
+        assert!((second - 1466.0).abs() < 15.0, "{second}");
+
+        let (down, circularize) = hohmann_dv(EARTH_MASS, &leo, 6.5e6);
+        assert!(down < 0.0 && circularize < 0.0, "{down} {circularize}");
+    }
+
+    #[test]
+    fn escape_from_circular_orbit() {
+        let orbit = Orbit {
+            semi_major_axis: 7.0e6,
+            eccentricity: 0.0,
+        };
+        let v = orbit.speed_at(EARTH_MASS, 7.0e6);
+        let dv = escape_dv(EARTH_MASS, &orbit);
+        assert!(
+            (dv - (std::f64::consts::SQRT_2 - 1.0) * v).abs() < 1e-6,
+            "{dv}"
+        );
+    }
+
+    #[test]
+    fn plane_change_turns_velocity() {
+        assert_eq!(plane_change_dv(100.0, 0.0), 0.0);
+        // an equilateral triangle
+        let dv = plane_change_dv(100.0, std::f64::consts::FRAC_PI_3);
+        assert!((dv - 100.0).abs() < 1e-9, "{dv}");
+        let dv = plane_change_dv(100.0, std::f64::consts::PI);
+        assert!((dv - 200.0).abs() < 1e-9, "{dv}");
+    }
+
+    #[test]
+    fn specific_energy_from_the_semi_major_axis() {
+        let r = 7.0e6;
+        let pos = DVec2::new(r, 0.0);
+        let v = DVec2::new(100.0, 8000.0);
+        let orbit = Orbit::from_pos_dir(EARTH_MASS, pos, v);
+        let energy = specific_energy(EARTH_MASS, r, v.length());
+        let expected = -G * EARTH_MASS / (2.0 * orbit.semi_major_axis);
+        assert!(
+            (energy - expected).abs() < 1e-6 * expected.abs(),
+            "{energy} == {expected}"
+        );
+
+        let escape = f64::sqrt(2.0 * G * EARTH_MASS / r);
+        assert!(specific_energy(EARTH_MASS, r, escape).abs() < 1e-6);
+    }
+
+    #[test]
+    fn escape_speed_is_parabolic() {
+        let r = 7.0e6;
+        let v = f64::sqrt(2.0 * G * EARTH_MASS / r);
+        let orbit = Orbit::from_pos_dir(EARTH_MASS, DVec2::new(r, 0.0), DVec2::new(0.0, v));
+
+        assert!(orbit.semi_major_axis.abs() > 1e6 * r, "{orbit:?}");
+        assert!((orbit.eccentricity - 1.0).abs() < 1e-6, "{orbit:?}");
+        assert_eq!(orbit.apoapsis(), f64::INFINITY);
+
+        let parabola = Orbit {
+            semi_major_axis: f64::INFINITY,
+            eccentricity: 1.0,
+        };
+        assert!(!parabola.is_closed());
+        assert_eq!(parabola.apoapsis(), f64::INFINITY);
+    }
+
+    #[test]
+    fn beyond_escape_speed_is_hyperbolic() {
+        let r = 7.0e6;
+        let v = 1.5 * f64::sqrt(2.0 * G * EARTH_MASS / r);
+        let orbit = Orbit::from_pos_dir(EARTH_MASS, DVec2::new(r, 0.0), DVec2::new(0.0, v));
+
+        assert!(orbit.semi_major_axis < 0.0, "{orbit:?}");
+        assert!(orbit.eccentricity > 1.0, "{orbit:?}");
+        assert!(!orbit.is_closed());
+        assert_eq!(orbit.apoapsis(), f64::INFINITY);
+        // launched perpendicular to the radius, so it's at periapsis right now
+        assert!(
+            (orbit.periapsis() - r).abs() < 1e-6 * r,
+            "{}",
+            orbit.periapsis()
+        );
+    }
+
+    fn assert_close(a: DVec3, b: DVec3) {
+        assert!((a - b).length() <= 1e-6 * b.length().max(1.0), "{a} == {b}");
+    }
+
+    fn assert_round_trip(elements: &str) {
+        let elements: OrbitalElements = elements.parse().unwrap();
+        let (pos, v) = elements.to_state(EARTH_MASS);
+
+        let computed = OrbitalElements::from_state(EARTH_MASS, pos, v);
+        let parsed: OrbitalElements = computed.to_string().parse().unwrap();
+        let (parsed_pos, parsed_v) = parsed.to_state(EARTH_MASS);
+
+        assert_close(parsed_pos, pos);
+        assert_close(parsed_v, v);
+        assert!(
+            (computed.semi_major_axis - elements.semi_major_axis).abs()
+                < 1e-6 * elements.semi_major_axis.abs(),
+            "{computed} == {elements}"
+        );
+        assert!((computed.eccentricity - elements.eccentricity).abs() < 1e-9);
+        assert!((computed.inclination - elements.inclination).abs() < 1e-9);
+    }
+
+    #[test]
+    fn phasing_ten_minutes() {
+        // a circular orbit at 7000 km around the earth (GM = 398600 km^3/s^2),
+        // which takes 5828.5 s for one revolution
+        let m = 3.986e14 / G;
+        let orbit = Orbit {
+            semi_major_axis: 7.0e6,
+            eccentricity: 0.0,
+        };
+        assert!((orbit.period(m) - 5828.52).abs() < 0.01);
+
+        // falling back by 10 minutes needs an orbit with a = 7472.5 km,
+        // 7780.97 m/s at periapsis instead of 7546.05 m/s
+        let dv = phasing_orbit_dv(m, &orbit, 600.0);
+        assert!((dv - 234.92).abs() < 0.01, "{dv}");
+
+        // catching up by 10 minutes dips down to a = 6511.0 km
+        let dv = phasing_orbit_dv(m, &orbit, -600.0);
+        assert!((dv + 288.92).abs() < 0.01, "{dv}");
+
+        assert!(phasing_orbit_dv(m, &orbit, 0.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn round_trip_equatorial() {
+        assert_round_trip("a=7000000 e=0.1 i=0 raan=0 argp=30 nu=45");
+    }
+
+    #[test]
+    fn round_trip_polar() {
+        assert_round_trip("a=8000000 e=0.05 i=90 raan=120 argp=60 nu=200");
+    }
+
+    #[test]
+    fn round_trip_retrograde() {
+        assert_round_trip("a=9000000 e=0.2 i=150 raan=300 argp=270 nu=10");
+    }
+
+    #[test]
+    fn round_trip_eccentric() {
+        assert_round_trip("a=30000000 e=0.8 i=28.5 raan=45 argp=90 nu=180");
+    }
+
+    #[test]
+    fn round_trip_hyperbolic() {
+        assert_round_trip("a=-20000000 e=1.5 i=10 raan=20 argp=30 nu=40");
+    }
+
+    #[test]
+    fn equatorial_prograde_axes() {
+        let r = 7.0e6;
+        let v = f64::sqrt(super::G * EARTH_MASS / r);
+        let elements = OrbitalElements::from_state(
+            EARTH_MASS,
+            DVec3::new(r, 0.0, 0.0),
+            DVec3::new(0.0, 0.0, -v),
+        );
+
+        assert!(elements.eccentricity < 1e-9);
+        assert!(elements.inclination < 1e-9);
+        assert!(
+            elements.true_anomaly < 1e-9 || elements.true_anomaly > std::f64::consts::TAU - 1e-9
+        );
+    }
+
+    #[test]
+    fn apsis_positions() {
+        let r = 7.0e6;
+        let v = f64::sqrt(G * EARTH_MASS / r);
+        let circular = OrbitalElements::from_state(
+            EARTH_MASS,
+            DVec3::new(0.0, r, 0.0),
+            DVec3::new(v, 0.0, 0.0),
+        );
+        let periapsis = circular.periapsis_position();
+        let apoapsis = circular.apoapsis_position();
+        assert!((periapsis.length() - r).abs() < 1e-3, "{periapsis}");
+        assert!((apoapsis.length() - r).abs() < 1e-3, "{apoapsis}");
+        assert_close(apoapsis, -periapsis);
+
+        let eccentric: OrbitalElements = "a=30000000 e=0.8 i=28.5 raan=45 argp=90 nu=123"
+            .parse()
+            .unwrap();
+        let at_periapsis = OrbitalElements {
+            true_anomaly: 0.0,
+            ..eccentric
+        };
+        let at_apoapsis = OrbitalElements {
+            true_anomaly: std::f64::consts::PI,
+            ..eccentric
+        };
+        assert_close(
+            eccentric.periapsis_position(),
+            at_periapsis.to_state(EARTH_MASS).0,
+        );
+        assert_close(
+            eccentric.apoapsis_position(),
+            at_apoapsis.to_state(EARTH_MASS).0,
+        );
+        assert!((eccentric.periapsis_position().length() - 6.0e6).abs() < 1e-3);
+    }
+
+    #[test]
+    fn true_anomaly_over_time() {
+        let elements: OrbitalElements = "a=30000000 e=0.6 i=10 raan=0 argp=0 nu=0".parse().unwrap();
+        let period = Orbit {
+            semi_major_axis: elements.semi_major_axis,
+            eccentricity: elements.eccentricity,
+        }
+        .period(EARTH_MASS);
+
+        let at = |dt| elements.true_anomaly_at_time(EARTH_MASS, dt);
+        assert!(at(0.0).abs() < 1e-9);
+        assert!((at(period / 2.0) - std::f64::consts::PI).abs() < 1e-9);
+        let full = at(period);
+        assert!(full.min(std::f64::consts::TAU - full) < 1e-9, "{full}");
+        // it's fastest at the periapsis, so the first quarter covers more than a quarter turn
+        assert!(at(period / 4.0) > std::f64::consts::FRAC_PI_2);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let parse = |s: &str| s.parse::<OrbitalElements>().unwrap_err();
+
+        assert_eq!(
+            parse("a=1 e=0 i=0 raan=0 argp=0"),
+            ParseElementsError::MissingKey("nu")
+        );
+        assert_eq!(
+            parse("a=1 e=0 i=0 raan=0 argp=0 nu=0 x=1"),
+            ParseElementsError::UnknownKey("x".to_owned())
+        );
+        assert_eq!(
+            parse("a=1 a=2"),
+            ParseElementsError::DuplicateKey("a".to_owned())
+        );
+        assert_eq!(
+            parse("a=big e=0"),
+            ParseElementsError::InvalidNumber("big".to_owned())
+        );
+        assert_eq!(parse("a"), ParseElementsError::MissingValue("a".to_owned()));
+        assert_eq!(
+            parse("a=-1 e=0.5 i=0 raan=0 argp=0 nu=0"),
+            ParseElementsError::InvalidShape
+        );
+        assert_eq!(
+            parse("a=1 e=nan i=0 raan=0 argp=0 nu=0"),
+            ParseElementsError::InvalidNumber("nan".to_owned())
+        );
+        // the asymptotes of e=2 are at ±120°
+        assert_eq!(
+            parse("a=-1 e=2 i=0 raan=0 argp=0 nu=121"),
+            ParseElementsError::UnreachableTrueAnomaly
+        );
+        assert_eq!(
+            parse("a=-1 e=2 i=0 raan=0 argp=0 nu=-150"),
+            ParseElementsError::UnreachableTrueAnomaly
+        );
+        assert!("a=-1 e=2 i=0 raan=0 argp=0 nu=119"
+            .parse::<OrbitalElements>()
+            .is_ok());
+    }
+
+    #[test]
+    fn hyperbolic_time_of_flight() {
+        use std::f64::consts::PI;
+
+        // G * m = 1e6 and a^3 = 1e9, so the mean motion is sqrt(1e-3)
+        let m = 1e6 / G;
+        let elements = OrbitalElements {
+            semi_major_axis: -1000.0,
+            eccentricity: 2.0,
+            inclination: 0.0,
+            longitude_of_ascending_node: 0.0,
+            argument_of_periapsis: 0.0,
+            true_anomaly: 0.0,
+        };
+
+        // cosh F = 2 at a right angle, so M = 2 sqrt(3) - acosh(2)
+        let expected = (2.0 * 3f64.sqrt() - 2f64.acosh()) / 1e-3f64.sqrt();
+        let t = elements.time_of_flight(m, PI / 2.0).unwrap();
+        assert!((t - expected).abs() < 1e-6, "{t} {expected}");
+
+        let approaching = OrbitalElements {
+            true_anomaly: -PI / 2.0,
+            ..elements
+        };
+        let t = approaching.time_of_flight(m, PI / 2.0).unwrap();
+        assert!((t - 2.0 * expected).abs() < 1e-6, "{t}");
+
+        assert_eq!(elements.time_of_flight(m, -PI / 2.0), None);
+        assert_eq!(elements.time_of_flight(m, 2.5), None);
+    }
 }
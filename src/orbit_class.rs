@@ -0,0 +1,227 @@
+//! A short label for the current orbit, from its shape, whether it clears the surface and
+//! whether the atmosphere is pulling it down.
+
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    atmosphere::Atmosphere,
+    decay::OrbitDecay,
+    hud::WarningText,
+    orbit::Orbit,
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    GravityAttractor, Planet, Spaceship,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrbitClass {
+    Circular,
+    Elliptical,
+    HighlyElliptical,
+    Parabolic,
+    Hyperbolic,
+    Suborbital,
+    Decaying,
+}
+
+impl OrbitClass {
+    fn color(self) -> Color {
+        match self {
+            OrbitClass::Circular | OrbitClass::Elliptical | OrbitClass::HighlyElliptical => {
+                Color::WHITE
+            }
+            OrbitClass::Parabolic | OrbitClass::Hyperbolic => Color::ORANGE,
+            OrbitClass::Suborbital => Color::RED,
+            OrbitClass::Decaying => Color::YELLOW,
+        }
+    }
+}
+
+impl fmt::Display for OrbitClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OrbitClass::Circular => "Circular",
+            OrbitClass::Elliptical => "Elliptical",
+            OrbitClass::HighlyElliptical => "Highly Elliptical",
+            OrbitClass::Parabolic => "Parabolic (escape)",
+            OrbitClass::Hyperbolic => "Hyperbolic (escape)",
+            OrbitClass::Suborbital => "Suborbital (will impact)",
+            OrbitClass::Decaying => "Decaying",
+        })
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct OrbitClassifier {
+    pub enabled: bool,
+    /// Orbits below this eccentricity count as circular.
+    pub circular_below: f64,
+    /// Orbits from this eccentricity on count as highly elliptical.
+    pub highly_elliptical_from: f64,
+    /// How far the eccentricity may be from 1 for a parabola, nothing is ever exactly one.
+    pub parabolic_tolerance: f64,
+    /// The periapsis has to be within this many scale heights of the atmosphere for drag to
+    /// decay the orbit, above that there's next to no air left.
+    pub decay_scale_heights: f64,
+}
+
+impl Default for OrbitClassifier {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            circular_below: 0.01,
+            highly_elliptical_from: 0.5,
+            parabolic_tolerance: 1e-3,
+            decay_scale_heights: 8.0,
+        }
+    }
+}
+
+impl OrbitClassifier {
+    /// Classifies `orbit` around a planet with `radius`. Closed orbits with the periapsis
+    /// below the surface hit it, even by a little. Escape trajectories are classified as
+    /// such whatever their periapsis, as they might be on their way out already.
+    pub fn classify(&self, orbit: &Orbit, radius: f64, decaying: bool) -> OrbitClass {
+        let e = orbit.eccentricity;
+        if (e - 1.0).abs() < self.parabolic_tolerance {
+            OrbitClass::Parabolic
+        } else if !orbit.is_closed() {
+            OrbitClass::Hyperbolic
+        } else if orbit.periapsis() < radius {
+            OrbitClass::Suborbital
+        } else if decaying {
+            OrbitClass::Decaying
+        } else if e < self.circular_below {
+            OrbitClass::Circular
+        } else if e < self.highly_elliptical_from {
+            OrbitClass::Elliptical
+        } else {
+            OrbitClass::HighlyElliptical
+        }
+    }
+
+    /// Whether the periapsis dips deep enough into `atmosphere` for drag to matter.
+    pub fn in_drag(&self, atmosphere: &Atmosphere, periapsis_altitude: f64) -> bool {
+        periapsis_altitude < self.decay_scale_heights * atmosphere.scale_height
+    }
+}
+
+/// Shows the class of the orbit around the reference body under the alerts.
+pub fn update_orbit_class(
+    classifier: Res<OrbitClassifier>,
+    decay: Res<OrbitDecay>,
+    reference: Res<ReferenceBody>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<
+        (
+            Entity,
+            &Transform,
+            &GravityAttractor,
+            &Planet,
+            Option<&Atmosphere>,
+        ),
+        Without<Spaceship>,
+    >,
+    velocities: BodyVelocities,
+    mut text_query: Query<&mut Text, With<WarningText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let section = &mut text.sections[5];
+    let Ok((ship, velocity)) = query.get_single() else {
+        return;
+    };
+    let (true, Some((body, body_transform, gravity, planet, atmosphere))) = (
+        classifier.enabled,
+        reference_attractor(
+            &reference,
+            &body_query,
+            ship.translation,
+            |(_, t, g, ..)| (t.translation, g.mass),
+        ),
+    ) else {
+        section.value = "-".to_owned();
+        return;
+    };
+
+    let orbit = Orbit::from_pos_dir_3d(
+        gravity.mass,
+        (ship.translation - body_transform.translation).as_dvec3(),
+        (velocity.linvel - velocities.of(body)).as_dvec3(),
+    );
+    // the measured decay lags an orbit behind, the atmosphere tells right away
+    let measured = decay.last_change.is_some_and(|(pe, _)| pe < 0.0);
+    let decaying = atmosphere.is_some_and(|atmosphere| {
+        classifier.in_drag(atmosphere, orbit.periapsis() - planet.radius) || measured
+    });
+
+    let class = classifier.classify(&orbit, planet.radius, decaying);
+    section.value = class.to_string();
+    section.style.color = class.color();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrbitClass, OrbitClassifier};
+    use crate::{atmosphere::Atmosphere, orbit::Orbit};
+
+    const RADIUS: f64 = 1000.0;
+
+    fn classify(semi_major_axis: f64, eccentricity: f64, decaying: bool) -> OrbitClass {
+        let orbit = Orbit {
+            semi_major_axis,
+            eccentricity,
+        };
+        OrbitClassifier::default().classify(&orbit, RADIUS, decaying)
+    }
+
+    #[test]
+    fn closed_orbits_by_eccentricity() {
+        assert_eq!(classify(1500.0, 0.0, false), OrbitClass::Circular);
+        assert_eq!(classify(1500.0, 0.009, false), OrbitClass::Circular);
+        assert_eq!(classify(1500.0, 0.01, false), OrbitClass::Elliptical);
+        assert_eq!(classify(1500.0, 0.3, false), OrbitClass::Elliptical);
+        assert_eq!(classify(3000.0, 0.5, false), OrbitClass::HighlyElliptical);
+        assert_eq!(classify(30000.0, 0.95, false), OrbitClass::HighlyElliptical);
+    }
+
+    #[test]
+    fn escape_trajectories() {
+        assert_eq!(classify(f64::INFINITY, 1.0, false), OrbitClass::Parabolic);
+        assert_eq!(classify(-1e9, 1.0005, false), OrbitClass::Parabolic);
+        assert_eq!(classify(-2000.0, 1.5, false), OrbitClass::Hyperbolic);
+        // on the way out even though the periapsis was below the surface
+        assert_eq!(classify(-200.0, 1.5, false), OrbitClass::Hyperbolic);
+    }
+
+    #[test]
+    fn suborbital_just_below_the_surface() {
+        // periapsis at 999.9
+        assert_eq!(
+            classify(1100.0, 1.0 - 999.9 / 1100.0, false),
+            OrbitClass::Suborbital
+        );
+        assert_eq!(
+            classify(1100.0, 1.0 - 1000.1 / 1100.0, false),
+            OrbitClass::Elliptical
+        );
+        // impact beats decay
+        assert_eq!(classify(500.0, 0.1, true), OrbitClass::Suborbital);
+    }
+
+    #[test]
+    fn decaying_in_the_atmosphere() {
+        assert_eq!(classify(1100.0, 0.0, true), OrbitClass::Decaying);
+
+        let classifier = OrbitClassifier::default();
+        let atmosphere = Atmosphere {
+            surface_density: 1.0,
+            scale_height: 10.0,
+        };
+        assert!(classifier.in_drag(&atmosphere, 50.0));
+        assert!(!classifier.in_drag(&atmosphere, 100.0));
+    }
+}
@@ -0,0 +1,294 @@
+//! The full orbital reference frame at the attractor: the orbital plane, the line of apsides,
+//! the line of nodes and the orbit normal, with labels for the apsides and nodes.
+
+use std::f64::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    input::InputBindings,
+    orbit::OrbitalElements,
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    GravityAttractor, Spaceship,
+};
+
+/// The parts of the orbit frame, each can be turned off on its own.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct OrbitFrame {
+    pub enabled: bool,
+    pub plane: bool,
+    pub apsides: bool,
+    pub nodes: bool,
+    pub normal: bool,
+    pub labels: bool,
+}
+
+impl Default for OrbitFrame {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            plane: true,
+            apsides: true,
+            nodes: true,
+            normal: true,
+            labels: true,
+        }
+    }
+}
+
+/// Below this inclination, the orbit counts as equatorial and has no line of nodes.
+const EQUATORIAL_INCLINATION: f64 = 1e-4;
+
+/// The direction of the ascending node, where the orbit with angular momentum along `normal`
+/// goes up through the equatorial plane perpendicular to `pole`.
+/// `None` if the orbit lies in the equatorial plane.
+pub fn ascending_node_direction(normal: Vec3, pole: Vec3) -> Option<Vec3> {
+    let node = pole.cross(normal);
+    (node.length() > EQUATORIAL_INCLINATION as f32 * normal.length()).then(|| node.normalize())
+}
+
+/// The point on the orbit at the true anomaly `nu`, if it is reached at all.
+fn point_at(elements: &OrbitalElements, nu: f64) -> Option<Vec3> {
+    let r = elements.radius_at_true_anomaly(nu);
+    (r.is_finite() && r > 0.0).then(|| elements.position_at_true_anomaly(nu).as_vec3())
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameLabel {
+    Periapsis,
+    Apoapsis,
+    AscendingNode,
+    DescendingNode,
+}
+
+impl FrameLabel {
+    const ALL: [FrameLabel; 4] = [
+        FrameLabel::Periapsis,
+        FrameLabel::Apoapsis,
+        FrameLabel::AscendingNode,
+        FrameLabel::DescendingNode,
+    ];
+
+    fn text(self) -> &'static str {
+        match self {
+            FrameLabel::Periapsis => "Pe",
+            FrameLabel::Apoapsis => "Ap",
+            FrameLabel::AscendingNode => "AN",
+            FrameLabel::DescendingNode => "DN",
+        }
+    }
+
+    /// Where the label sits relative to the attractor, with the ascending node at `nu_an`.
+    fn position(self, elements: &OrbitalElements, nu_an: f64) -> Option<Vec3> {
+        match self {
+            FrameLabel::Periapsis => point_at(elements, 0.0),
+            FrameLabel::Apoapsis => point_at(elements, PI),
+            FrameLabel::AscendingNode => point_at(elements, nu_an),
+            FrameLabel::DescendingNode => point_at(elements, nu_an + PI),
+        }
+    }
+}
+
+pub fn spawn_frame_labels(mut commands: Commands) {
+    for label in FrameLabel::ALL {
+        commands.spawn((
+            TextBundle::from_section(
+                label.text(),
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                ..default()
+            }),
+            label,
+        ));
+    }
+}
+
+pub fn toggle_orbit_frame(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut frame: ResMut<OrbitFrame>,
+) {
+    if keyboard_input.just_pressed(bindings.orbit_frame) {
+        frame.enabled = !frame.enabled;
+    }
+}
+
+/// The plane is drawn as white rings out to the apoapsis, the line of apsides from the
+/// periapsis (red) to the apoapsis (blue), the line of nodes in yellow and the normal in pink.
+pub fn draw_orbit_frame(
+    frame: Res<OrbitFrame>,
+    ship_query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    reference: Res<ReferenceBody>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut label_query: Query<(&FrameLabel, &mut Style, &mut Visibility)>,
+    mut gizmos: Gizmos,
+) {
+    let mut labels_at = Vec::new();
+    draw_frame(
+        &frame,
+        &ship_query,
+        &body_query,
+        &velocities,
+        &reference,
+        &mut gizmos,
+        &mut labels_at,
+    );
+
+    let camera = camera_query.iter().find(|(camera, _)| camera.is_active);
+    for (label, mut style, mut visibility) in &mut label_query {
+        let on_screen = labels_at
+            .iter()
+            .find(|&&(l, _)| l == *label)
+            .zip(camera)
+            .and_then(|(&(_, world), (camera, transform))| {
+                camera.world_to_viewport(transform, world)
+            });
+        match on_screen {
+            Some(screen) => {
+                style.left = Val::Px(screen.x + 6.0);
+                style.top = Val::Px(screen.y - 8.0);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn draw_frame(
+    frame: &OrbitFrame,
+    ship_query: &Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: &Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: &BodyVelocities,
+    reference: &ReferenceBody,
+    gizmos: &mut Gizmos,
+    labels_at: &mut Vec<(FrameLabel, Vec3)>,
+) {
+    if !frame.enabled {
+        return;
+    }
+    let Ok((ship, velocity)) = ship_query.get_single() else {
+        return;
+    };
+    let Some((body, body_transform, gravity)) =
+        reference_attractor(reference, body_query, ship.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+    let center = body_transform.translation;
+    let pos = ship.translation - center;
+    let vel = velocity.linvel - velocities.of(body);
+    let Some(normal) = pos.cross(vel).try_normalize() else {
+        return;
+    };
+    let elements = OrbitalElements::from_state(gravity.mass, pos.as_dvec3(), vel.as_dvec3());
+    let periapsis = point_at(&elements, 0.0);
+    let apoapsis = point_at(&elements, PI);
+    let size = apoapsis
+        .or(periapsis.map(|p| p * 2.0))
+        .map_or(pos.length(), Vec3::length)
+        .max(pos.length());
+
+    if frame.plane {
+        for ring in 1..=3 {
+            let radius = size * ring as f32 / 3.0;
+            gizmos.circle(center, normal, radius, Color::rgba(1.0, 1.0, 1.0, 0.3));
+        }
+    }
+
+    if frame.apsides {
+        if let Some(periapsis) = periapsis {
+            // an open orbit has no apoapsis, the line goes just as far the other way
+            let apoapsis = apoapsis.unwrap_or(-periapsis.normalize() * size);
+            gizmos.line_gradient(
+                center + periapsis,
+                center + apoapsis,
+                Color::ORANGE_RED,
+                Color::AQUAMARINE,
+            );
+        }
+    }
+
+    // the game frame uses +Y as the pole, just like the orbital elements
+    let ascending_node = ascending_node_direction(normal, Vec3::Y);
+    let nu_an = (TAU - elements.argument_of_periapsis).rem_euclid(TAU);
+    if let (true, Some(node)) = (frame.nodes, ascending_node) {
+        gizmos.line(center - node * size, center + node * size, Color::YELLOW);
+        if let Some(at) = point_at(&elements, nu_an) {
+            gizmos.sphere(center + at, Quat::IDENTITY, size * 0.02, Color::YELLOW);
+        }
+    }
+
+    if frame.normal {
+        gizmos.ray(center, normal * size * 0.5, Color::PINK);
+    }
+
+    if frame.labels {
+        for label in FrameLabel::ALL {
+            let is_node = matches!(
+                label,
+                FrameLabel::AscendingNode | FrameLabel::DescendingNode
+            );
+            if is_node && (ascending_node.is_none() || !frame.nodes) {
+                continue;
+            }
+            if !is_node && !frame.apsides {
+                continue;
+            }
+            if let Some(at) = label.position(&elements, nu_an) {
+                labels_at.push((label, center + at));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use bevy::prelude::Vec3;
+    use glam::DVec3;
+
+    use super::{ascending_node_direction, point_at};
+    use crate::orbit::OrbitalElements;
+
+    #[test]
+    fn ascending_node_goes_up() {
+        let m = 5.972e24;
+        let pos = DVec3::new(7.0e6, 1.0e6, 0.0);
+        let v = DVec3::new(0.0, 2000.0, -7500.0);
+        let elements = OrbitalElements::from_state(m, pos, v);
+
+        let normal = pos.cross(v).as_vec3().normalize();
+        let node = ascending_node_direction(normal, Vec3::Y).unwrap();
+        let nu_an = (TAU - elements.argument_of_periapsis).rem_euclid(TAU);
+        let at = point_at(&elements, nu_an).unwrap();
+
+        assert!(at.y.abs() < 1.0, "{at}");
+        assert!(at.normalize().abs_diff_eq(node, 1e-4), "{at} along {node}");
+        // moving up there
+        let later = OrbitalElements {
+            true_anomaly: nu_an,
+            ..elements
+        }
+        .to_state(m)
+        .1;
+        assert!(later.y > 0.0, "{later}");
+    }
+
+    #[test]
+    fn equatorial_orbit_has_no_nodes() {
+        assert_eq!(ascending_node_direction(Vec3::Y, Vec3::Y), None);
+        assert_eq!(ascending_node_direction(-Vec3::Y, Vec3::Y), None);
+    }
+}
@@ -0,0 +1,229 @@
+//! The ship's orbit as a line, which with `--orbit-line-on-top` stays visible behind the planet,
+//! dashed and dimmed there.
+
+use bevy::{gizmos::GizmoConfig, prelude::*};
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    orbit::OrbitalElements,
+    predict::{draw_continuation_arrow, PredictionLimits},
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    snapshot::orbit_points,
+    GravityAttractor, Planet, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct OrbitLine {
+    pub enabled: bool,
+    pub color: Color,
+    pub occluded_color: Color,
+    /// Draws all gizmos on top of the meshes, otherwise the planet hides the occluded part
+    /// of the line anyway. Off unless `--orbit-line-on-top` is given, as it's a setting of
+    /// all gizmos, not just this line.
+    pub draw_on_top: bool,
+}
+
+impl Default for OrbitLine {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            color: Color::WHITE,
+            occluded_color: Color::rgba(1.0, 1.0, 1.0, 0.3),
+            draw_on_top: false,
+        }
+    }
+}
+
+impl OrbitLine {
+    pub fn from_args() -> Self {
+        Self {
+            draw_on_top: std::env::args().any(|arg| arg == "--orbit-line-on-top"),
+            ..default()
+        }
+    }
+}
+
+/// Whether the line of sight from `eye` to `point` passes through the sphere at `center`.
+/// A point on the surface facing the eye isn't occluded.
+pub fn sphere_occludes(eye: Vec3, point: Vec3, center: Vec3, radius: f32) -> bool {
+    let to_point = point - eye;
+    let length = to_point.length();
+    let Some(dir) = to_point.try_normalize() else {
+        return false;
+    };
+    // the closest approach of the line of sight to the center
+    let along = (center - eye).dot(dir);
+    let closest = eye + dir * along.clamp(0.0, length);
+    let distance_squared = closest.distance_squared(center);
+    if distance_squared >= radius * radius {
+        return false;
+    }
+    // where the line of sight enters the sphere
+    let entry = along - (radius * radius - distance_squared).sqrt();
+    entry < length - radius * 1e-3
+}
+
+/// Segments of the open orbit ahead of the ship.
+const OPEN_ORBIT_SEGMENTS: usize = 128;
+
+/// The open orbit from the ship on, relative to the attractor with mass `m`, until `limits`
+/// cut it off on the way out. The limits are always reached before the asymptote.
+fn open_orbit_ahead(elements: &OrbitalElements, m: f64, limits: &PredictionLimits) -> Vec<Vec3> {
+    let asymptote = f64::acos(-1.0 / elements.eccentricity);
+    let start = (elements.true_anomaly + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU)
+        - std::f64::consts::PI;
+    (0..=OPEN_ORBIT_SEGMENTS)
+        .map(|i| start + (asymptote - start) * i as f64 / OPEN_ORBIT_SEGMENTS as f64)
+        .take_while(|&nu| {
+            let r = elements.radius_at_true_anomaly(nu);
+            let within_time = elements
+                .time_of_flight(m, nu)
+                .is_some_and(|t| t <= limits.max_time);
+            r > 0.0 && r <= limits.max_distance && within_time
+        })
+        .map(|nu| elements.position_at_true_anomaly(nu).as_vec3())
+        .collect()
+}
+
+pub fn draw_orbit_line(
+    line: Res<OrbitLine>,
+    limits: Res<PredictionLimits>,
+    mut config: ResMut<GizmoConfig>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    reference: Res<ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    planet_query: Query<(&Transform, &Planet)>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+) {
+    // left alone unless asked for, the default depth test stays for everyone else
+    if line.draw_on_top {
+        let depth_bias = if line.enabled { -1.0 } else { 0.0 };
+        if config.depth_bias != depth_bias {
+            config.depth_bias = depth_bias;
+        }
+    }
+    if !line.enabled {
+        return;
+    }
+    let (Ok((ship, velocity)), Some((_, camera))) = (
+        query.get_single(),
+        camera_query.iter().find(|(camera, _)| camera.is_active),
+    ) else {
+        return;
+    };
+    let Some((body, body_transform, gravity)) =
+        reference_attractor(&reference, &body_query, ship.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let elements = OrbitalElements::from_state(
+        gravity.mass,
+        (ship.translation - body_transform.translation).as_dvec3(),
+        (velocity.linvel - velocities.of(body)).as_dvec3(),
+    );
+    let eye = camera.translation();
+    let open = elements.eccentricity >= 1.0;
+    let relative = if open {
+        open_orbit_ahead(&elements, gravity.mass, &limits)
+    } else {
+        orbit_points(&elements)
+    };
+    let points: Vec<Vec3> = relative
+        .into_iter()
+        .map(|p| body_transform.translation + p)
+        .collect();
+    if open {
+        draw_continuation_arrow(&mut gizmos, &points, line.color);
+    }
+
+    for (i, segment) in points.windows(2).enumerate() {
+        let middle = segment[0].lerp(segment[1], 0.5);
+        let occluded = planet_query
+            .iter()
+            .any(|(t, p)| sphere_occludes(eye, middle, t.translation, p.radius as f32));
+        if !occluded {
+            gizmos.line(segment[0], segment[1], line.color);
+        } else if i % 2 == 0 {
+            gizmos.line(segment[0], segment[1], line.occluded_color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{open_orbit_ahead, sphere_occludes};
+    use crate::{orbit::OrbitalElements, predict::PredictionLimits};
+
+    #[test]
+    fn behind_the_planet_is_occluded() {
+        let eye = Vec3::new(0.0, 0.0, 100.0);
+        let center = Vec3::ZERO;
+
+        assert!(sphere_occludes(
+            eye,
+            Vec3::new(0.0, 0.0, -50.0),
+            center,
+            10.0
+        ));
+        // the near surface and in front of it
+        assert!(!sphere_occludes(
+            eye,
+            Vec3::new(0.0, 0.0, 10.0),
+            center,
+            10.0
+        ));
+        assert!(!sphere_occludes(
+            eye,
+            Vec3::new(0.0, 0.0, 50.0),
+            center,
+            10.0
+        ));
+        // off to the side
+        assert!(!sphere_occludes(
+            eye,
+            Vec3::new(20.0, 0.0, -50.0),
+            center,
+            10.0
+        ));
+        // behind the eye
+        assert!(!sphere_occludes(
+            eye,
+            Vec3::new(0.0, 0.0, 200.0),
+            center,
+            10.0
+        ));
+    }
+
+    #[test]
+    fn open_orbit_is_bounded() {
+        let elements: OrbitalElements = "a=-1000 e=2 i=0 raan=0 argp=0 nu=0".parse().unwrap();
+        let m = 1e6 / crate::orbit::G;
+
+        let far = PredictionLimits {
+            max_time: 1e9,
+            max_distance: 20_000.0,
+        };
+        let points = open_orbit_ahead(&elements, m, &far);
+        assert!(points.len() > 2);
+        assert!(points.iter().all(|p| p.length() <= 20_000.0));
+        // starting at the ship, at the periapsis
+        assert!((points[0].length() - 1000.0).abs() < 1e-3, "{}", points[0]);
+
+        let soon = PredictionLimits {
+            max_time: 10.0,
+            ..far
+        };
+        let short = open_orbit_ahead(&elements, m, &soon);
+        assert!(short.len() < points.len());
+        // a bit more than the periapsis speed of sqrt(3e3) per second
+        let end = short.last().unwrap().distance(short[0]);
+        assert!(end < 10.0 * 60.0, "{end}");
+    }
+}
@@ -0,0 +1,173 @@
+//! Switching between the two ways of computing the orbit: projecting the state into its
+//! orbital plane and using the 2D formulas, or the elements straight from the 3D state. With
+//! `--compare-orbit-methods` the HUD shows both to check the new one against the old.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use glam::DVec3;
+
+use crate::{
+    input::InputBindings,
+    orbit::{Orbit, OrbitalElements},
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    units::HudUnits,
+    GravityAttractor, OrbitText, Spaceship,
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OrbitMethod {
+    /// [`Orbit::from_pos_dir_3d`], what the orbit readout has always been.
+    #[default]
+    Projected2D,
+    /// [`OrbitalElements::from_state`].
+    State3D,
+}
+
+impl OrbitMethod {
+    pub fn orbit(self, m: f64, pos: DVec3, v: DVec3) -> Orbit {
+        match self {
+            OrbitMethod::Projected2D => Orbit::from_pos_dir_3d(m, pos, v),
+            OrbitMethod::State3D => {
+                let elements = OrbitalElements::from_state(m, pos, v);
+                Orbit {
+                    semi_major_axis: elements.semi_major_axis,
+                    eccentricity: elements.eccentricity,
+                }
+            }
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            OrbitMethod::Projected2D => OrbitMethod::State3D,
+            OrbitMethod::State3D => OrbitMethod::Projected2D,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            OrbitMethod::Projected2D => "2D",
+            OrbitMethod::State3D => "3D",
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct OrbitComputation {
+    /// The method behind the semi major axis, apoapsis and periapsis in the HUD.
+    pub method: OrbitMethod,
+    /// Whether to show the results of both methods.
+    pub compare: bool,
+}
+
+impl OrbitComputation {
+    pub fn from_args() -> Self {
+        Self {
+            compare: std::env::args().any(|arg| arg == "--compare-orbit-methods"),
+            ..default()
+        }
+    }
+}
+
+pub fn toggle_orbit_method(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut computation: ResMut<OrbitComputation>,
+) {
+    if keyboard_input.just_pressed(bindings.orbit_method) {
+        computation.method = computation.method.next();
+        info!("Orbit method: {:?}", computation.method);
+    }
+}
+
+/// Both methods next to each other, and how far they're apart.
+pub fn update_orbit_comparison(
+    units: HudUnits,
+    computation: Res<OrbitComputation>,
+    reference: Res<ReferenceBody>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let section = &mut text.sections[29];
+    section.value = "-".to_owned();
+    let Ok((ship, velocity)) = query.get_single() else {
+        return;
+    };
+    let (true, Some((body, body_transform, gravity))) = (
+        computation.compare,
+        reference_attractor(&reference, &body_query, ship.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        }),
+    ) else {
+        return;
+    };
+
+    let pos = (ship.translation - body_transform.translation).as_dvec3();
+    let v = (velocity.linvel - velocities.of(body)).as_dvec3();
+    let [projected, state] = [OrbitMethod::Projected2D, OrbitMethod::State3D]
+        .map(|method| (method, method.orbit(gravity.mass, pos, v)));
+    let describe = |(method, orbit): (OrbitMethod, Orbit)| {
+        let selected = if method == computation.method {
+            "*"
+        } else {
+            ""
+        };
+        format!(
+            "{}{selected} a {} e {:.5}",
+            method.label(),
+            units.distance(orbit.semi_major_axis),
+            orbit.eccentricity
+        )
+    };
+    section.value = format!(
+        "{} / {} (da {}, de {:+.1e})",
+        describe(projected),
+        describe(state),
+        units.distance_change(state.1.semi_major_axis - projected.1.semi_major_axis),
+        state.1.eccentricity - projected.1.eccentricity,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{DQuat, DVec3};
+
+    use super::OrbitMethod;
+    use crate::orbit::G;
+
+    const M: f64 = 5.972e24;
+
+    #[test]
+    fn both_methods_agree() {
+        let r = 7.0e6;
+        let v = 1.1 * f64::sqrt(G * M / r);
+        let tilt = DQuat::from_rotation_x(0.4) * DQuat::from_rotation_y(1.3);
+        let pos = tilt * DVec3::new(r, 0.0, 0.0);
+        let vel = tilt * DVec3::new(0.0, 0.1 * v, -v);
+
+        let projected = OrbitMethod::Projected2D.orbit(M, pos, vel);
+        let state = OrbitMethod::State3D.orbit(M, pos, vel);
+        let a = projected.semi_major_axis;
+        assert!(
+            (a - state.semi_major_axis).abs() < 1e-6 * a,
+            "{projected:?} {state:?}"
+        );
+        assert!(
+            (projected.eccentricity - state.eccentricity).abs() < 1e-9,
+            "{projected:?} {state:?}"
+        );
+    }
+
+    #[test]
+    fn switches_back_and_forth() {
+        let method = OrbitMethod::default();
+        assert_eq!(method, OrbitMethod::Projected2D);
+        assert_eq!(method.next(), OrbitMethod::State3D);
+        assert_eq!(method.next().next(), method);
+    }
+}
@@ -0,0 +1,69 @@
+//! Pausing the simulation while the window isn't focused, so that it doesn't keep running
+//! unattended or catch up with a huge step after refocusing.
+
+use bevy::{prelude::*, window::WindowFocused};
+use bevy_rapier3d::prelude::*;
+
+use crate::{input::InputBindings, ThrusterSound};
+
+/// Disabled with `--no-pause-on-focus-loss`.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PauseOnFocusLoss {
+    pub enabled: bool,
+}
+
+impl Default for PauseOnFocusLoss {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+impl PauseOnFocusLoss {
+    pub fn from_args() -> Self {
+        Self {
+            enabled: !std::env::args().any(|arg| arg == "--no-pause-on-focus-loss"),
+        }
+    }
+}
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationPaused(pub bool);
+
+pub fn pause_on_focus_loss(
+    config: Res<PauseOnFocusLoss>,
+    mut focus_events: EventReader<WindowFocused>,
+    mut paused: ResMut<SimulationPaused>,
+    mut time: ResMut<Time>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    sound_query: Query<&AudioSink, With<ThrusterSound>>,
+) {
+    let Some(focused) = focus_events.iter().last().map(|event| event.focused) else {
+        return;
+    };
+    if !config.enabled || paused.0 != focused {
+        return;
+    }
+    // already halted by something else, that shouldn't be resumed on focus
+    if !focused && time.is_paused() {
+        return;
+    }
+
+    paused.0 = !focused;
+    rapier_config.physics_pipeline_active = focused;
+    let sound = sound_query.get_single();
+    if focused {
+        info!("Window focused, resuming");
+        time.unpause();
+        if let (Ok(sound), true) = (sound, keyboard_input.pressed(bindings.thrust)) {
+            sound.play();
+        }
+    } else {
+        info!("Window unfocused, pausing");
+        time.pause();
+        if let Ok(sound) = sound {
+            sound.pause();
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! The phase angle to the selected body, how far it's ahead of the ship around the attractor,
+//! and how fast that changes, for timing a phasing maneuver to catch up with it.
+
+use std::f64::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use glam::DVec3;
+
+use crate::{
+    inspect::{velocity_of, SelectedBody},
+    rails::OnRails,
+    reference::{reference_attractor, ReferenceBody},
+    GravityAttractor, OrbitText, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PhaseAngle {
+    pub enabled: bool,
+}
+
+impl Default for PhaseAngle {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// `angle` in radians brought into `-PI..=PI`.
+pub fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(TAU) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+/// How far the target at `target` is ahead of the ship at `ship` in the ship's orbital plane
+/// with the angular momentum `normal`, both relative to the attractor. In radians, negative
+/// when the target is behind.
+pub fn phase_angle(ship: DVec3, target: DVec3, normal: DVec3) -> Option<f64> {
+    let normal = normal.try_normalize()?;
+    Some(f64::atan2(normal.dot(ship.cross(target)), ship.dot(target)))
+}
+
+/// The phase angle of the last frame and the target it was to.
+#[derive(Default)]
+pub struct LastPhase(Option<(Entity, f64)>);
+
+pub fn update_phase_angle(
+    config: Res<PhaseAngle>,
+    time: Res<Time>,
+    selected: Res<SelectedBody>,
+    reference: Res<ReferenceBody>,
+    ship_query: Query<(Entity, &Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor, Option<&OnRails>)>,
+    target_query: Query<&Transform>,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+    mut last: Local<LastPhase>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let section = &mut text.sections[27];
+    section.value = "-".to_owned();
+    let Ok((ship, ship_transform, ship_velocity)) = ship_query.get_single() else {
+        return;
+    };
+    let target = selected.0.filter(|&target| target != ship);
+    let (true, Some(target), Some((body, body_transform, _, body_rails))) = (
+        config.enabled,
+        target,
+        reference_attractor(
+            &reference,
+            &body_query,
+            ship_transform.translation,
+            |(_, t, g, _)| (t.translation, g.mass),
+        ),
+    ) else {
+        last.0 = None;
+        return;
+    };
+    let Ok(target_transform) = target_query.get(target) else {
+        last.0 = None;
+        return;
+    };
+    if target == body {
+        last.0 = None;
+        return;
+    }
+
+    let parent_mass = body_rails
+        .and_then(|rails| body_query.get(rails.parent).ok())
+        .map_or(0.0, |(_, _, g, _)| g.mass);
+    let body_velocity = velocity_of(None, body_rails, parent_mass, time.elapsed_seconds_f64());
+    let center = body_transform.translation;
+    let ship_pos = (ship_transform.translation - center).as_dvec3();
+    let normal = ship_pos.cross((ship_velocity.linvel - body_velocity).as_dvec3());
+    let Some(phase) = phase_angle(
+        ship_pos,
+        (target_transform.translation - center).as_dvec3(),
+        normal,
+    ) else {
+        last.0 = None;
+        return;
+    };
+
+    let dt = time.delta_seconds_f64();
+    // going from just ahead to just behind is a small step, not almost a full turn
+    let rate = match last.0 {
+        Some((last_target, last_phase)) if last_target == target && dt > 0.0 => {
+            Some(wrap_angle(phase - last_phase) / dt)
+        }
+        _ => None,
+    };
+    last.0 = Some((target, phase));
+
+    section.value = format!(
+        "{:+.1}° {}",
+        phase.to_degrees(),
+        if phase >= 0.0 { "ahead" } else { "behind" }
+    );
+    if let Some(rate) = rate {
+        // closing when the angle heads towards zero
+        let closing = rate * phase < 0.0;
+        section.value += &format!(
+            ", {} {:.3}°/s",
+            if closing { "closing" } else { "opening" },
+            rate.abs().to_degrees()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use glam::DVec3;
+
+    use super::{phase_angle, wrap_angle};
+
+    #[test]
+    fn ahead_and_behind_in_the_orbit() {
+        // going from +X towards -Z
+        let ship = DVec3::new(100.0, 0.0, 0.0);
+        let normal = ship.cross(DVec3::new(0.0, 0.0, -1.0));
+
+        let ahead = phase_angle(ship, DVec3::new(0.0, 0.0, -50.0), normal).unwrap();
+        assert!((ahead - PI / 2.0).abs() < 1e-12, "{ahead}");
+        let behind = phase_angle(ship, DVec3::new(70.0, 0.0, 70.0), normal).unwrap();
+        assert!((behind + PI / 4.0).abs() < 1e-12, "{behind}");
+        // out of the plane only counts with its projection
+        let above = phase_angle(ship, DVec3::new(0.0, 30.0, -50.0), normal).unwrap();
+        assert!((above - PI / 2.0).abs() < 1e-12, "{above}");
+
+        assert_eq!(phase_angle(ship, ship, DVec3::ZERO), None);
+    }
+
+    #[test]
+    fn wraps_around_half_a_turn() {
+        assert!((wrap_angle(PI - 0.1 - (-PI + 0.1)) + 0.2).abs() < 1e-12);
+        assert!((wrap_angle(-PI + 0.1 - (PI - 0.1)) - 0.2).abs() < 1e-12);
+        assert_eq!(wrap_angle(0.5), 0.5);
+        assert_eq!(wrap_angle(-PI), PI);
+        assert_eq!(wrap_angle(PI), PI);
+    }
+}
@@ -0,0 +1,221 @@
+//! Where and how much to burn to get into the orbital plane of the selected body, for a
+//! rendezvous with something in a different plane.
+
+use std::f64::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use glam::DVec3;
+
+use crate::{
+    inspect::{velocity_of, SelectedBody},
+    orbit::{self, OrbitalElements},
+    orbit_frame::ascending_node_direction,
+    rails::OnRails,
+    reference::{reference_attractor, ReferenceBody},
+    GravityAttractor, OrbitText, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PlaneMatchHelper {
+    pub enabled: bool,
+    pub color: Color,
+}
+
+impl Default for PlaneMatchHelper {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            color: Color::FUCHSIA,
+        }
+    }
+}
+
+/// The plane change burn at one of the nodes between the orbit and the target plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaneMatch {
+    /// Where to burn, relative to the attractor.
+    pub node: DVec3,
+    /// Whether the orbit goes up through the target plane there, relative to its normal.
+    pub ascending: bool,
+    pub time_until: f64,
+    /// The angle between the two planes in radians.
+    pub relative_inclination: f64,
+    pub dv: f64,
+}
+
+/// The cheaper of the plane change burns at the two nodes where the orbit at `pos` with `vel`
+/// around a body with mass `m` crosses the plane with the angular momentum `target_normal`.
+/// The cheaper one is the one further out, where the orbit is slower. `None` if the planes
+/// are the same or an open orbit doesn't get to either node.
+pub fn plane_match(m: f64, pos: DVec3, vel: DVec3, target_normal: DVec3) -> Option<PlaneMatch> {
+    let normal = pos.cross(vel);
+    let (normal_dir, target_dir) = (normal.try_normalize()?, target_normal.try_normalize()?);
+    let node_dir = ascending_node_direction(normal_dir.as_vec3(), target_dir.as_vec3())?;
+    let node_dir = node_dir.as_dvec3().normalize();
+    let relative_inclination = normal_dir.angle_between(target_dir);
+
+    let elements = OrbitalElements::from_state(m, pos, vel);
+    // how far the node is ahead along the orbit
+    let ahead = f64::atan2(
+        normal_dir.dot(pos.cross(node_dir)),
+        pos.normalize().dot(node_dir),
+    );
+
+    [(true, ahead), (false, ahead + PI)]
+        .into_iter()
+        .filter_map(|(ascending, ahead)| {
+            let nu = (elements.true_anomaly + ahead).rem_euclid(TAU);
+            let r = elements.radius_at_true_anomaly(nu);
+            if !r.is_finite() || r <= 0.0 {
+                return None;
+            }
+            // only the horizontal velocity turns, the radial part lies along the line of nodes
+            let speed = normal.length() / r;
+            Some(PlaneMatch {
+                node: if ascending { node_dir } else { -node_dir } * r,
+                ascending,
+                time_until: elements.time_of_flight(m, nu)?,
+                relative_inclination,
+                dv: orbit::plane_change_dv(speed, relative_inclination),
+            })
+        })
+        .min_by(|a, b| a.dv.total_cmp(&b.dv))
+}
+
+/// Shows the line of nodes with the selected body and the burn at the cheaper node.
+pub fn draw_plane_match(
+    helper: Res<PlaneMatchHelper>,
+    time: Res<Time>,
+    selected: Res<SelectedBody>,
+    reference: Res<ReferenceBody>,
+    ship_query: Query<(Entity, &Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor, Option<&OnRails>)>,
+    target_query: Query<(&Transform, Option<&Velocity>, Option<&OnRails>)>,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    text.sections[23].value = "-".to_owned();
+    if !helper.enabled {
+        return;
+    }
+    let Ok((ship, ship_transform, ship_velocity)) = ship_query.get_single() else {
+        return;
+    };
+    let Some(target) = selected.0.filter(|&target| target != ship) else {
+        return;
+    };
+    let Some((body, body_transform, gravity, body_rails)) = reference_attractor(
+        &reference,
+        &body_query,
+        ship_transform.translation,
+        |(_, t, g, _)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+    let Ok((target_transform, target_velocity, target_rails)) = target_query.get(target) else {
+        return;
+    };
+    if target == body {
+        return;
+    }
+
+    let now = time.elapsed_seconds_f64();
+    let parent_mass = |rails: Option<&OnRails>| {
+        rails
+            .and_then(|rails| body_query.get(rails.parent).ok())
+            .map_or(0.0, |(_, _, g, _)| g.mass)
+    };
+    let body_velocity = velocity_of(None, body_rails, parent_mass(body_rails), now);
+    let target_velocity = velocity_of(
+        target_velocity,
+        target_rails,
+        parent_mass(target_rails),
+        now,
+    );
+
+    let target_normal = (target_transform.translation - body_transform.translation)
+        .as_dvec3()
+        .cross((target_velocity - body_velocity).as_dvec3());
+    let Some(burn) = plane_match(
+        gravity.mass,
+        (ship_transform.translation - body_transform.translation).as_dvec3(),
+        (ship_velocity.linvel - body_velocity).as_dvec3(),
+        target_normal,
+    ) else {
+        text.sections[23].value = "in plane".to_owned();
+        return;
+    };
+
+    let center = body_transform.translation;
+    let node = center + burn.node.as_vec3();
+    gizmos.line(center - burn.node.as_vec3(), node, helper.color * 0.6);
+    gizmos.sphere(node, Quat::IDENTITY, 3.0, helper.color);
+
+    text.sections[23].value = format!(
+        "{:.1}° off, {:.2} at {} node in {:.0}s",
+        burn.relative_inclination.to_degrees(),
+        burn.dv,
+        if burn.ascending {
+            "ascending"
+        } else {
+            "descending"
+        },
+        burn.time_until,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{DQuat, DVec3};
+
+    use super::plane_match;
+    use crate::orbit::{self, G};
+
+    const M: f64 = 5.972e24;
+
+    #[test]
+    fn circular_orbit_into_tilted_plane() {
+        let r = 7.0e6;
+        let v = f64::sqrt(G * M / r);
+        let pos = DVec3::new(r, 0.0, 0.0);
+        let vel = DVec3::new(0.0, 0.0, -v);
+        let tilt = 30f64.to_radians();
+        let target_normal = DQuat::from_rotation_x(tilt) * pos.cross(vel);
+
+        let burn = plane_match(M, pos, vel, target_normal).unwrap();
+        assert!((burn.relative_inclination - tilt).abs() < 1e-9);
+        assert!((burn.dv - orbit::plane_change_dv(v, tilt)).abs() < 1e-6);
+        // the line of nodes is along X, the ship is at one of them right now
+        assert!(
+            burn.node.y.abs() < 1e-3 && burn.node.z.abs() < 1e-3,
+            "{}",
+            burn.node
+        );
+        assert!((burn.node.length() - r).abs() < 1e-3);
+
+        assert_eq!(plane_match(M, pos, vel, pos.cross(vel)), None);
+    }
+
+    #[test]
+    fn cheaper_at_the_slow_node() {
+        // periapsis at +X, the line of nodes along the line of apsides
+        let r = 7.0e6;
+        let pos = DVec3::new(r, 0.0, 0.0);
+        let vel = DVec3::new(0.0, 0.0, -1.2 * f64::sqrt(G * M / r));
+        let target_normal = DQuat::from_rotation_x(0.1) * pos.cross(vel);
+
+        let burn = plane_match(M, pos, vel, target_normal).unwrap();
+        let apoapsis = orbit::Orbit::from_pos_dir_3d(M, pos, vel).apoapsis();
+        assert!(burn.node.x < 0.0, "{}", burn.node);
+        assert!((burn.node.length() - apoapsis).abs() < 1.0);
+
+        let speed = pos.cross(vel).length() / apoapsis;
+        assert!((burn.dv - orbit::plane_change_dv(speed, 0.1)).abs() < 1e-6);
+        let period = orbit::Orbit::from_pos_dir_3d(M, pos, vel).period(M);
+        assert!((burn.time_until - period / 2.0).abs() < 1e-3 * period);
+    }
+}
@@ -0,0 +1,108 @@
+//! Changing the mass of a body while the game runs, to see how the orbits respond to it.
+//! Everything reads the mass from the [`GravityAttractor`] every frame, only the bodies on
+//! rails around it need to be moved over to the new mass.
+
+use bevy::prelude::*;
+
+use crate::{
+    input::InputBindings,
+    orbit::OrbitalElements,
+    rails::OnRails,
+    reference::{reference_attractor, ReferenceBody},
+    GravityAttractor, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MassControl {
+    /// What the mass is multiplied or divided by with each key press.
+    pub factor: f64,
+}
+
+impl Default for MassControl {
+    fn default() -> Self {
+        Self { factor: 1.1 }
+    }
+}
+
+/// Restarts `rails` at `now` from its position and velocity around the parent's `old_mass`, on
+/// the orbit they make around `new_mass`. The body continues from there like a free body
+/// would, instead of jumping to where it would have been if the mass had always been the new one.
+pub fn rebase_rails(rails: &mut OnRails, old_mass: f64, new_mass: f64, now: f64) {
+    let (pos, vel) = rails.state(old_mass, now);
+    rails.orbit = OrbitalElements::from_state(new_mass, pos, vel);
+    rails.epoch = now;
+}
+
+/// Scales the mass of the reference body with the mass keys.
+pub fn adjust_mass(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    control: Res<MassControl>,
+    time: Res<Time>,
+    reference: Res<ReferenceBody>,
+    ship_query: Query<&Transform, With<Spaceship>>,
+    mut body_query: Query<(Entity, &Transform, &mut GravityAttractor), Without<Spaceship>>,
+    mut rails_query: Query<&mut OnRails>,
+) {
+    let factor = if keyboard_input.just_pressed(bindings.mass_up) {
+        control.factor
+    } else if keyboard_input.just_pressed(bindings.mass_down) {
+        1.0 / control.factor
+    } else {
+        return;
+    };
+    let Ok(ship) = ship_query.get_single() else {
+        return;
+    };
+    let Some((body, _, _)) =
+        reference_attractor(&reference, &body_query, ship.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+    let Ok((_, _, mut gravity)) = body_query.get_mut(body) else {
+        return;
+    };
+
+    let old_mass = gravity.mass;
+    gravity.mass *= factor;
+    info!("Mass of {body:?} is now {:.4e}", gravity.mass);
+
+    let now = time.elapsed_seconds_f64();
+    for mut rails in &mut rails_query {
+        if rails.parent == body {
+            rebase_rails(&mut rails, old_mass, gravity.mass, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::rebase_rails;
+    use crate::{orbit::OrbitalElements, rails::OnRails};
+
+    #[test]
+    fn rails_continue_where_they_were() {
+        let mass = 1.0e17;
+        let orbit: OrbitalElements = "a=3000 e=0.2 i=0 raan=0 argp=0 nu=0".parse().unwrap();
+        let mut rails = OnRails {
+            parent: Entity::PLACEHOLDER,
+            orbit,
+            epoch: 10.0,
+        };
+
+        let before = rails.state(mass, 50.0);
+        rebase_rails(&mut rails, mass, 2.0 * mass, 50.0);
+        assert_eq!(rails.epoch, 50.0);
+
+        // no jump with twice the mass, neither in the position nor the velocity
+        let after = rails.state(2.0 * mass, 50.0);
+        assert!(after.0.distance(before.0) < 1e-6, "{after:?} == {before:?}");
+        assert!(after.1.distance(before.1) < 1e-9, "{after:?} == {before:?}");
+        // too slow for the heavier parent, it falls towards it from here
+        assert!(rails.orbit.semi_major_axis < 3000.0, "{:?}", rails.orbit);
+    }
+}
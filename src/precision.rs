@@ -0,0 +1,223 @@
+//! Keeping the state of force driven bodies in `f64`, only converting it to `f32` for
+//! rapier and rendering.
+//!
+//! Far away from the origin, an `f32` translation can't represent the small distance moved
+//! in a single tick, so slow movement gets rounded away and orbits accumulate errors.
+//! With [`OrbitPrecision::Double`], rapier still steps the bodies, handling contacts and
+//! rotation, but the change it made is applied to the [`PrecisePose`] in `f64` and the
+//! `f32` state is overwritten with it after every step.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use glam::DVec3;
+
+use crate::{
+    forces::ExternalForceSet, mass_of, precise_gravity_acceleration, GravityAttractor, GravityForce,
+};
+
+/// Selected with `--precision=double`.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrbitPrecision {
+    /// Rapier's `f32` state is the authoritative one.
+    #[default]
+    Single,
+    Double,
+}
+
+impl OrbitPrecision {
+    pub fn from_args() -> Self {
+        match std::env::args().find_map(|arg| arg.strip_prefix("--precision=").map(str::to_owned)) {
+            None => OrbitPrecision::default(),
+            Some(precision) => match precision.as_str() {
+                "single" => OrbitPrecision::Single,
+                "double" => OrbitPrecision::Double,
+                _ => {
+                    warn!("Unknown precision `{precision}`, using single precision");
+                    OrbitPrecision::Single
+                }
+            },
+        }
+    }
+}
+
+/// The authoritative position and velocity when using [`OrbitPrecision::Double`].
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct PrecisePose {
+    pub position: DVec3,
+    pub velocity: DVec3,
+    /// The `f32` state last written to the body, to find out what rapier changed since.
+    pub written: Option<(Vec3, Vec3)>,
+}
+
+impl PrecisePose {
+    pub fn new(translation: Vec3, linvel: Vec3) -> Self {
+        PrecisePose {
+            position: translation.as_dvec3(),
+            velocity: linvel.as_dvec3(),
+            written: None,
+        }
+    }
+
+    /// Applies a rapier step of `dt` in `substeps` that moved the body to `translation` with
+    /// `linvel`, replacing the `f32` gravity rapier applied with the `f64` one.
+    /// Returns the new `f32` state that should be written back.
+    pub fn step(
+        &mut self,
+        (translation, linvel): (Vec3, Vec3),
+        (gravity, precise_gravity): (Vec3, DVec3),
+        dt: f64,
+        substeps: u32,
+    ) -> (Vec3, Vec3) {
+        let Some((written_translation, written_linvel)) = self.written else {
+            *self = PrecisePose::new(translation, linvel);
+            self.written = Some((translation, linvel));
+            return (translation, linvel);
+        };
+
+        // the change in velocity is small, so it's still precise in `f32`
+        let old_velocity = self.velocity;
+        self.velocity +=
+            (linvel - written_linvel).as_dvec3() + (precise_gravity - gravity.as_dvec3()) * dt;
+
+        // constant acceleration over all substeps, integrated with symplectic euler
+        let n = f64::from(substeps);
+        let weight = (n + 1.0) / (2.0 * n);
+        self.position += old_velocity * dt + (self.velocity - old_velocity) * dt * weight;
+
+        // anything else that moved the body, like a contact or a teleport,
+        // moved it further than rounding can explain
+        let expected = written_translation
+            + (written_linvel + (linvel - written_linvel) * weight as f32) * dt as f32;
+        let deviation = translation - expected;
+        let rounding = 4.0 * f32::EPSILON * translation.abs().max(expected.abs()).max_element();
+        if deviation.abs().max_element() > rounding {
+            self.position += deviation.as_dvec3();
+        }
+
+        let state = (self.position.as_vec3(), self.velocity.as_vec3());
+        self.written = Some(state);
+        state
+    }
+}
+
+/// The duration of the last rapier step and the amount of substeps it was done in.
+fn last_step(mode: TimestepMode, time: &Time) -> Option<(f64, u32)> {
+    match mode {
+        TimestepMode::Variable {
+            max_dt,
+            time_scale,
+            substeps,
+        } => Some((
+            f64::from((time.delta_seconds() * time_scale).min(max_dt)),
+            substeps as u32,
+        )),
+        TimestepMode::Fixed { dt, substeps } => Some((f64::from(dt), substeps as u32)),
+        // might not have stepped at all this frame
+        TimestepMode::Interpolated { .. } => None,
+    }
+}
+
+pub fn add_precise_pose(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform, &Velocity), (With<ExternalForceSet>, Without<PrecisePose>)>,
+) {
+    for (entity, transform, velocity) in &query {
+        commands
+            .entity(entity)
+            .insert(PrecisePose::new(transform.translation, velocity.linvel));
+    }
+}
+
+pub fn integrate_precise_pose(
+    precision: Res<OrbitPrecision>,
+    config: Res<RapierConfiguration>,
+    time: Res<Time>,
+    mut query: Query<(
+        Entity,
+        &mut PrecisePose,
+        &mut Transform,
+        &mut Velocity,
+        &ExternalForceSet,
+        Option<&ReadMassProperties>,
+    )>,
+    body_query: Query<(Entity, &GravityAttractor, &Transform), Without<PrecisePose>>,
+) {
+    let step = last_step(config.timestep_mode, &time).filter(|_| config.physics_pipeline_active);
+
+    for (entity, mut pose, mut transform, mut velocity, forces, mass) in &mut query {
+        let (Some((dt, substeps)), OrbitPrecision::Double) = (step, *precision) else {
+            *pose = PrecisePose::new(transform.translation, velocity.linvel);
+            continue;
+        };
+
+        let bodies = body_query
+            .iter()
+            .filter(|&(body, _, _)| body != entity)
+            .map(|(_, gravity, body_transform)| {
+                (body_transform.translation.as_dvec3(), gravity.mass)
+            });
+        let precise_gravity = precise_gravity_acceleration(pose.position, bodies);
+
+        let (translation, linvel) = pose.step(
+            (transform.translation, velocity.linvel),
+            (
+                forces.get::<GravityForce>().force / mass_of(mass),
+                precise_gravity,
+            ),
+            dt,
+            substeps,
+        );
+        transform.translation = translation;
+        velocity.linvel = linvel;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use glam::DVec3;
+
+    use super::PrecisePose;
+
+    /// Steps like rapier does with a single substep, in `f32`.
+    fn rapier_step((translation, linvel): (Vec3, Vec3), dt: f32) -> (Vec3, Vec3) {
+        (translation + linvel * dt, linvel)
+    }
+
+    #[test]
+    fn slow_movement_far_away_is_kept() {
+        let dt = 1.0 / 60.0;
+        let start = (Vec3::new(1.0e6, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+
+        let mut single = start;
+        let mut pose = PrecisePose::new(start.0, start.1);
+        let mut written = pose.step(start, (Vec3::ZERO, DVec3::ZERO), dt, 1);
+        for _ in 0..600 {
+            single = rapier_step(single, dt as f32);
+            let stepped = rapier_step(written, dt as f32);
+            written = pose.step(stepped, (Vec3::ZERO, DVec3::ZERO), dt, 1);
+        }
+
+        // one tick moves less than half of what an `f32` can resolve here
+        assert_eq!(single.0, start.0);
+        assert!((pose.position.x - 1.0e6 - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn contacts_move_the_precise_pose() {
+        let dt = 1.0 / 60.0;
+        let start = (Vec3::new(100.0, 0.0, 0.0), Vec3::ZERO);
+
+        let mut pose = PrecisePose::new(start.0, start.1);
+        pose.step(start, (Vec3::ZERO, DVec3::ZERO), dt, 1);
+        // pushed out of the ground
+        pose.step(
+            (Vec3::new(100.5, 0.0, 0.0), Vec3::ZERO),
+            (Vec3::ZERO, DVec3::ZERO),
+            dt,
+            1,
+        );
+
+        assert!((pose.position.x - 100.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,712 @@
+//! Numerical trajectory prediction, for when the analytic conic doesn't cut it
+//! (continuous forces, multiple attractors, ...).
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ReadMassProperties, Velocity};
+use glam::DVec3;
+
+use crate::{
+    atmosphere::{drag_force, Atmosphere, SHIP_DRAG_AREA},
+    dominant_attractor,
+    impulse::ScheduledBurns,
+    input::InputBindings,
+    mass_of,
+    orbit::{self, Orbit},
+    GravityAttractor, Planet, Spaceship, Thrusters,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct State {
+    pub position: DVec3,
+    pub velocity: DVec3,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Attractor {
+    pub position: DVec3,
+    pub mass: f64,
+}
+
+/// A thrust that keeps its attitude to the velocity, like a ship holding prograde. Under
+/// continuous low thrust that makes a spiral instead of a conic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SteeredThrust {
+    /// Where the body the orbit normal is taken around is.
+    pub center: DVec3,
+    /// The acceleration along prograde, the orbit normal and the direction perpendicular to
+    /// both, which is radial out on a circular orbit.
+    pub components: DVec3,
+}
+
+impl SteeredThrust {
+    /// Holds `acceleration` at the attitude it has to the orbit of `state` around `center`.
+    pub fn new(center: DVec3, state: &State, acceleration: DVec3) -> Option<Self> {
+        let [prograde, normal, outward] = Self::basis(center, state)?;
+        Some(Self {
+            center,
+            components: DVec3::new(
+                acceleration.dot(prograde),
+                acceleration.dot(normal),
+                acceleration.dot(outward),
+            ),
+        })
+    }
+
+    fn basis(center: DVec3, state: &State) -> Option<[DVec3; 3]> {
+        let prograde = state.velocity.try_normalize()?;
+        let normal = (state.position - center)
+            .cross(state.velocity)
+            .try_normalize()?;
+        Some([prograde, normal, prograde.cross(normal)])
+    }
+
+    pub fn acceleration(&self, state: &State) -> DVec3 {
+        Self::basis(self.center, state).map_or(DVec3::ZERO, |[prograde, normal, outward]| {
+            prograde * self.components.x + normal * self.components.y + outward * self.components.z
+        })
+    }
+}
+
+/// The atmosphere of a planet, slowing down the predicted body like [`crate::atmosphere`]
+/// does the ship.
+#[derive(Debug, Clone, Copy)]
+pub struct AtmosphereDrag {
+    pub center: DVec3,
+    /// The radius of the planet, the altitude is measured from it.
+    pub radius: f64,
+    pub atmosphere: Atmosphere,
+    /// The mass of the predicted body, the drag area is the ship's.
+    pub mass: f64,
+}
+
+impl AtmosphereDrag {
+    pub fn acceleration(&self, state: &State) -> DVec3 {
+        let altitude = state.position.distance(self.center) - self.radius;
+        let density = self.atmosphere.density(altitude);
+        drag_force(density, SHIP_DRAG_AREA, state.velocity.as_vec3()).as_dvec3() / self.mass
+    }
+}
+
+/// Everything that accelerates the predicted body.
+#[derive(Debug, Clone, Default)]
+pub struct AccelerationModel {
+    pub attractors: Vec<Attractor>,
+    pub drag: Vec<AtmosphereDrag>,
+    pub thrust: Option<SteeredThrust>,
+}
+
+impl AccelerationModel {
+    pub fn acceleration(&self, state: &State) -> DVec3 {
+        self.attractors
+            .iter()
+            .map(|body| {
+                let to_body = body.position - state.position;
+                let distance = to_body.length();
+                to_body / distance * (orbit::G * body.mass / (distance * distance))
+            })
+            .sum::<DVec3>()
+            + self
+                .drag
+                .iter()
+                .map(|drag| drag.acceleration(state))
+                .sum::<DVec3>()
+            + self
+                .thrust
+                .map_or(DVec3::ZERO, |thrust| thrust.acceleration(state))
+    }
+}
+
+/// Advances the state by `dt` using the classic Runge-Kutta method.
+pub fn rk4_step(model: &AccelerationModel, state: State, dt: f64) -> State {
+    let derive = |s: State| (s.velocity, model.acceleration(&s));
+    let offset = |(dp, dv): (DVec3, DVec3), h: f64| State {
+        position: state.position + dp * h,
+        velocity: state.velocity + dv * h,
+    };
+
+    let k1 = derive(state);
+    let k2 = derive(offset(k1, dt / 2.0));
+    let k3 = derive(offset(k2, dt / 2.0));
+    let k4 = derive(offset(k3, dt));
+
+    State {
+        position: state.position + (k1.0 + 2.0 * k2.0 + 2.0 * k3.0 + k4.0) * (dt / 6.0),
+        velocity: state.velocity + (k1.1 + 2.0 * k2.1 + 2.0 * k3.1 + k4.1) * (dt / 6.0),
+    }
+}
+
+/// Propagates `state` for `horizon` seconds (rounded to whole steps of `dt`), returning
+/// the position after every step, starting with the initial one.
+pub fn rk4_predict(model: &AccelerationModel, state: State, horizon: f64, dt: f64) -> Vec<DVec3> {
+    let steps = (horizon / dt).round() as usize;
+    rk4_propagate(model, state, steps, dt, 0.0).0
+}
+
+/// Takes `steps` steps of `dt` and then one of `rest`, returning the position after every
+/// step, starting with the initial one, and the final state.
+fn rk4_propagate(
+    model: &AccelerationModel,
+    state: State,
+    steps: usize,
+    dt: f64,
+    rest: f64,
+) -> (Vec<DVec3>, State) {
+    let mut positions = Vec::with_capacity(steps + 2);
+    positions.push(state.position);
+
+    let mut state = state;
+    for _ in 0..steps {
+        state = rk4_step(model, state, dt);
+        positions.push(state.position);
+    }
+    if rest > 0.0 {
+        state = rk4_step(model, state, rest);
+        positions.push(state.position);
+    }
+
+    (positions, state)
+}
+
+/// Like [`rk4_predict`], but executing a prograde burn of `prograde_dv` after `burn_in`
+/// seconds, exactly at that time, and then predicting for as long as `horizon_after` returns
+/// for the state right after the burn. Returns the positions before and after the burn,
+/// both including the position of the burn, and that state.
+pub fn rk4_predict_with_burn(
+    model: &AccelerationModel,
+    state: State,
+    (burn_in, prograde_dv): (f64, f64),
+    horizon_after: impl FnOnce(&State) -> f64,
+    dt: f64,
+) -> (Vec<DVec3>, Vec<DVec3>, State) {
+    let burn_in = burn_in.max(0.0);
+    let steps = (burn_in / dt).floor() as usize;
+    let (before, mut node) = rk4_propagate(model, state, steps, dt, burn_in - steps as f64 * dt);
+
+    node.velocity += node.velocity.normalize_or_zero() * prograde_dv;
+
+    let after = rk4_predict(model, node, horizon_after(&node), dt);
+    (before, after, node)
+}
+
+#[derive(Resource)]
+pub struct Rk4Prediction {
+    pub enabled: bool,
+    /// How far ahead to predict in seconds.
+    pub horizon: f64,
+    /// Predict this many orbital periods ahead instead of `horizon` when the orbit is closed,
+    /// to see how the orbit precesses.
+    pub orbits: Option<f64>,
+    /// Integration step in seconds. It's made larger when it would need more than `max_steps`.
+    pub step: f64,
+    pub max_steps: usize,
+    /// Show the orbit after the next scheduled burn, continuing from its node.
+    pub show_burns: bool,
+    /// Keep the thrusters going at the current throttle and attitude to the velocity,
+    /// to see the spiral of a long low-thrust burn.
+    pub include_thrust: bool,
+}
+
+impl Default for Rk4Prediction {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            horizon: 30.0,
+            orbits: None,
+            step: 0.05,
+            max_steps: 10_000,
+            show_burns: true,
+            include_thrust: false,
+        }
+    }
+}
+
+impl Rk4Prediction {
+    /// The prediction horizon for an orbit around a body with mass `m`.
+    pub fn horizon_for(&self, m: f64, orbit: &Orbit) -> f64 {
+        match self.orbits {
+            Some(orbits) if orbit.is_closed() => orbits * orbit.period(m),
+            _ => self.horizon,
+        }
+    }
+}
+
+/// Bounds for all predicted paths, so that escape trajectories don't go on forever.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct PredictionLimits {
+    /// How far ahead to predict open paths at most, in seconds. Closed orbits come back
+    /// around by themselves, so they are always predicted in full.
+    pub max_time: f64,
+    /// How far from the dominant attractor the path may go.
+    pub max_distance: f64,
+}
+
+impl Default for PredictionLimits {
+    fn default() -> Self {
+        Self {
+            max_time: 600.0,
+            max_distance: 100_000.0,
+        }
+    }
+}
+
+impl PredictionLimits {
+    /// `horizon` bounded by [`Self::max_time`] unless the path is `closed`, and whether that
+    /// cut it off.
+    pub fn bound_time(&self, horizon: f64, closed: bool) -> (f64, bool) {
+        if closed || horizon <= self.max_time {
+            (horizon, false)
+        } else {
+            (self.max_time, true)
+        }
+    }
+}
+
+/// The positions up to the first one further than `max_distance` from `center`,
+/// and whether any were cut off.
+pub fn truncate_at_distance(
+    mut positions: Vec<DVec3>,
+    center: DVec3,
+    max_distance: f64,
+) -> (Vec<DVec3>, bool) {
+    match positions
+        .iter()
+        .position(|p| p.distance(center) > max_distance)
+    {
+        Some(end) => {
+            positions.truncate(end);
+            (positions, true)
+        }
+        None => (positions, false),
+    }
+}
+
+/// The arrow at the end of a path that was cut off, pointing where it goes on.
+pub fn draw_continuation_arrow(gizmos: &mut Gizmos, points: &[Vec3], color: Color) {
+    let [.., before, tip] = points else {
+        return;
+    };
+    let Some(direction) = (*tip - *before).try_normalize() else {
+        return;
+    };
+    let length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    let size = length * 0.03;
+    let side = direction.any_orthonormal_vector() * size * 0.5;
+    let back = *tip - direction * size;
+    gizmos.line(*tip, back + side, color);
+    gizmos.line(*tip, back - side, color);
+}
+
+pub fn cycle_prediction_orbits(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut config: ResMut<Rk4Prediction>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        config.orbits = match config.orbits {
+            None => Some(1.0),
+            Some(n) if n < 2.0 => Some(2.0),
+            Some(n) if n < 5.0 => Some(5.0),
+            Some(_) => None,
+        };
+        info!("Predicting orbits: {:?}", config.orbits);
+    }
+}
+
+pub fn toggle_thrust_prediction(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut config: ResMut<Rk4Prediction>,
+) {
+    if keyboard_input.just_pressed(bindings.spiral_prediction) {
+        config.include_thrust = !config.include_thrust;
+        info!("Predicting with thrust: {}", config.include_thrust);
+    }
+}
+
+pub fn draw_rk4_prediction(
+    config: Res<Rk4Prediction>,
+    limits: Res<PredictionLimits>,
+    time: Res<Time>,
+    burns: Res<ScheduledBurns>,
+    query: Query<(&Transform, &Velocity, &Thrusters, &ReadMassProperties), With<Spaceship>>,
+    body_query: Query<
+        (
+            &Transform,
+            &GravityAttractor,
+            Option<(&Atmosphere, &Planet)>,
+        ),
+        Without<Spaceship>,
+    >,
+    mut gizmos: Gizmos,
+) {
+    if !config.enabled {
+        return;
+    }
+
+    let (ship_transform, v, thrusters, mass) = query.single();
+    let mass = mass_of(Some(mass));
+
+    let mut model = AccelerationModel {
+        attractors: body_query
+            .iter()
+            .map(|(transform, gravity, _)| Attractor {
+                position: transform.translation.as_dvec3(),
+                mass: gravity.mass,
+            })
+            .collect(),
+        drag: body_query
+            .iter()
+            .filter_map(|(transform, _, atmosphere)| {
+                let (&atmosphere, planet) = atmosphere?;
+                Some(AtmosphereDrag {
+                    center: transform.translation.as_dvec3(),
+                    radius: planet.radius,
+                    atmosphere,
+                    mass: f64::from(mass),
+                })
+            })
+            .collect(),
+        thrust: None,
+    };
+    let state = State {
+        position: ship_transform.translation.as_dvec3(),
+        velocity: v.linvel.as_dvec3(),
+    };
+
+    // the period is taken around whatever pulls the hardest
+    let dominant = dominant_attractor(&model.attractors, ship_transform.translation, |body| {
+        (body.position.as_vec3(), body.mass)
+    })
+    .copied();
+    if config.include_thrust {
+        let thrust = ship_transform.rotation * thrusters.local_thrust() / mass;
+        model.thrust =
+            dominant.and_then(|body| SteeredThrust::new(body.position, &state, thrust.as_dvec3()));
+    }
+    // a spiral doesn't close, so it's only bounded by the limits and the step count
+    let color = if model.thrust.is_some() {
+        Color::YELLOW
+    } else {
+        Color::CYAN
+    };
+    // the horizon of the path from `state`, and whether it's closed
+    let horizon_for = |state: State| {
+        dominant.map_or((config.horizon, false), |body| {
+            let orbit =
+                Orbit::from_pos_dir_3d(body.mass, state.position - body.position, state.velocity);
+            (config.horizon_for(body.mass, &orbit), orbit.is_closed())
+        })
+    };
+    let (horizon, closed) = horizon_for(state);
+    let (horizon, cut_off) = limits.bound_time(horizon, closed);
+    // drawn until too far away from where the period is taken
+    let center = dominant.map_or(state.position, |body| body.position);
+    let mut draw = |positions: Vec<DVec3>, cut_off: bool, color: Color| {
+        let (positions, too_far) = truncate_at_distance(positions, center, limits.max_distance);
+        let points: Vec<Vec3> = positions.into_iter().map(|p| p.as_vec3()).collect();
+        if cut_off || too_far {
+            draw_continuation_arrow(&mut gizmos, &points, color);
+        }
+        gizmos.linestrip(points, color);
+    };
+
+    // a burn beyond the end of the path isn't on it
+    let burn = burns
+        .next()
+        .filter(|_| config.show_burns)
+        .map(|burn| {
+            (
+                burn.at - time.elapsed_seconds_f64(),
+                f64::from(burn.prograde_dv),
+            )
+        })
+        .filter(|&(burn_in, _)| !limits.bound_time(burn_in, closed).1);
+    let Some((burn_in, prograde_dv)) = burn else {
+        let step = f64::max(config.step, horizon / config.max_steps as f64);
+        let positions = rk4_predict(&model, state, horizon, step);
+        draw(positions, cut_off, color);
+        return;
+    };
+
+    // the whole orbit after the burn is shown as well, bounded from the node on
+    let step = f64::max(config.step, (burn_in + horizon) / config.max_steps as f64);
+    let mut after_cut_off = false;
+    let (before, after, node) = rk4_predict_with_burn(
+        &model,
+        state,
+        (burn_in, prograde_dv),
+        |node| {
+            let (after, closed) = horizon_for(*node);
+            let (after, cut_off) = limits.bound_time(after.max(horizon - burn_in), closed);
+            after_cut_off = cut_off;
+            after
+        },
+        step,
+    );
+    draw(before, false, color);
+    draw(after, after_cut_off, Color::ORANGE);
+    gizmos.sphere(node.position.as_vec3(), Quat::IDENTITY, 5.0, Color::ORANGE);
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec3;
+
+    use super::{
+        rk4_predict, rk4_predict_with_burn, rk4_propagate, truncate_at_distance, AccelerationModel,
+        AtmosphereDrag, Attractor, PredictionLimits, Rk4Prediction, State, SteeredThrust,
+    };
+    use crate::{
+        atmosphere::Atmosphere,
+        orbit::{self, Orbit, OrbitalElements},
+    };
+
+    const M: f64 = 5.972e24;
+
+    fn model() -> AccelerationModel {
+        AccelerationModel {
+            attractors: vec![Attractor {
+                position: DVec3::ZERO,
+                mass: M,
+            }],
+            drag: Vec::new(),
+            thrust: None,
+        }
+    }
+
+    #[test]
+    fn circular_matches_analytic() {
+        let r = 4.2e7;
+        let v = f64::sqrt(orbit::G * M / r);
+        let state = State {
+            position: DVec3::new(r, 0.0, 0.0),
+            velocity: DVec3::new(0.0, 0.0, v),
+        };
+
+        // a quarter orbit
+        let horizon = std::f64::consts::FRAC_PI_2 * r / v;
+        let positions = rk4_predict(&model(), state, horizon, horizon / 1000.0);
+
+        let expected = DVec3::new(0.0, 0.0, r);
+        let end = *positions.last().unwrap();
+        assert!(end.distance(expected) < r * 1e-6, "{end} == {expected}");
+        for p in positions {
+            assert!((p.length() - r).abs() < r * 1e-6, "{} == {r}", p.length());
+        }
+    }
+
+    #[test]
+    fn eccentric_keeps_elements() {
+        let pos = DVec3::new(4.2e7, 0.0, 0.0);
+        let vel = DVec3::new(0.0, 300.0, 3400.0);
+        let before = Orbit::from_pos_dir_3d(M, pos, vel);
+
+        // a few hours of a roughly day-long orbit
+        let horizon = 6.0 * 3600.0;
+        let dt = 10.0;
+        let model = model();
+        let mut state = State {
+            position: pos,
+            velocity: vel,
+        };
+        for _ in 0..(horizon / dt) as usize {
+            state = super::rk4_step(&model, state, dt);
+        }
+        let after = Orbit::from_pos_dir_3d(M, state.position, state.velocity);
+
+        assert!(
+            (before.semi_major_axis - after.semi_major_axis).abs() < 1.0,
+            "{} == {}",
+            before.semi_major_axis,
+            after.semi_major_axis
+        );
+        assert!(
+            (before.eccentricity - after.eccentricity).abs() < 1e-4,
+            "{} == {}",
+            before.eccentricity,
+            after.eccentricity
+        );
+    }
+
+    #[test]
+    fn kepler_matches_rk4() {
+        let pos = DVec3::new(4.2e7, 0.0, 0.0);
+        let vel = DVec3::new(0.0, 300.0, 3400.0);
+        let elements = OrbitalElements::from_state(M, pos, vel);
+
+        let horizon = 6.0 * 3600.0;
+        let positions = rk4_predict(
+            &model(),
+            State {
+                position: pos,
+                velocity: vel,
+            },
+            horizon,
+            10.0,
+        );
+
+        let end = *positions.last().unwrap();
+        let predicted = elements.position_at_time(M, horizon);
+        assert!(end.distance(predicted) < 100.0, "{end} == {predicted}");
+    }
+
+    #[test]
+    fn burn_continues_from_node() {
+        let r = 4.2e7;
+        let v = f64::sqrt(orbit::G * M / r);
+        let state = State {
+            position: DVec3::new(r, 0.0, 0.0),
+            velocity: DVec3::new(0.0, 0.0, v),
+        };
+        let period = Orbit {
+            semi_major_axis: r,
+            eccentricity: 0.0,
+        }
+        .period(M);
+        let dt = period / 2000.0;
+
+        // without any dv it's the plain prediction, also for a burn between two steps
+        let (before, after, _) = rk4_predict_with_burn(
+            &model(),
+            state,
+            (100.5 * dt, 0.0),
+            |_| period - 100.5 * dt,
+            dt,
+        );
+        assert_eq!(before.len(), 102);
+        assert_eq!(before.last(), after.first());
+        for p in before.iter().chain(&after) {
+            assert!((p.length() - r).abs() < r * 1e-6, "{} == {r}", p.length());
+        }
+
+        // a prograde burn raises the opposite side of the orbit
+        let (_, after, node) =
+            rk4_predict_with_burn(&model(), state, (period / 4.0, 100.0), |_| period, dt);
+        let raised = Orbit::from_pos_dir_3d(M, node.position, node.velocity);
+        assert!(raised.apoapsis() > r * 1.01, "{raised:?}");
+        let furthest = after.iter().map(|p| p.length()).fold(0.0, f64::max);
+        assert!(
+            (furthest - raised.apoapsis()).abs() < r * 1e-3,
+            "{furthest}"
+        );
+    }
+
+    #[test]
+    fn horizon_in_orbits() {
+        let config = Rk4Prediction {
+            orbits: Some(2.0),
+            ..Default::default()
+        };
+        let closed = Orbit {
+            semi_major_axis: 4.2e7,
+            eccentricity: 0.1,
+        };
+        let open = Orbit {
+            semi_major_axis: -4.2e7,
+            eccentricity: 1.5,
+        };
+
+        assert_eq!(config.horizon_for(M, &closed), 2.0 * closed.period(M));
+        assert_eq!(config.horizon_for(M, &open), config.horizon);
+    }
+
+    #[test]
+    fn only_open_paths_are_cut_off() {
+        let limits = PredictionLimits {
+            max_time: 600.0,
+            ..Default::default()
+        };
+        // a low orbit takes far longer than the limit, and is still shown in full
+        assert_eq!(limits.bound_time(8450.0, true), (8450.0, false));
+        assert_eq!(limits.bound_time(8450.0, false), (600.0, true));
+        assert_eq!(limits.bound_time(300.0, false), (300.0, false));
+    }
+
+    #[test]
+    fn cut_off_when_too_far() {
+        let positions: Vec<_> = (0..10).map(|i| DVec3::new(i as f64, 0.0, 0.0)).collect();
+
+        let (near, cut_off) = truncate_at_distance(positions.clone(), DVec3::ZERO, 4.5);
+        assert!(cut_off);
+        assert_eq!(near.len(), 5);
+
+        let (all, cut_off) = truncate_at_distance(positions, DVec3::ZERO, 100.0);
+        assert!(!cut_off);
+        assert_eq!(all.len(), 10);
+    }
+
+    #[test]
+    fn prograde_thrust_spirals_out() {
+        let r = 4.2e7;
+        let v = f64::sqrt(orbit::G * M / r);
+        let state = State {
+            position: DVec3::new(r, 0.0, 0.0),
+            velocity: DVec3::new(0.0, 0.0, v),
+        };
+        let thrust = SteeredThrust::new(DVec3::ZERO, &state, DVec3::new(0.0, 0.0, 1e-3)).unwrap();
+        assert_eq!(thrust.components, DVec3::new(1e-3, 0.0, 0.0));
+        let model = AccelerationModel {
+            thrust: Some(thrust),
+            ..model()
+        };
+
+        // a few orbits, each one a little higher than the one before
+        let period = Orbit {
+            semi_major_axis: r,
+            eccentricity: 0.0,
+        }
+        .period(M);
+        let positions = rk4_predict(&model, state, 3.0 * period, 60.0);
+        let radii: Vec<f64> = positions.iter().map(|p| p.length()).collect();
+        for orbit in radii
+            .chunks(radii.len() / 3)
+            .take(3)
+            .collect::<Vec<_>>()
+            .windows(2)
+        {
+            let lowest = orbit[1].iter().copied().fold(f64::MAX, f64::min);
+            let highest = orbit[0].iter().copied().fold(0.0, f64::max);
+            assert!(lowest > highest * 0.99, "{lowest} > {highest}");
+        }
+        let end = *radii.last().unwrap();
+        assert!(end > r * 1.01, "{end}");
+        // stays nearly circular, in the plane
+        assert!(positions.iter().all(|p| p.y.abs() < 1e-3));
+    }
+
+    #[test]
+    fn drag_lowers_the_orbit() {
+        let radius = 6.4e6;
+        let r = radius + 1e5;
+        let v = f64::sqrt(orbit::G * M / r);
+        let state = State {
+            position: DVec3::new(r, 0.0, 0.0),
+            velocity: DVec3::new(0.0, 0.0, v),
+        };
+        let model_with_drag = AccelerationModel {
+            drag: vec![AtmosphereDrag {
+                center: DVec3::ZERO,
+                radius,
+                atmosphere: Atmosphere {
+                    surface_density: 1e-10,
+                    scale_height: 1e5,
+                },
+                mass: 1.0,
+            }],
+            ..model()
+        };
+
+        let energy = |model: &AccelerationModel| {
+            let (_, end) = rk4_propagate(model, state, 100, 10.0, 0.0);
+            orbit::specific_energy(M, end.position.length(), end.velocity.length())
+        };
+        let (without, with) = (energy(&model()), energy(&model_with_drag));
+        // the drag works against the orbital speed for the 1000 s
+        let density = 1e-10 * f64::exp(-1.0);
+        let expected = 0.5 * density * v.powi(3) * 1000.0;
+        let lost = without - with;
+        assert!(
+            (lost - expected).abs() < expected * 0.05,
+            "{lost} == {expected}"
+        );
+    }
+}
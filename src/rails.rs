@@ -0,0 +1,173 @@
+//! Bodies moving "on rails": following their Keplerian orbit around a parent exactly,
+//! instead of being simulated. This keeps scenery like moons cheap and perfectly stable.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use glam::DVec3;
+
+use crate::{
+    dominant_attractor, landing::Landed, orbit::OrbitalElements, GravityAttractor, Spaceship,
+};
+
+/// Moves the body along `orbit` around `parent`, which has to be a [`GravityAttractor`].
+/// The body should be kinematic so that the physics doesn't move it as well.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct OnRails {
+    pub parent: Entity,
+    pub orbit: OrbitalElements,
+    /// The elapsed time in seconds at which the body was at the true anomaly of `orbit`.
+    pub epoch: f64,
+}
+
+impl OnRails {
+    /// Where the body is at `time`, for a parent at `parent_pos` with `parent_mass`.
+    pub fn position(&self, parent_pos: Vec3, parent_mass: f64, time: f64) -> Vec3 {
+        parent_pos
+            + self
+                .orbit
+                .position_at_time(parent_mass, time - self.epoch)
+                .as_vec3()
+    }
+
+    /// The position and velocity relative to the parent at `time`.
+    pub fn state(&self, parent_mass: f64, time: f64) -> (DVec3, DVec3) {
+        OrbitalElements {
+            true_anomaly: self
+                .orbit
+                .true_anomaly_at_time(parent_mass, time - self.epoch),
+            ..self.orbit
+        }
+        .to_state(parent_mass)
+    }
+}
+
+/// The ship was put on rails along its current orbit, ignoring thrust, drag and numerical drift.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct OrbitFreeze;
+
+/// F2 puts the ship on rails along its osculating orbit, and hands it back to the physics
+/// with the matching velocity when pressed again.
+pub fn toggle_orbit_freeze(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Velocity,
+            Option<&OnRails>,
+            Option<&OrbitFreeze>,
+        ),
+        (With<Spaceship>, Without<Landed>),
+    >,
+    body_query: Query<
+        (Entity, &Transform, &GravityAttractor, Option<&Velocity>),
+        Without<Spaceship>,
+    >,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F2) {
+        return;
+    }
+    let Ok((entity, transform, mut velocity, rails, frozen)) = query.get_single_mut() else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+
+    if let (Some(rails), Some(_)) = (rails, frozen) {
+        if let Ok((_, _, gravity, parent_velocity)) = body_query.get(rails.parent) {
+            let (_, v) = rails.state(gravity.mass, now);
+            velocity.linvel = parent_velocity.map_or(Vec3::ZERO, |v| v.linvel) + v.as_vec3();
+        }
+
+        info!("Orbit unfrozen");
+        commands
+            .entity(entity)
+            .remove::<(OnRails, OrbitFreeze)>()
+            .insert(RigidBody::Dynamic);
+        return;
+    }
+
+    let Some((parent, parent_transform, gravity, parent_velocity)) =
+        dominant_attractor(&body_query, transform.translation, |(_, t, g, _)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+    let parent_velocity = parent_velocity.map_or(Vec3::ZERO, |v| v.linvel);
+
+    let orbit = OrbitalElements::from_state(
+        gravity.mass,
+        (transform.translation - parent_transform.translation).as_dvec3(),
+        (velocity.linvel - parent_velocity).as_dvec3(),
+    );
+    if !(orbit.semi_major_axis > 0.0 && orbit.eccentricity < 1.0) {
+        info!("Can only freeze closed orbits");
+        return;
+    }
+
+    info!("Orbit frozen: {orbit}");
+    commands.entity(entity).insert((
+        OnRails {
+            parent,
+            orbit,
+            epoch: now,
+        },
+        OrbitFreeze,
+        RigidBody::KinematicPositionBased,
+    ));
+}
+
+pub fn move_on_rails(
+    time: Res<Time>,
+    mut query: Query<(&OnRails, &mut Transform)>,
+    parent_query: Query<(&Transform, &GravityAttractor), Without<OnRails>>,
+) {
+    let now = time.elapsed_seconds_f64();
+
+    for (rails, mut transform) in &mut query {
+        let Ok((parent_transform, parent_gravity)) = parent_query.get(rails.parent) else {
+            continue;
+        };
+        transform.translation =
+            rails.position(parent_transform.translation, parent_gravity.mass, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::OnRails;
+    use crate::orbit::{Orbit, OrbitalElements};
+
+    #[test]
+    fn follows_orbit() {
+        let mass = 1.0e17;
+        let orbit: OrbitalElements = "a=3000 e=0 i=0 raan=0 argp=0 nu=0".parse().unwrap();
+        let period = Orbit {
+            semi_major_axis: 3000.0,
+            eccentricity: 0.0,
+        }
+        .period(mass);
+        let rails = OnRails {
+            parent: Entity::PLACEHOLDER,
+            orbit,
+            epoch: 10.0,
+        };
+        let parent = Vec3::new(100.0, 0.0, 0.0);
+
+        let start = rails.position(parent, mass, 10.0);
+        assert!(
+            start.distance(Vec3::new(3100.0, 0.0, 0.0)) < 1e-2,
+            "{start}"
+        );
+
+        let half = rails.position(parent, mass, 10.0 + period / 2.0);
+        assert!(half.distance(Vec3::new(-2900.0, 0.0, 0.0)) < 1e-2, "{half}");
+
+        let full = rails.position(parent, mass, 10.0 + period);
+        assert!(full.distance(start) < 1e-2, "{full}");
+    }
+}
@@ -0,0 +1,228 @@
+//! Small attitude thrusters around the hull instead of a magic torque. Each one pushes at an
+//! offset from the center of mass, so a torque takes several of them balanced against each
+//! other to not push the ship sideways as well.
+
+use bevy::prelude::*;
+
+use crate::Spaceship;
+
+/// One thruster, in the local frame of the ship.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RcsThruster {
+    pub offset: Vec3,
+    /// The direction of the force it makes, the exhaust goes the other way.
+    pub direction: Vec3,
+    pub max_force: f32,
+}
+
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct RcsThrusters(pub Vec<RcsThruster>);
+
+impl RcsThrusters {
+    /// A ring of thrusters at the top and the bottom of a hull with `height` and `width`,
+    /// two on each side pushing both ways around the long axis.
+    pub fn rings(height: f32, width: f32, max_force: f32) -> Self {
+        let sides = [Vec3::X, Vec3::Z, -Vec3::X, -Vec3::Z];
+        let thrusters = [height / 2.0, -height / 2.0]
+            .into_iter()
+            .flat_map(|y| {
+                sides.into_iter().flat_map(move |side| {
+                    let tangent = Vec3::Y.cross(side);
+                    [tangent, -tangent].map(|direction| RcsThruster {
+                        offset: side * width / 2.0 + Vec3::Y * y,
+                        direction,
+                        max_force,
+                    })
+                })
+            })
+            .collect();
+        Self(thrusters)
+    }
+
+    /// The force and the torque around `center_of_mass` of firing with `levels`.
+    pub fn net(&self, levels: &[f32], center_of_mass: Vec3) -> (Vec3, Vec3) {
+        self.0
+            .iter()
+            .zip(levels)
+            .map(|(thruster, &level)| {
+                let force = thruster.direction * thruster.max_force * level;
+                (force, (thruster.offset - center_of_mass).cross(force))
+            })
+            .fold((Vec3::ZERO, Vec3::ZERO), |(f, t), (force, torque)| {
+                (f + force, t + torque)
+            })
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct RcsBalancing {
+    /// Whether ships turn with thrusters instead of a plain torque.
+    pub enabled: bool,
+    /// How much more a wrong torque counts than a wrong force. Turning is what they are
+    /// for, a little push off course is the smaller problem.
+    pub torque_weight: f32,
+    pub max_force: f32,
+}
+
+impl Default for RcsBalancing {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            torque_weight: 4.0,
+            max_force: 0.1,
+        }
+    }
+}
+
+impl RcsBalancing {
+    /// `--rcs` turns the ship with thrusters.
+    pub fn from_args() -> Self {
+        Self {
+            enabled: std::env::args().any(|arg| arg == "--rcs"),
+            ..default()
+        }
+    }
+}
+
+/// How hard to fire each of `thrusters`, from 0 to 1, to make `force` and `torque` around
+/// `center_of_mass`, all in the same frame. It's the weighted least squares solution with
+/// the levels kept in range, with a little extra cost on each level so that thrusters don't
+/// fire against each other for nothing.
+pub fn allocate_thrusters(
+    force: Vec3,
+    torque: Vec3,
+    thrusters: &RcsThrusters,
+    center_of_mass: Vec3,
+    torque_weight: f32,
+) -> Vec<f32> {
+    const REGULARIZATION: f32 = 1e-4;
+    const SWEEPS: usize = 200;
+
+    let weights = [1.0, 1.0, 1.0, torque_weight, torque_weight, torque_weight];
+    // what each thruster does at full force, as force and torque
+    let columns: Vec<[f32; 6]> = thrusters
+        .0
+        .iter()
+        .map(|thruster| {
+            let f = thruster.direction * thruster.max_force;
+            let t = (thruster.offset - center_of_mass).cross(f);
+            [f.x, f.y, f.z, t.x, t.y, t.z]
+        })
+        .collect();
+    let target = [force.x, force.y, force.z, torque.x, torque.y, torque.z];
+
+    // coordinate descent: it's convex, so going through one level after the other, each
+    // time to its best value with the others fixed, converges to the optimum
+    let mut levels = vec![0.0; columns.len()];
+    let mut residual = target;
+    for _ in 0..SWEEPS {
+        let mut change = 0.0f32;
+        for (level, column) in levels.iter_mut().zip(&columns) {
+            let (mut dot, mut norm) = (0.0, REGULARIZATION);
+            for row in 0..6 {
+                dot += weights[row] * column[row] * (residual[row] + column[row] * *level);
+                norm += weights[row] * column[row] * column[row];
+            }
+            let new = (dot / norm).clamp(0.0, 1.0);
+            for row in 0..6 {
+                residual[row] -= column[row] * (new - *level);
+            }
+            change = change.max((new - *level).abs());
+            *level = new;
+        }
+        if change < 1e-6 {
+            break;
+        }
+    }
+    levels
+}
+
+/// Puts thrusters on ships that don't have any yet once balancing is turned on.
+pub fn install_rcs(
+    mut commands: Commands,
+    config: Res<RcsBalancing>,
+    query: Query<Entity, (With<Spaceship>, Without<RcsThrusters>)>,
+) {
+    if !config.enabled {
+        return;
+    }
+    for ship in &query {
+        commands.entity(ship).insert(RcsThrusters::rings(
+            crate::SHIP_HEIGHT,
+            0.5,
+            config.max_force,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{allocate_thrusters, RcsThrusters};
+
+    fn thrusters() -> RcsThrusters {
+        RcsThrusters::rings(4.0, 0.5, 0.1)
+    }
+
+    #[test]
+    fn pure_torque_without_pushing() {
+        let thrusters = thrusters();
+        for torque in [
+            Vec3::new(0.2, 0.0, 0.0),
+            Vec3::new(0.0, -0.02, 0.0),
+            Vec3::new(0.0, 0.0, -0.2),
+            Vec3::new(0.1, 0.01, 0.1),
+        ] {
+            let levels = allocate_thrusters(Vec3::ZERO, torque, &thrusters, Vec3::ZERO, 4.0);
+            assert!(levels.iter().all(|l| (0.0..=1.0).contains(l)), "{levels:?}");
+            let (net_force, net_torque) = thrusters.net(&levels, Vec3::ZERO);
+            assert!(
+                net_torque.distance(torque) < 1e-3,
+                "{net_torque} == {torque}"
+            );
+            assert!(net_force.length() < 1e-3, "{net_force}");
+        }
+    }
+
+    #[test]
+    fn pure_force_without_turning() {
+        let thrusters = thrusters();
+        let force = Vec3::new(0.1, 0.0, -0.05);
+        let levels = allocate_thrusters(force, Vec3::ZERO, &thrusters, Vec3::ZERO, 4.0);
+        let (net_force, net_torque) = thrusters.net(&levels, Vec3::ZERO);
+        assert!(net_force.distance(force) < 1e-3, "{net_force} == {force}");
+        assert!(net_torque.length() < 1e-3, "{net_torque}");
+    }
+
+    #[test]
+    fn balances_around_the_center_of_mass() {
+        // heavier at the bottom, the top thrusters have the longer lever
+        let thrusters = thrusters();
+        let center_of_mass = Vec3::new(0.0, -1.0, 0.0);
+        let force = Vec3::new(0.1, 0.0, 0.0);
+        let levels = allocate_thrusters(force, Vec3::ZERO, &thrusters, center_of_mass, 4.0);
+        let (net_force, net_torque) = thrusters.net(&levels, center_of_mass);
+        assert!(net_force.distance(force) < 1e-3, "{net_force} == {force}");
+        assert!(net_torque.length() < 1e-3, "{net_torque}");
+        // not the same as balancing around the middle
+        let (_, around_middle) = thrusters.net(&levels, Vec3::ZERO);
+        assert!(around_middle.length() > 1e-2, "{around_middle}");
+    }
+
+    #[test]
+    fn saturates_beyond_the_limits() {
+        let thrusters = thrusters();
+        let levels = allocate_thrusters(
+            Vec3::ZERO,
+            Vec3::new(100.0, 0.0, 0.0),
+            &thrusters,
+            Vec3::ZERO,
+            4.0,
+        );
+        assert!(levels.iter().all(|l| (0.0..=1.0).contains(l)), "{levels:?}");
+        let (net_force, net_torque) = thrusters.net(&levels, Vec3::ZERO);
+        assert!(net_torque.x > 0.0, "{net_torque}");
+        assert!(net_force.length() < 1e-3, "{net_force}");
+    }
+}
@@ -0,0 +1,154 @@
+//! Picking the body that the orbit readouts are relative to, instead of whichever attractor
+//! pulls the hardest on the ship.
+
+use bevy::{
+    ecs::{
+        query::{ROQueryItem, ReadOnlyWorldQuery, WorldQuery},
+        system::SystemParam,
+    },
+    prelude::*,
+};
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    dominant_attractor, hud::ShipStatusText, input::InputBindings, inspect::velocity_of,
+    rails::OnRails, GravityAttractor, Spaceship,
+};
+
+/// The body to show the orbit, altitude and velocity relative to.
+/// `None` follows the dominant attractor.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReferenceBody(pub Option<Entity>);
+
+/// The selected reference body out of `bodies`, or the dominant attractor at `pos` if there is
+/// none or it isn't one of them.
+pub fn reference_attractor<'a, Q: WorldQuery, F: ReadOnlyWorldQuery>(
+    reference: &ReferenceBody,
+    bodies: &'a Query<'_, '_, Q, F>,
+    pos: Vec3,
+    body: impl Fn(&ROQueryItem<'a, Q>) -> (Vec3, f64),
+) -> Option<ROQueryItem<'a, Q>> {
+    reference
+        .0
+        .and_then(|entity| bodies.get(entity).ok())
+        .or_else(|| dominant_attractor(bodies, pos, body))
+}
+
+/// How fast the attractors move, so that the ship's velocity can be taken relative to the
+/// reference body like its position is.
+#[derive(SystemParam)]
+pub struct BodyVelocities<'w, 's> {
+    time: Res<'w, Time>,
+    bodies: Query<
+        'w,
+        's,
+        (
+            &'static GravityAttractor,
+            Option<&'static Velocity>,
+            Option<&'static OnRails>,
+        ),
+        Without<Spaceship>,
+    >,
+}
+
+impl BodyVelocities<'_, '_> {
+    /// The velocity of `body`, zero if it isn't an attractor.
+    pub fn of(&self, body: Entity) -> Vec3 {
+        let Ok((_, velocity, rails)) = self.bodies.get(body) else {
+            return Vec3::ZERO;
+        };
+        let parent_mass = rails
+            .and_then(|rails| self.bodies.get(rails.parent).ok())
+            .map_or(0.0, |(g, ..)| g.mass);
+        velocity_of(
+            velocity,
+            rails,
+            parent_mass,
+            self.time.elapsed_seconds_f64(),
+        )
+    }
+}
+
+/// The body after `current` in `bodies`, going back to the dominant attractor after the last.
+fn next_reference(bodies: &[Entity], current: Option<Entity>) -> Option<Entity> {
+    match current {
+        None => bodies.first().copied(),
+        Some(current) => bodies
+            .iter()
+            .position(|&body| body == current)
+            .and_then(|i| bodies.get(i + 1))
+            .copied(),
+    }
+}
+
+/// Cycles through the attractors, and goes back to the dominant one when the selected body
+/// is despawned.
+pub fn select_reference_body(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut reference: ResMut<ReferenceBody>,
+    body_query: Query<(Entity, Option<&Name>), With<GravityAttractor>>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+) {
+    if let Some(entity) = reference.0 {
+        if !body_query.contains(entity) {
+            info!("Reference body is gone, following the dominant attractor");
+            reference.0 = None;
+        }
+    }
+
+    if keyboard_input.just_pressed(bindings.reference_body) {
+        let mut bodies: Vec<_> = body_query.iter().map(|(entity, _)| entity).collect();
+        bodies.sort();
+        reference.0 = next_reference(&bodies, reference.0);
+    }
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[9].value = match reference.0.and_then(|entity| body_query.get(entity).ok()) {
+            Some((_, Some(name))) => name.to_string(),
+            Some((entity, None)) => format!("{entity:?}"),
+            None => "dominant".to_owned(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::SystemState, prelude::*};
+    use bevy_rapier3d::prelude::Velocity;
+
+    use super::{next_reference, BodyVelocities};
+    use crate::{GravityAttractor, Spaceship};
+
+    #[test]
+    fn cycles_back_to_dominant() {
+        let bodies = [Entity::from_raw(3), Entity::from_raw(5)];
+        assert_eq!(next_reference(&bodies, None), Some(bodies[0]));
+        assert_eq!(next_reference(&bodies, Some(bodies[0])), Some(bodies[1]));
+        assert_eq!(next_reference(&bodies, Some(bodies[1])), None);
+        // not a body anymore
+        assert_eq!(next_reference(&bodies, Some(Entity::from_raw(4))), None);
+        assert_eq!(next_reference(&[], None), None);
+    }
+
+    #[test]
+    fn velocity_of_the_bodies() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let moving = world
+            .spawn((
+                GravityAttractor { mass: 1.0e12 },
+                Velocity::linear(Vec3::new(3.0, 0.0, -4.0)),
+            ))
+            .id();
+        let fixed = world.spawn(GravityAttractor { mass: 1.0e12 }).id();
+        let ship = world.spawn((Spaceship, Velocity::linear(Vec3::X))).id();
+
+        let mut state = SystemState::<BodyVelocities>::new(&mut world);
+        let velocities = state.get(&world);
+        assert_eq!(velocities.of(moving), Vec3::new(3.0, 0.0, -4.0));
+        assert_eq!(velocities.of(fixed), Vec3::ZERO);
+        // not an attractor
+        assert_eq!(velocities.of(ship), Vec3::ZERO);
+    }
+}
@@ -0,0 +1,148 @@
+//! Cycling through rendering modes to debug materials and lighting,
+//! and debug gizmos for the orientation of the ship.
+
+use bevy::{
+    asset::HandleId,
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    render::render_resource::{AsBindGroup, ShaderRef},
+    utils::HashSet,
+};
+
+/// Colors every surface by its world space normal.
+#[derive(AsBindGroup, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "5b0e4a43-6f0a-4a8f-9d7c-1f3f2f6c9e21"]
+pub struct NormalsMaterial {}
+
+impl Material for NormalsMaterial {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/normals.wgsl".into()
+    }
+}
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderMode {
+    #[default]
+    Shaded,
+    Unlit,
+    Normals,
+}
+
+impl RenderMode {
+    fn next(self) -> Self {
+        match self {
+            RenderMode::Shaded => RenderMode::Unlit,
+            RenderMode::Unlit => RenderMode::Normals,
+            RenderMode::Normals => RenderMode::Shaded,
+        }
+    }
+}
+
+/// The material an entity had before it was switched to the [`NormalsMaterial`].
+#[derive(Component)]
+pub struct OriginalMaterial(Handle<StandardMaterial>);
+
+pub fn cycle_render_mode(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mode: ResMut<RenderMode>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut normals_materials: ResMut<Assets<NormalsMaterial>>,
+    mut normals_material: Local<Option<Handle<NormalsMaterial>>>,
+    // materials that were unlit on their own, so they stay unlit when going back to shaded
+    mut originally_unlit: Local<HashSet<HandleId>>,
+    standard_query: Query<(Entity, &Handle<StandardMaterial>)>,
+    normals_query: Query<(Entity, &OriginalMaterial)>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    let old = *mode;
+    *mode = old.next();
+
+    if old == RenderMode::Shaded {
+        *originally_unlit = materials
+            .iter()
+            .filter(|(_, material)| material.unlit)
+            .map(|(id, _)| id)
+            .collect();
+    }
+
+    match *mode {
+        RenderMode::Shaded | RenderMode::Unlit => {
+            for (entity, original) in &normals_query {
+                commands
+                    .entity(entity)
+                    .remove::<(Handle<NormalsMaterial>, OriginalMaterial)>()
+                    .insert(original.0.clone());
+            }
+
+            let unlit = *mode == RenderMode::Unlit;
+            for (id, material) in materials.iter_mut() {
+                material.unlit = unlit || originally_unlit.contains(&id);
+            }
+        }
+        RenderMode::Normals => {
+            let normals_material = normals_material
+                .get_or_insert_with(|| normals_materials.add(NormalsMaterial {}))
+                .clone();
+
+            for (entity, original) in &standard_query {
+                commands
+                    .entity(entity)
+                    .remove::<Handle<StandardMaterial>>()
+                    .insert((OriginalMaterial(original.clone()), normals_material.clone()));
+            }
+        }
+    }
+
+    info!("Render mode: {:?}", *mode);
+}
+
+/// Which debug gizmos are drawn, and how.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct GizmoStyle {
+    pub ship_axes: bool,
+    pub axis_length: f32,
+}
+
+impl Default for GizmoStyle {
+    fn default() -> Self {
+        Self {
+            ship_axes: false,
+            axis_length: 2.0,
+        }
+    }
+}
+
+/// F1 toggles the ship axes.
+pub fn toggle_ship_axes(keyboard_input: Res<Input<KeyCode>>, mut style: ResMut<GizmoStyle>) {
+    if keyboard_input.just_pressed(KeyCode::F1) {
+        style.ship_axes = !style.ship_axes;
+    }
+}
+
+/// Draws the local X, Y and Z axes of the ship in red, green and blue.
+/// The thrusters push along Y.
+pub fn draw_ship_axes(
+    style: Res<GizmoStyle>,
+    query: Query<&Transform, With<crate::Spaceship>>,
+    mut gizmos: Gizmos,
+) {
+    if !style.ship_axes {
+        return;
+    }
+
+    for transform in &query {
+        let rotation = Mat3::from_quat(transform.rotation);
+        let axes = [
+            (rotation.x_axis, Color::RED),
+            (rotation.y_axis, Color::GREEN),
+            (rotation.z_axis, Color::BLUE),
+        ];
+        for (axis, color) in axes {
+            gizmos.ray(transform.translation, axis * style.axis_length, color);
+        }
+    }
+}
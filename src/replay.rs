@@ -0,0 +1,395 @@
+//! Recording the keys held on every tick and playing them back later, reproducing
+//! a flight exactly as the physics is deterministic with a fixed timestep.
+//!
+//! The file format is plain text:
+//!
+//! ```text
+//! spaceflight replay 1
+//! scenario circular
+//! 0 Space
+//! 120
+//! end 300
+//! ```
+//!
+//! After the version and the scenario, every line is a tick followed by the keys held from
+//! then on, and the last line is the total amount of ticks.
+
+use std::{collections::HashSet, fmt, path::PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{input::LayoutProfile, scenario::Scenario};
+
+pub const REPLAY_VERSION: u32 = 1;
+
+/// The keys that aren't bound in [`InputBindings`](crate::input::InputBindings), like the function keys for debugging and
+/// switching the layout.
+const UNBOUND_KEYS: &[KeyCode] = &[
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+/// The keys that are recorded: the bindings of every layout and the unbound keys, so a
+/// recording still replays after switching the layout.
+fn replay_keys() -> Vec<KeyCode> {
+    let mut keys = LayoutProfile::PRESETS
+        .iter()
+        .filter_map(|profile| profile.bindings())
+        .flat_map(|bindings| bindings.keys())
+        .chain(UNBOUND_KEYS.iter().copied())
+        .collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    replay_keys().into_iter().find(|&key| key_name(key) == name)
+}
+
+/// The held keys whenever they changed, by tick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub scenario: Scenario,
+    pub changes: Vec<(u32, Vec<KeyCode>)>,
+    pub ticks: u32,
+}
+
+impl Recording {
+    pub fn new(scenario: Scenario) -> Self {
+        Recording {
+            scenario,
+            changes: Vec::new(),
+            ticks: 0,
+        }
+    }
+
+    /// Appends a tick where `held` are held down.
+    pub fn push(&mut self, held: &[KeyCode]) {
+        let changed = self
+            .changes
+            .last()
+            .map_or(!held.is_empty(), |(_, last)| last != held);
+        if changed {
+            self.changes.push((self.ticks, held.to_vec()));
+        }
+        self.ticks += 1;
+    }
+
+    /// The keys held at `tick`, none after the end.
+    pub fn held_at(&self, tick: u32) -> &[KeyCode] {
+        if tick >= self.ticks {
+            return &[];
+        }
+        let index = self.changes.partition_point(|&(t, _)| t <= tick);
+        match index {
+            0 => &[],
+            i => &self.changes[i - 1].1,
+        }
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "spaceflight replay {REPLAY_VERSION}")?;
+        writeln!(f, "scenario {}", self.scenario.name())?;
+        for (tick, keys) in &self.changes {
+            write!(f, "{tick}")?;
+            for &key in keys {
+                write!(f, " {}", key_name(key))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "end {}", self.ticks)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    NotAReplay,
+    UnsupportedVersion(String),
+    UnknownScenario(String),
+    UnknownKey(String),
+    /// A line that isn't a tick with keys, or ticks that aren't in order.
+    InvalidLine(String),
+    MissingEnd,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::NotAReplay => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported replay version {version}, only {REPLAY_VERSION} is supported"
+            ),
+            ReplayError::UnknownScenario(name) => write!(f, "unknown scenario `{name}`"),
+            ReplayError::UnknownKey(name) => write!(f, "unknown key `{name}`"),
+            ReplayError::InvalidLine(line) => write!(f, "invalid line `{line}`"),
+            ReplayError::MissingEnd => write!(f, "the replay is cut off"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl std::str::FromStr for Recording {
+    type Err = ReplayError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix("spaceflight replay "))
+            .ok_or(ReplayError::NotAReplay)?;
+        if version != REPLAY_VERSION.to_string() {
+            return Err(ReplayError::UnsupportedVersion(version.to_owned()));
+        }
+
+        let scenario = lines
+            .next()
+            .and_then(|line| line.strip_prefix("scenario "))
+            .ok_or(ReplayError::NotAReplay)?;
+        let scenario = Scenario::from_name(scenario)
+            .ok_or_else(|| ReplayError::UnknownScenario(scenario.to_owned()))?;
+
+        let mut recording = Recording::new(scenario);
+        for line in lines {
+            let invalid = || ReplayError::InvalidLine(line.to_owned());
+
+            if let Some(ticks) = line.strip_prefix("end ") {
+                recording.ticks = ticks.parse().map_err(|_| invalid())?;
+                if recording
+                    .changes
+                    .last()
+                    .is_some_and(|&(t, _)| t >= recording.ticks)
+                {
+                    return Err(invalid());
+                }
+                return Ok(recording);
+            }
+
+            let mut parts = line.split_whitespace();
+            let tick: u32 = parts.next().unwrap().parse().map_err(|_| invalid())?;
+            if recording.changes.last().is_some_and(|&(t, _)| t >= tick) {
+                return Err(invalid());
+            }
+            let keys = parts
+                .map(|name| {
+                    key_from_name(name).ok_or_else(|| ReplayError::UnknownKey(name.to_owned()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            recording.changes.push((tick, keys));
+        }
+
+        Err(ReplayError::MissingEnd)
+    }
+}
+
+/// `--replay=<path>` plays back a recording instead of the keyboard.
+pub fn replay_from_args() -> Option<Recording> {
+    let path = std::env::args().find_map(|arg| arg.strip_prefix("--replay=").map(PathBuf::from))?;
+    let recording = std::fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|s| s.parse::<Recording>().map_err(|err| err.to_string()));
+    match recording {
+        Ok(recording) => Some(recording),
+        Err(err) => {
+            error!("Can't replay {}: {err}", path.display());
+            None
+        }
+    }
+}
+
+/// `--record=<path>` records the flight, saved when the game is closed.
+pub fn record_path_from_args() -> Option<PathBuf> {
+    std::env::args().find_map(|arg| arg.strip_prefix("--record=").map(PathBuf::from))
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct Recorder {
+    pub recording: Recording,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct Replayer {
+    pub recording: Recording,
+    pub tick: u32,
+}
+
+impl Replayer {
+    pub fn new(recording: Recording) -> Self {
+        Replayer { recording, tick: 0 }
+    }
+}
+
+pub fn record_input(keyboard_input: Res<Input<KeyCode>>, mut recorder: ResMut<Recorder>) {
+    let held = replay_keys()
+        .into_iter()
+        .filter(|&key| keyboard_input.pressed(key))
+        .collect::<Vec<_>>();
+    recorder.recording.push(&held);
+}
+
+pub fn save_recording(mut exit: EventReader<AppExit>, recorder: Res<Recorder>) {
+    if exit.iter().next().is_none() {
+        return;
+    }
+    let Some(path) = &recorder.path else {
+        return;
+    };
+
+    match std::fs::write(path, recorder.recording.to_string()) {
+        Ok(()) => info!("Saved the replay to {}", path.display()),
+        Err(err) => error!("Can't save the replay to {}: {err}", path.display()),
+    }
+}
+
+/// Replaces whatever the keyboard did with the keys of the recording.
+pub fn replay_input(mut keyboard_input: ResMut<Input<KeyCode>>, mut replayer: ResMut<Replayer>) {
+    let tick = replayer.tick;
+    if tick == replayer.recording.ticks {
+        info!("Replay finished");
+    }
+    replayer.tick += 1;
+
+    let held = replayer
+        .recording
+        .held_at(tick)
+        .iter()
+        .copied()
+        .collect::<HashSet<_>>();
+    let previous = match tick.checked_sub(1) {
+        Some(previous) => replayer
+            .recording
+            .held_at(previous)
+            .iter()
+            .copied()
+            .collect(),
+        None => HashSet::new(),
+    };
+
+    // rebuilt from scratch, so that live keys don't leak in
+    let mut input = Input::default();
+    for &key in held.union(&previous) {
+        input.press(key);
+        if previous.contains(&key) {
+            input.clear_just_pressed(key);
+        }
+        if !held.contains(&key) {
+            input.release(key);
+        }
+    }
+    *keyboard_input = input;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{key_from_name, key_name, Recorder, Recording, ReplayError, Replayer};
+    use crate::{
+        headless::{self, KeyHold},
+        input::LayoutProfile,
+        scenario::Scenario,
+    };
+
+    #[test]
+    fn replays_recorded_burn() {
+        let inputs = [
+            KeyHold {
+                key: KeyCode::Space,
+                ticks: 10..70,
+            },
+            KeyHold {
+                key: KeyCode::W,
+                ticks: 20..30,
+            },
+        ];
+        let ticks = 120;
+
+        let mut app = headless::headless_app(Scenario::CircularOrbit);
+        app.insert_resource(Recorder {
+            recording: Recording::new(Scenario::CircularOrbit),
+            path: None,
+        })
+        .add_systems(PreUpdate, super::record_input);
+        let recorded = headless::run_app(&mut app, &inputs, ticks);
+
+        let recording = app.world.resource::<Recorder>().recording.to_string();
+        let recording: Recording = recording.parse().unwrap();
+        assert_eq!(recording.ticks, ticks);
+
+        let mut app = headless::headless_app(recording.scenario);
+        app.insert_resource(Replayer::new(recording))
+            .add_systems(PreUpdate, super::replay_input);
+        let replayed = headless::run_app(&mut app, &[], ticks);
+
+        assert_eq!(recorded, replayed);
+    }
+
+    #[test]
+    fn records_every_binding() {
+        for profile in LayoutProfile::PRESETS {
+            for key in profile.bindings().unwrap().keys() {
+                assert_eq!(key_from_name(&key_name(key)), Some(key), "{profile:?}");
+            }
+        }
+        assert_eq!(key_from_name("F2"), Some(KeyCode::F2));
+    }
+
+    #[test]
+    fn nothing_held_after_the_end() {
+        let mut recording = Recording::new(Scenario::CircularOrbit);
+        recording.push(&[KeyCode::Space]);
+        recording.push(&[KeyCode::Space, KeyCode::W]);
+
+        assert_eq!(recording.held_at(0), &[KeyCode::Space]);
+        assert_eq!(recording.held_at(1), &[KeyCode::Space, KeyCode::W]);
+        assert_eq!(recording.held_at(2), &[]);
+        assert_eq!(recording.held_at(100), &[]);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let parse = |s: &str| s.parse::<Recording>().unwrap_err();
+
+        assert_eq!(parse("hello"), ReplayError::NotAReplay);
+        assert_eq!(
+            parse("spaceflight replay 99\nscenario default\nend 1"),
+            ReplayError::UnsupportedVersion("99".to_owned())
+        );
+        assert_eq!(
+            parse("spaceflight replay 1\nscenario nowhere\nend 1"),
+            ReplayError::UnknownScenario("nowhere".to_owned())
+        );
+        assert_eq!(
+            parse("spaceflight replay 1\nscenario default\n0 Banana\nend 1"),
+            ReplayError::UnknownKey("Banana".to_owned())
+        );
+        assert_eq!(
+            parse("spaceflight replay 1\nscenario default\n5 Space\n2\nend 10"),
+            ReplayError::InvalidLine("2".to_owned())
+        );
+        assert_eq!(
+            parse("spaceflight replay 1\nscenario default\n0 Space"),
+            ReplayError::MissingEnd
+        );
+    }
+}
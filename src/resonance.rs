@@ -0,0 +1,97 @@
+use std::fmt;
+
+use crate::orbit::Orbit;
+
+/// A mean-motion resonance `p:q`, meaning the inner body completes `p` orbits
+/// in the time the outer body completes `q`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resonance {
+    pub p: u32,
+    pub q: u32,
+}
+
+impl Resonance {
+    pub fn ratio(&self) -> f64 {
+        f64::from(self.p) / f64::from(self.q)
+    }
+}
+
+impl fmt::Display for Resonance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.p, self.q)
+    }
+}
+
+/// The resonances worth pointing out, roughly ordered by how often they show up.
+pub const COMMON_RESONANCES: &[Resonance] = &[
+    Resonance { p: 1, q: 1 },
+    Resonance { p: 2, q: 1 },
+    Resonance { p: 3, q: 2 },
+    Resonance { p: 4, q: 3 },
+    Resonance { p: 3, q: 1 },
+    Resonance { p: 5, q: 2 },
+    Resonance { p: 5, q: 3 },
+];
+
+/// Relative deviation from an exact ratio that still counts as resonant.
+pub const RESONANCE_TOLERANCE: f64 = 0.02;
+
+/// The ratio of the longer to the shorter period of two orbits around a body of mass `m`,
+/// so it's always `>= 1`.
+pub fn period_ratio(m: f64, a: &Orbit, b: &Orbit) -> f64 {
+    let (pa, pb) = (a.period(m), b.period(m));
+    f64::max(pa, pb) / f64::min(pa, pb)
+}
+
+/// Finds the common resonance closest to `ratio`, if it's within `tolerance`.
+pub fn classify_resonance(ratio: f64, tolerance: f64) -> Option<Resonance> {
+    if !ratio.is_finite() {
+        return None;
+    }
+
+    COMMON_RESONANCES
+        .iter()
+        .map(|&res| (res, (ratio - res.ratio()).abs() / res.ratio()))
+        .filter(|&(_, deviation)| deviation <= tolerance)
+        .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        .map(|(res, _)| res)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::orbit::Orbit;
+
+    use super::{classify_resonance, period_ratio, Resonance, RESONANCE_TOLERANCE};
+
+    const M: f64 = 5.972e24;
+
+    fn circular(semi_major_axis: f64) -> Orbit {
+        Orbit {
+            semi_major_axis,
+            eccentricity: 0.0,
+        }
+    }
+
+    fn classify(inner: f64, period_factor: f64) -> Option<Resonance> {
+        // P ~ a^(3/2), so scaling the period by k scales a by k^(2/3).
+        let outer = inner * period_factor.powf(2.0 / 3.0);
+        let ratio = period_ratio(M, &circular(inner), &circular(outer));
+        classify_resonance(ratio, RESONANCE_TOLERANCE)
+    }
+
+    #[test]
+    fn two_to_one() {
+        assert_eq!(classify(42000.0, 2.0), Some(Resonance { p: 2, q: 1 }));
+    }
+
+    #[test]
+    fn three_to_two() {
+        assert_eq!(classify(42000.0, 1.5), Some(Resonance { p: 3, q: 2 }));
+    }
+
+    #[test]
+    fn non_resonant() {
+        let res = classify(42000.0, 1.87);
+        assert_eq!(res.map_or("none".to_owned(), |r| r.to_string()), "none");
+    }
+}
@@ -0,0 +1,136 @@
+//! Rotation of celestial bodies, either spinning freely or tidally locked to their parent.
+
+use bevy::prelude::*;
+
+use crate::input::InputBindings;
+
+/// Spins the body with a constant angular velocity, in radians per second.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct RotationRate(pub Vec3);
+
+/// Keeps the same face of the body pointed at `parent`, like the moon does with the earth.
+/// The face is the local -X axis. This overrides any [`RotationRate`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct TidallyLocked {
+    pub parent: Entity,
+}
+
+/// The local direction that faces the parent of a [`TidallyLocked`] body.
+pub const LOCKED_FACE: Vec3 = Vec3::NEG_X;
+
+/// Stops the [`RotationRate`] spin, keeping the bodies as they are, for watching the
+/// surface below without it moving away.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct RotationPaused(pub bool);
+
+pub fn toggle_rotation_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut paused: ResMut<RotationPaused>,
+) {
+    if keyboard_input.just_pressed(bindings.freeze_rotation) {
+        paused.0 = !paused.0;
+        info!("Planet rotation paused: {}", paused.0);
+    }
+}
+
+pub fn spin_bodies(
+    time: Res<Time>,
+    paused: Res<RotationPaused>,
+    mut query: Query<(&RotationRate, &mut Transform), Without<TidallyLocked>>,
+) {
+    if paused.0 {
+        return;
+    }
+    for (rate, mut transform) in &mut query {
+        let angle = rate.0 * time.delta_seconds();
+        transform.rotation = Quat::from_scaled_axis(angle) * transform.rotation;
+    }
+}
+
+pub fn tidal_lock(
+    mut query: Query<(&TidallyLocked, &mut Transform)>,
+    parent_query: Query<&Transform, Without<TidallyLocked>>,
+) {
+    for (locked, mut transform) in &mut query {
+        let Ok(parent) = parent_query.get(locked.parent) else {
+            continue;
+        };
+        let Some(to_parent) = (parent.translation - transform.translation).try_normalize() else {
+            continue;
+        };
+
+        // the smallest rotation that turns the face back to the parent,
+        // which keeps the spin axis along the orbit normal
+        let face = transform.rotation * LOCKED_FACE;
+        transform.rotation =
+            (Quat::from_rotation_arc(face, to_parent) * transform.rotation).normalize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+    use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+    use super::{RotationPaused, RotationRate, TidallyLocked};
+
+    #[test]
+    fn quarter_orbit_is_quarter_turn() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<RotationPaused>()
+            .add_systems(Update, (super::spin_bodies, super::tidal_lock).chain());
+
+        let parent = app.world.spawn(Transform::IDENTITY).id();
+        let moon = app
+            .world
+            .spawn((
+                TidallyLocked { parent },
+                // the lock wins against the spin
+                RotationRate(Vec3::new(0.0, 100.0, 0.0)),
+                Transform::from_xyz(1000.0, 0.0, 0.0),
+            ))
+            .id();
+
+        app.update();
+        let rotation = app.world.get::<Transform>(moon).unwrap().rotation;
+        assert!(rotation.angle_between(Quat::IDENTITY) < 1e-4, "{rotation}");
+
+        // a quarter of the way around, at +Z
+        app.world.get_mut::<Transform>(moon).unwrap().translation = Vec3::new(0.0, 0.0, 1000.0);
+        app.update();
+
+        let rotation = app.world.get::<Transform>(moon).unwrap().rotation;
+        let expected = Quat::from_rotation_y(-FRAC_PI_2);
+        assert!(
+            rotation.angle_between(expected) < 1e-4,
+            "{rotation} == {expected}"
+        );
+    }
+
+    #[test]
+    fn paused_rotation_stays() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(1)))
+            .init_resource::<RotationPaused>()
+            .add_systems(Update, super::spin_bodies);
+        let planet = app
+            .world
+            .spawn((RotationRate(Vec3::new(0.0, 0.1, 0.0)), Transform::IDENTITY))
+            .id();
+        let rotation = |app: &App| app.world.get::<Transform>(planet).unwrap().rotation;
+
+        app.update();
+        app.update();
+        let spun = rotation(&app);
+        assert!(spun.angle_between(Quat::IDENTITY) > 0.05, "{spun}");
+
+        app.world.resource_mut::<RotationPaused>().0 = true;
+        app.update();
+        app.update();
+        assert_eq!(rotation(&app), spun);
+    }
+}
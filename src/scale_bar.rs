@@ -0,0 +1,200 @@
+//! A scale bar showing how long a distance on screen is at the ship, and a small
+//! north/east/up indicator in the corner, to get a feel for the scale of the scene.
+
+use bevy::prelude::*;
+
+use crate::{
+    reference::{reference_attractor, ReferenceBody},
+    units::HudUnits,
+    GravityAttractor, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ScaleBar {
+    pub enabled: bool,
+    /// The longest the bar gets in logical pixels, it's shortened to a round length.
+    pub max_length: f32,
+}
+
+impl Default for ScaleBar {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_length: 150.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ScaleBarNode;
+
+#[derive(Component)]
+pub struct ScaleBarText;
+
+/// The world length that `pixels` logical pixels cover at `distance` from a perspective
+/// camera with a vertical field of view of `fov` and a viewport `viewport_height` pixels high.
+pub fn world_length_of_pixels(fov: f32, viewport_height: f32, distance: f32, pixels: f32) -> f32 {
+    2.0 * distance * (fov / 2.0).tan() * pixels / viewport_height
+}
+
+/// The largest 1, 2 or 5 times a power of ten that is at most `length`.
+pub fn round_length(length: f32) -> f32 {
+    let magnitude = 10f32.powf(length.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&l| l <= length)
+        .unwrap_or(magnitude)
+}
+
+pub fn spawn_scale_bar(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ),
+                ScaleBarText,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        height: Val::Px(4.0),
+                        ..default()
+                    },
+                    background_color: Color::GRAY.into(),
+                    ..default()
+                },
+                ScaleBarNode,
+            ));
+        });
+}
+
+/// Sizes the scale bar for the distance from the camera to the ship.
+pub fn draw_scale_bar(
+    config: Res<ScaleBar>,
+    units: HudUnits,
+    camera_query: Query<(&Camera, &GlobalTransform, &Projection)>,
+    ship_query: Query<&Transform, With<Spaceship>>,
+    mut bar_query: Query<(&mut Style, &mut Visibility), With<ScaleBarNode>>,
+    mut text_query: Query<&mut Text, With<ScaleBarText>>,
+) {
+    let (Ok((mut style, mut visibility)), Ok(mut text)) =
+        (bar_query.get_single_mut(), text_query.get_single_mut())
+    else {
+        return;
+    };
+    let measured = camera_query
+        .iter()
+        .find(|(camera, _, _)| camera.is_active)
+        .zip(ship_query.get_single().ok());
+    let Some(((camera, camera_transform, Projection::Perspective(projection)), ship)) = measured
+    else {
+        *visibility = Visibility::Hidden;
+        text.sections[0].value.clear();
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+    if !config.enabled {
+        *visibility = Visibility::Hidden;
+        text.sections[0].value.clear();
+        return;
+    }
+
+    let distance = camera_transform.translation().distance(ship.translation);
+    let max_world = world_length_of_pixels(projection.fov, viewport.y, distance, config.max_length);
+    let length = round_length(max_world);
+
+    *visibility = Visibility::Inherited;
+    style.width = Val::Px(config.max_length * length / max_world);
+    text.sections[0].value = units.distance(f64::from(length));
+}
+
+/// Draws north, east and up at the ship's position on the dominant body in the bottom right
+/// corner of the screen, in red, green and blue.
+pub fn draw_surface_axes(
+    config: Res<ScaleBar>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    ship_query: Query<&Transform, With<Spaceship>>,
+    reference: Res<ReferenceBody>,
+    body_query: Query<(&Transform, &GravityAttractor), Without<Spaceship>>,
+    mut gizmos: Gizmos,
+) {
+    if !config.enabled {
+        return;
+    }
+    let (Some((camera, camera_transform)), Ok(ship)) = (
+        camera_query.iter().find(|(camera, _)| camera.is_active),
+        ship_query.get_single(),
+    ) else {
+        return;
+    };
+    let Some((body, _)) =
+        reference_attractor(&reference, &body_query, ship.translation, |(t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+
+    let up = (ship.translation - body.translation).normalize_or_zero();
+    let pole = body.rotation * Vec3::Y;
+    let north = (pole - up * pole.dot(up))
+        .try_normalize()
+        .unwrap_or(Vec3::NEG_Z);
+    let east = north.cross(up);
+
+    // close in front of the camera, so it's always the same size on screen
+    let corner = Vec2::new(viewport.x - 50.0, viewport.y - 50.0);
+    let Some(ray) = camera.viewport_to_world(camera_transform, corner) else {
+        return;
+    };
+    let center = ray.get_point(1.0);
+    let size = 0.05;
+    gizmos.ray(center, north * size, Color::RED);
+    gizmos.ray(center, east * size, Color::GREEN);
+    gizmos.ray(center, up * size, Color::BLUE);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{round_length, world_length_of_pixels};
+
+    #[test]
+    fn rounds_to_one_two_five() {
+        assert_eq!(round_length(1.0), 1.0);
+        assert_eq!(round_length(1.9), 1.0);
+        assert_eq!(round_length(4.0), 2.0);
+        assert_eq!(round_length(730.0), 500.0);
+        assert!((round_length(0.03) - 0.02).abs() < 1e-9);
+    }
+
+    #[test]
+    fn full_viewport_covers_frustum() {
+        let fov = std::f32::consts::FRAC_PI_2;
+        // at 90°, the frustum is twice as high as it is far away
+        let length = world_length_of_pixels(fov, 600.0, 10.0, 600.0);
+        assert!((length - 20.0).abs() < 1e-4, "{length}");
+        assert!((world_length_of_pixels(fov, 600.0, 10.0, 150.0) - 5.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+
+use crate::{
+    atmosphere::Atmosphere,
+    formation::FormationFlight,
+    orbit,
+    orbit::OrbitalElements,
+    rails::OnRails,
+    rotation::{RotationRate, TidallyLocked},
+    spawn_planet_system, spawn_spaceship,
+    stages::{Stage, Stages},
+    PlanetConfig, Spaceship, SpaceshipBundle, MOON_DENSITY,
+};
+
+/// The scene to start in, selected by the first command line argument that isn't a flag.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    #[default]
+    Default,
+    /// A low circular orbit in a thick atmosphere, slowly decaying until reentry.
+    OrbitDecay,
+    /// The same low circular orbit, but without an atmosphere.
+    CircularOrbit,
+    /// The circular orbit with a second ship flying in formation behind the player.
+    Formation,
+    /// The default scene with a few more planets far away, rendered with less detail.
+    DistantPlanets,
+    /// The circular orbit around a spinning planet, with a tidally locked moon on rails further out.
+    Moon,
+    /// The default scene with a two stage rocket.
+    Rocket,
+    /// The circular orbit, but the ship is tumbling out of control.
+    Tumble,
+}
+
+impl Scenario {
+    const NAMES: [(&'static str, Scenario); 8] = [
+        ("default", Scenario::Default),
+        ("decay", Scenario::OrbitDecay),
+        ("circular", Scenario::CircularOrbit),
+        ("formation", Scenario::Formation),
+        ("distant", Scenario::DistantPlanets),
+        ("moon", Scenario::Moon),
+        ("rocket", Scenario::Rocket),
+        ("tumble", Scenario::Tumble),
+    ];
+
+    pub fn from_args() -> Self {
+        let arg = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+        match arg.as_deref() {
+            None => Scenario::Default,
+            Some(name) => Scenario::from_name(name).unwrap_or_else(|| {
+                warn!("Unknown scenario `{name}`, using the default one");
+                Scenario::Default
+            }),
+        }
+    }
+
+    /// Whether a scenario was picked on the command line at all.
+    pub fn given_in_args() -> bool {
+        std::env::args().skip(1).any(|arg| !arg.starts_with("--"))
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, scenario)| scenario)
+    }
+
+    pub fn name(self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|&&(_, s)| s == self)
+            .map(|&(name, _)| name)
+            .unwrap()
+    }
+}
+
+/// Radius of the small planet used by the orbit scenarios.
+pub const SMALL_PLANET_RADIUS: f64 = 1000.0;
+/// Density of the small planet used by the orbit scenarios, in kg/m^3.
+/// It's ridiculously dense so that an orbit only takes about a minute.
+pub const SMALL_PLANET_DENSITY: f64 = 4.0e7;
+/// Altitude of the low orbit in the orbit scenarios.
+pub const LOW_ORBIT_ALTITUDE: f64 = 100.0;
+
+/// Radius of the moon in the moon scenario.
+pub const MOON_RADIUS: f64 = 100.0;
+/// Distance of the moon from the small planet.
+pub const MOON_ORBIT_RADIUS: f64 = 3000.0;
+
+/// The angular velocity the ship starts with in the tumble scenario, in rad/s.
+pub const TUMBLE_ANGVEL: Vec3 = Vec3::new(0.8, 0.3, -0.5);
+
+/// Spawns the planets and the ship of the scenario.
+pub fn spawn(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    asset_server: &AssetServer,
+    scenario: Scenario,
+) {
+    match scenario {
+        Scenario::Default | Scenario::DistantPlanets | Scenario::Rocket => {
+            let mut planets = vec![PlanetConfig {
+                position: Transform::from_xyz(0.0, -100.0, 0.0),
+                ..default()
+            }];
+            if scenario == Scenario::DistantPlanets {
+                planets.extend((0..5).map(|i| {
+                    let angle = std::f32::consts::TAU / 5.0 * (i as f32);
+                    let distance = 80000.0 + 20000.0 * (i as f32);
+                    let position = Vec3::new(angle.cos(), 0.1, angle.sin()) * distance;
+                    PlanetConfig {
+                        name: format!("Planet {}", i + 1),
+                        sectors: 64,
+                        stacks: 64,
+                        lod_distances: vec![0.0, 30000.0, 70000.0],
+                        ..PlanetConfig::new(
+                            Transform::from_translation(position),
+                            3000.0,
+                            MOON_DENSITY,
+                        )
+                    }
+                }));
+            }
+            spawn_planet_system(commands, meshes, materials, asset_server, &planets);
+
+            let ship =
+                SpaceshipBundle::new(meshes, materials, Vec3::new(0.0, 100.0, 0.0), Vec3::ZERO);
+            let ship = spawn_spaceship(commands, meshes, materials, ship);
+            if scenario == Scenario::Rocket {
+                commands.entity(ship).insert(Stages(vec![
+                    Stage {
+                        dry_mass: 0.5,
+                        fuel: 0.5,
+                        thrust: 1.0,
+                    },
+                    Stage {
+                        dry_mass: 1.0,
+                        fuel: 2.0,
+                        thrust: 4.0,
+                    },
+                ]));
+            }
+        }
+        Scenario::OrbitDecay
+        | Scenario::CircularOrbit
+        | Scenario::Formation
+        | Scenario::Moon
+        | Scenario::Tumble => {
+            let radius = SMALL_PLANET_RADIUS;
+            let planet = PlanetConfig::new(Transform::IDENTITY, radius, SMALL_PLANET_DENSITY);
+            let mass = planet.mass;
+            let mut planets = vec![planet];
+            if scenario == Scenario::Moon {
+                planets.push(PlanetConfig {
+                    name: "Moon".to_owned(),
+                    ..PlanetConfig::new(
+                        Transform::from_xyz(MOON_ORBIT_RADIUS as f32, 0.0, 0.0),
+                        MOON_RADIUS,
+                        SMALL_PLANET_DENSITY,
+                    )
+                });
+            }
+            let spawned = spawn_planet_system(commands, meshes, materials, asset_server, &planets);
+            let planet_entity = spawned[0];
+            let mut planet = commands.entity(planet_entity);
+            if scenario == Scenario::Moon {
+                // slow enough to not fling anything off the surface
+                planet.insert(RotationRate(Vec3::new(0.0, 0.01, 0.0)));
+            }
+            if scenario == Scenario::OrbitDecay {
+                planet.insert(Atmosphere {
+                    surface_density: 2.0e-5,
+                    scale_height: 50.0,
+                });
+            }
+
+            let orbit_radius = radius + LOW_ORBIT_ALTITUDE;
+            let angvel = if scenario == Scenario::Tumble {
+                TUMBLE_ANGVEL
+            } else {
+                Vec3::ZERO
+            };
+            let mut ship = SpaceshipBundle::new(
+                meshes,
+                materials,
+                Vec3::new(orbit_radius as f32, 0.0, 0.0),
+                angvel,
+            );
+            let velocity = Vec3::new(0.0, 0.0, (orbit::G * mass / orbit_radius).sqrt() as f32);
+            ship.vel.linvel = velocity;
+            let leader = spawn_spaceship(commands, meshes, materials, ship);
+
+            if scenario == Scenario::Moon {
+                commands.entity(spawned[1]).insert((
+                    OnRails {
+                        parent: planet_entity,
+                        orbit: OrbitalElements {
+                            semi_major_axis: MOON_ORBIT_RADIUS,
+                            eccentricity: 0.0,
+                            inclination: 0.0,
+                            longitude_of_ascending_node: 0.0,
+                            argument_of_periapsis: 0.0,
+                            true_anomaly: 0.0,
+                        },
+                        epoch: 0.0,
+                    },
+                    TidallyLocked {
+                        parent: planet_entity,
+                    },
+                ));
+            }
+
+            if scenario == Scenario::Formation {
+                let mut wingman = SpaceshipBundle::new(
+                    meshes,
+                    materials,
+                    Vec3::new(orbit_radius as f32 + 5.0, 0.0, -5.0),
+                    Vec3::ZERO,
+                );
+                wingman.vel.linvel = velocity;
+                let wingman = spawn_spaceship(commands, meshes, materials, wingman);
+                commands
+                    .entity(wingman)
+                    .remove::<Spaceship>()
+                    .insert(FormationFlight {
+                        leader,
+                        // 10 behind along the track
+                        offset: Vec3::new(0.0, -10.0, 0.0),
+                    });
+            }
+        }
+    }
+}
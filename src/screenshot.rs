@@ -0,0 +1,194 @@
+//! Screenshots of the window, one at a time with the screenshot key or as a numbered sequence
+//! to put together into an animation.
+
+use std::{fs, path::PathBuf};
+
+use bevy::{prelude::*, render::view::screenshot::ScreenshotManager, window::PrimaryWindow};
+
+use crate::input::InputBindings;
+
+/// Where single screenshots go, numbered by the elapsed time.
+const SCREENSHOT_DIR: &str = "screenshots";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScreenshotInterval {
+    Frames(u32),
+    /// Seconds of simulation time.
+    Seconds(f64),
+}
+
+/// Takes `count` screenshots `interval` apart into `dir`, then stops.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct ScreenshotSequence {
+    pub interval: ScreenshotInterval,
+    pub count: u32,
+    pub dir: PathBuf,
+    taken: u32,
+    /// The frame or time of the last screenshot.
+    last: Option<f64>,
+}
+
+impl ScreenshotSequence {
+    pub fn new(interval: ScreenshotInterval, count: u32, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            interval,
+            count,
+            dir: dir.into(),
+            taken: 0,
+            last: None,
+        }
+    }
+
+    /// `<count>@<interval>`, with the interval in frames like `10f` or in seconds like `0.5s`.
+    pub fn parse(arg: &str) -> Option<Self> {
+        let (count, interval) = arg.split_once('@')?;
+        let interval = if let Some(frames) = interval.strip_suffix('f') {
+            ScreenshotInterval::Frames(frames.parse().ok().filter(|&f| f > 0)?)
+        } else {
+            let seconds = interval.strip_suffix('s')?.parse().ok();
+            ScreenshotInterval::Seconds(seconds.filter(|&s: &f64| s > 0.0)?)
+        };
+        let count = count.parse().ok().filter(|&c| c > 0)?;
+        Some(Self::new(interval, count, "sequence"))
+    }
+
+    /// `--screenshot-sequence=<count>@<interval>` starts one right away.
+    pub fn from_args() -> Option<Self> {
+        let arg = std::env::args().find_map(|arg| {
+            arg.strip_prefix("--screenshot-sequence=")
+                .map(str::to_owned)
+        })?;
+        let sequence = Self::parse(&arg);
+        if sequence.is_none() {
+            error!("Invalid screenshot sequence `{arg}`, expected e.g. `100@10f` or `50@0.5s`");
+        }
+        sequence
+    }
+
+    /// The next file name, padded so that they sort in order.
+    fn next_path(&self) -> PathBuf {
+        let width = self.count.to_string().len();
+        self.dir
+            .join(format!("frame_{:0width$}.png", self.taken, width = width))
+    }
+
+    /// Whether a screenshot is due at `frame` and `time`.
+    fn due(&self, frame: u64, time: f64) -> bool {
+        let (now, interval) = match self.interval {
+            ScreenshotInterval::Frames(frames) => (frame as f64, f64::from(frames)),
+            ScreenshotInterval::Seconds(seconds) => (time, seconds),
+        };
+        self.last.is_none_or(|last| now - last >= interval)
+    }
+}
+
+pub fn take_screenshot_on_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    time: Res<Time>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+) {
+    if !keyboard_input.just_pressed(bindings.screenshot) {
+        return;
+    }
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    if let Err(err) = fs::create_dir_all(SCREENSHOT_DIR) {
+        error!("Failed to create `{SCREENSHOT_DIR}`: {err}");
+        return;
+    }
+
+    let path = PathBuf::from(SCREENSHOT_DIR).join(format!(
+        "screenshot_{:.0}.png",
+        time.elapsed_seconds_f64() * 1000.0
+    ));
+    match screenshots.save_screenshot_to_disk(window, &path) {
+        Ok(()) => info!("Saved a screenshot to `{}`", path.display()),
+        Err(err) => warn!("No screenshot: {err}"),
+    }
+}
+
+pub fn take_screenshot_sequence(
+    mut commands: Commands,
+    sequence: Option<ResMut<ScreenshotSequence>>,
+    time: Res<Time>,
+    mut frame: Local<u64>,
+    window_query: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+) {
+    *frame += 1;
+    let Some(mut sequence) = sequence else {
+        return;
+    };
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    if sequence.taken == 0 && sequence.last.is_none() {
+        if let Err(err) = fs::create_dir_all(&sequence.dir) {
+            error!("Failed to create `{}`: {err}", sequence.dir.display());
+            commands.remove_resource::<ScreenshotSequence>();
+            return;
+        }
+    }
+    let now = time.elapsed_seconds_f64();
+    if !sequence.due(*frame, now) {
+        return;
+    }
+    // only one per frame, try again on the next one otherwise
+    if screenshots
+        .save_screenshot_to_disk(window, sequence.next_path())
+        .is_err()
+    {
+        return;
+    }
+
+    sequence.taken += 1;
+    sequence.last = Some(match sequence.interval {
+        ScreenshotInterval::Frames(_) => *frame as f64,
+        ScreenshotInterval::Seconds(_) => now,
+    });
+    if sequence.taken >= sequence.count {
+        info!(
+            "Saved {} screenshots to `{}`",
+            sequence.taken,
+            sequence.dir.display()
+        );
+        commands.remove_resource::<ScreenshotSequence>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{ScreenshotInterval, ScreenshotSequence};
+
+    #[test]
+    fn parses_count_and_interval() {
+        let frames = ScreenshotSequence::parse("100@10f").unwrap();
+        assert_eq!(frames.count, 100);
+        assert_eq!(frames.interval, ScreenshotInterval::Frames(10));
+        let seconds = ScreenshotSequence::parse("5@0.5s").unwrap();
+        assert_eq!(seconds.interval, ScreenshotInterval::Seconds(0.5));
+
+        assert_eq!(ScreenshotSequence::parse("100"), None);
+        assert_eq!(ScreenshotSequence::parse("100@10"), None);
+        assert_eq!(ScreenshotSequence::parse("100@0f"), None);
+    }
+
+    #[test]
+    fn numbers_and_schedules() {
+        let mut sequence = ScreenshotSequence::new(ScreenshotInterval::Frames(3), 100, "out");
+        assert_eq!(sequence.next_path(), PathBuf::from("out/frame_000.png"));
+        assert!(sequence.due(1, 0.0));
+
+        sequence.taken = 42;
+        sequence.last = Some(1.0);
+        assert_eq!(sequence.next_path(), PathBuf::from("out/frame_042.png"));
+        assert!(!sequence.due(3, 0.0));
+        assert!(sequence.due(4, 0.0));
+    }
+}
@@ -0,0 +1,79 @@
+//! A cross on the ship when it's too small on screen to see, zoomed far out.
+
+use bevy::prelude::*;
+
+use crate::{scale_bar::world_length_of_pixels, Spaceship, SHIP_HEIGHT};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ShipMarker {
+    pub enabled: bool,
+    /// Below this height on screen in logical pixels, the marker is drawn this big instead.
+    pub min_pixels: f32,
+    pub color: Color,
+}
+
+impl Default for ShipMarker {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_pixels: 12.0,
+            color: Color::YELLOW,
+        }
+    }
+}
+
+/// The world size of the marker when one pixel covers `pixel_size` in the world, `None` when
+/// the ship itself is at least `min_pixels` high on screen.
+pub fn marker_size(ship_size: f32, pixel_size: f32, min_pixels: f32) -> Option<f32> {
+    (ship_size < pixel_size * min_pixels).then_some(pixel_size * min_pixels)
+}
+
+pub fn draw_ship_marker(
+    marker: Res<ShipMarker>,
+    camera_query: Query<(&Camera, &GlobalTransform, &Projection)>,
+    ship_query: Query<&Transform, With<Spaceship>>,
+    mut gizmos: Gizmos,
+) {
+    if !marker.enabled {
+        return;
+    }
+    let Some((camera, camera_transform, Projection::Perspective(projection))) =
+        camera_query.iter().find(|(camera, _, _)| camera.is_active)
+    else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+
+    for ship in &ship_query {
+        let distance = camera_transform.translation().distance(ship.translation);
+        let pixel_size = world_length_of_pixels(projection.fov, viewport.y, distance, 1.0);
+        let Some(size) = marker_size(SHIP_HEIGHT, pixel_size, marker.min_pixels) else {
+            continue;
+        };
+
+        let half = size / 2.0;
+        let right = camera_transform.right() * half;
+        let up = camera_transform.up() * half;
+        gizmos.line(
+            ship.translation - right,
+            ship.translation + right,
+            marker.color,
+        );
+        gizmos.line(ship.translation - up, ship.translation + up, marker.color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::marker_size;
+
+    #[test]
+    fn only_when_too_small() {
+        // 4 units at 0.1 per pixel are 40 pixels
+        assert_eq!(marker_size(4.0, 0.1, 12.0), None);
+        // at 1 per pixel only 4
+        assert_eq!(marker_size(4.0, 1.0, 12.0), Some(12.0));
+    }
+}
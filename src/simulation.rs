@@ -0,0 +1,97 @@
+//! Everything that makes up the simulated world, without any rendering or UI,
+//! so that it can also run headless.
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    apply_gravity, ascent, atmosphere, audio, autopilot, burn_guard, fire_thrusters, flat_spin,
+    forces::update_external_forces, formation, gamepad, gravity_gradient, health, impulse, input,
+    landing, nan_guard, pause, precision, rails, rcs, reference, rotation, snap, stages,
+};
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+            // We ain't a normal game, we do our own gravity.
+            .insert_resource(RapierConfiguration {
+                gravity: Vec3::ZERO,
+                ..default()
+            })
+            .init_resource::<input::InputBindings>()
+            .init_resource::<gamepad::GamepadInput>()
+            .init_resource::<audio::ThrusterSoundSettings>()
+            .init_resource::<landing::StickyLanding>()
+            .init_resource::<health::DamageConfig>()
+            .init_resource::<nan_guard::NanGuard>()
+            .init_resource::<impulse::ScheduledBurns>()
+            .init_resource::<impulse::BurnProgress>()
+            .init_resource::<precision::OrbitPrecision>()
+            .init_resource::<gravity_gradient::GravityGradient>()
+            .init_resource::<pause::SimulationPaused>()
+            .init_resource::<snap::SnapFrame>()
+            .init_resource::<rotation::RotationPaused>()
+            .init_resource::<ascent::AscentSettings>()
+            .init_resource::<flat_spin::FlatSpin>()
+            .init_resource::<rcs::RcsBalancing>()
+            .init_resource::<burn_guard::BurnGuard>()
+            .init_resource::<reference::ReferenceBody>()
+            .add_event::<impulse::ImpulseBurn>()
+            .add_systems(
+                Update,
+                (
+                    // forces all need to be set before they are combined,
+                    // otherwise the order would depend on the scheduler.
+                    (
+                        fire_thrusters,
+                        apply_gravity,
+                        gravity_gradient::apply_gravity_gradient_torque,
+                        atmosphere::apply_drag,
+                        formation::formation_flight,
+                        autopilot::land_at.after(apply_gravity),
+                        (ascent::start_gravity_turn, ascent::gravity_turn).chain(),
+                        snap::snap_to_cardinal,
+                        flat_spin::flat_spin_assist,
+                    )
+                        .before(update_external_forces),
+                    update_external_forces,
+                    (rcs::install_rcs, burn_guard::check_burn_guard).before(fire_thrusters),
+                    // before gravity, so that it pulls towards where the bodies are now
+                    rails::move_on_rails.before(apply_gravity),
+                    rails::toggle_orbit_freeze.after(rails::move_on_rails),
+                    (rotation::toggle_rotation_pause, rotation::spin_bodies).chain(),
+                    (stages::init_stages, stages::stage_ship).chain(),
+                    rotation::tidal_lock.after(rails::move_on_rails),
+                    impulse::debug_impulse_burn,
+                    impulse::schedule_debug_burn,
+                    impulse::auto_circularize,
+                    impulse::execute_scheduled_burns,
+                    impulse::execute_impulse_burns
+                        .after(impulse::debug_impulse_burn)
+                        .after(impulse::execute_scheduled_burns),
+                    landing::toggle_sticky_landing,
+                    landing::sticky_landing,
+                    landing::liftoff,
+                    (
+                        health::damage_from_g_forces.after(update_external_forces),
+                        health::damage_from_impacts,
+                    )
+                        .before(health::destroy_ship),
+                    health::destroy_ship,
+                    nan_guard::nan_guard.after(update_external_forces),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    precision::add_precise_pose,
+                    precision::integrate_precise_pose,
+                )
+                    .chain()
+                    .after(PhysicsSet::Writeback)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
@@ -0,0 +1,230 @@
+//! Snapping the ship to the nearest orientation aligned at right angles with a reference frame,
+//! to point it exactly along an axis.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    dominant_attractor, forces::ExternalForceSet, hud::ShipStatusText, input::InputBindings,
+    GravityAttractor, Spaceship,
+};
+
+/// The reference frame the ship is snapped in, cycled with the snap frame key.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapFrame {
+    #[default]
+    World,
+    /// Prograde, radial out and orbit normal, around the dominant attractor.
+    Orbital,
+    /// The rotation of the dominant attractor.
+    PlanetLocal,
+}
+
+impl SnapFrame {
+    fn next(self) -> Self {
+        match self {
+            SnapFrame::World => SnapFrame::Orbital,
+            SnapFrame::Orbital => SnapFrame::PlanetLocal,
+            SnapFrame::PlanetLocal => SnapFrame::World,
+        }
+    }
+
+    /// The rotation of the frame for a ship at `pos` moving with `vel`,
+    /// relative to an attractor with `attractor_transform`.
+    fn rotation(self, (pos, vel): (Vec3, Vec3), attractor_transform: &Transform) -> Quat {
+        match self {
+            SnapFrame::World => Quat::IDENTITY,
+            SnapFrame::Orbital => {
+                let radial = (pos - attractor_transform.translation).normalize_or_zero();
+                let normal = radial.cross(vel).try_normalize().unwrap_or(Vec3::Y);
+                let prograde = normal.cross(radial);
+                Quat::from_mat3(&Mat3::from_cols(prograde, radial, normal))
+            }
+            SnapFrame::PlanetLocal => attractor_transform.rotation,
+        }
+    }
+}
+
+/// Rotates the ship towards `local`, an orientation relative to `frame`.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct SnapTo {
+    pub frame: SnapFrame,
+    pub local: Quat,
+}
+
+/// Marks the snap torque in the [`ExternalForceSet`].
+pub struct SnapTorque;
+
+/// Closer than this in radians, the ship counts as aligned.
+pub const SNAP_TOLERANCE: f32 = 0.5 * std::f32::consts::PI / 180.0;
+const SNAP_STIFFNESS: f32 = 2.0;
+const SNAP_DAMPING: f32 = 3.0;
+
+/// The signed unit axis closest to `v`, skipping the axis `skip` (by index).
+fn nearest_axis(v: Vec3, skip: Option<usize>) -> (usize, Vec3) {
+    let (index, component) = v
+        .to_array()
+        .into_iter()
+        .enumerate()
+        .filter(|&(i, _)| Some(i) != skip)
+        .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+        .unwrap();
+
+    let mut axis = Vec3::ZERO;
+    axis[index] = component.signum();
+    (index, axis)
+}
+
+/// The closest orientation to `orientation` that has all its axes along the axes of `frame`,
+/// relative to `frame`.
+pub fn nearest_cardinal(frame: Quat, orientation: Quat) -> Quat {
+    let local = Mat3::from_quat(frame.inverse() * orientation);
+
+    // the thrust axis is the most important one to get right, then the others follow
+    let (y_index, y) = nearest_axis(local.y_axis, None);
+    let (_, x) = nearest_axis(local.x_axis, Some(y_index));
+    Quat::from_mat3(&Mat3::from_cols(x, y, x.cross(y)))
+}
+
+/// The rotation still needed to get from `orientation` to `target`, as an axis scaled by the angle.
+fn remaining_rotation(orientation: Quat, target: Quat) -> Vec3 {
+    let error = target * orientation.inverse();
+    // the short way around
+    let error = if error.w < 0.0 { -error } else { error };
+    error.to_scaled_axis()
+}
+
+pub fn snap_to_cardinal(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut frame: ResMut<SnapFrame>,
+    mut query: Query<
+        (
+            Entity,
+            &Transform,
+            &Velocity,
+            &mut ExternalForceSet,
+            Option<&SnapTo>,
+        ),
+        With<Spaceship>,
+    >,
+    body_query: Query<(&Transform, &GravityAttractor), Without<Spaceship>>,
+) {
+    if keyboard_input.just_pressed(bindings.snap_frame) {
+        *frame = frame.next();
+        info!("Snap frame: {:?}", *frame);
+    }
+
+    let Ok((entity, transform, velocity, mut forces, snap)) = query.get_single_mut() else {
+        return;
+    };
+    let attractor = dominant_attractor(&body_query, transform.translation, |(t, g)| {
+        (t.translation, g.mass)
+    })
+    .map_or(Transform::IDENTITY, |(t, _)| *t);
+    let state = (transform.translation, velocity.linvel);
+
+    let manual_rotation = [
+        bindings.pitch_up,
+        bindings.pitch_down,
+        bindings.roll_left,
+        bindings.roll_right,
+        bindings.yaw_left,
+        bindings.yaw_right,
+    ]
+    .into_iter()
+    .any(|key| keyboard_input.pressed(key));
+
+    let snap = if keyboard_input.just_pressed(bindings.snap) {
+        let frame_rotation = frame.rotation(state, &attractor);
+        let local = nearest_cardinal(frame_rotation, transform.rotation);
+        let remaining = remaining_rotation(transform.rotation, frame_rotation * local);
+        if remaining.length() < SNAP_TOLERANCE {
+            info!("Already aligned in the {:?} frame", *frame);
+            None
+        } else {
+            let snap = SnapTo {
+                frame: *frame,
+                local,
+            };
+            commands.entity(entity).insert(snap);
+            Some(snap)
+        }
+    } else {
+        snap.copied()
+    };
+    let Some(snap) = snap else {
+        return;
+    };
+
+    let target = snap.frame.rotation(state, &attractor) * snap.local;
+    let remaining = remaining_rotation(transform.rotation, target);
+    let settled = remaining.length() < SNAP_TOLERANCE && velocity.angvel.length() < SNAP_TOLERANCE;
+    if settled || manual_rotation {
+        commands.entity(entity).remove::<SnapTo>();
+        forces.set::<SnapTorque>(ExternalForce::default());
+        return;
+    }
+
+    forces.set::<SnapTorque>(ExternalForce {
+        force: Vec3::ZERO,
+        torque: SNAP_STIFFNESS * remaining - SNAP_DAMPING * velocity.angvel,
+    });
+}
+
+pub fn update_snap_text(
+    frame: Res<SnapFrame>,
+    query: Query<(&Transform, &Velocity, Option<&SnapTo>), With<Spaceship>>,
+    body_query: Query<(&Transform, &GravityAttractor), Without<Spaceship>>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+) {
+    let mut text = text_query.single_mut();
+    let Ok((transform, velocity, snap)) = query.get_single() else {
+        return;
+    };
+
+    text.sections[7].value = match snap {
+        None => format!("{:?}", *frame),
+        Some(snap) => {
+            let attractor = dominant_attractor(&body_query, transform.translation, |(t, g)| {
+                (t.translation, g.mass)
+            })
+            .map_or(Transform::IDENTITY, |(t, _)| *t);
+            let target = snap
+                .frame
+                .rotation((transform.translation, velocity.linvel), &attractor)
+                * snap.local;
+            let remaining = remaining_rotation(transform.rotation, target).length();
+            format!("{:.1}° to go ({:?})", remaining.to_degrees(), snap.frame)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{nearest_cardinal, remaining_rotation};
+
+    #[test]
+    fn aligned_stays_aligned() {
+        let frame = Quat::from_rotation_y(0.7);
+        let orientation = frame * Quat::from_rotation_x(std::f32::consts::FRAC_PI_2);
+
+        let local = nearest_cardinal(frame, orientation);
+        assert!(remaining_rotation(orientation, frame * local).length() < 1e-5);
+    }
+
+    #[test]
+    fn snaps_to_nearest_right_angle() {
+        let orientation = Quat::from_rotation_z(0.3) * Quat::from_rotation_x(-0.2);
+        let local = nearest_cardinal(Quat::IDENTITY, orientation);
+        assert!(local.abs_diff_eq(Quat::IDENTITY, 1e-6), "{local}");
+
+        let orientation = Quat::from_rotation_z(1.2);
+        let local = nearest_cardinal(Quat::IDENTITY, orientation);
+        let expected = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        assert!(local.abs_diff_eq(expected, 1e-6), "{local}");
+    }
+}
@@ -0,0 +1,159 @@
+//! A saved copy of the orbit, for comparing it with the orbit after a maneuver.
+
+use std::f64::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    input::InputBindings,
+    orbit::{Orbit, OrbitalElements},
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    units::HudUnits,
+    GravityAttractor, OrbitText, Spaceship,
+};
+
+/// The orbit from when the snapshot key was pressed, around `attractor`.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct OrbitSnapshot {
+    pub attractor: Entity,
+    pub orbit: Orbit,
+    pub elements: OrbitalElements,
+}
+
+/// How many segments the snapshot orbit is drawn with.
+const SNAPSHOT_SEGMENTS: usize = 128;
+/// Open orbits are drawn until they are this many periapses away.
+pub const OPEN_ORBIT_EXTENT: f64 = 10.0;
+
+/// The orbit as a line strip relative to the attractor. Open orbits are cut off
+/// at [`OPEN_ORBIT_EXTENT`].
+pub fn orbit_points(elements: &OrbitalElements) -> Vec<Vec3> {
+    let max_radius = elements.radius_at_true_anomaly(0.0) * OPEN_ORBIT_EXTENT;
+    let closed = elements.eccentricity < 1.0;
+    (0..=SNAPSHOT_SEGMENTS)
+        .map(|i| {
+            // start at apoapsis, so open orbits aren't split where the strip wraps around
+            TAU * i as f64 / SNAPSHOT_SEGMENTS as f64 + std::f64::consts::PI
+        })
+        .filter(|&nu| {
+            let r = elements.radius_at_true_anomaly(nu);
+            closed || (r > 0.0 && r < max_radius)
+        })
+        .map(|nu| elements.position_at_true_anomaly(nu).as_vec3())
+        .collect()
+}
+
+fn ship_orbit(
+    query: &Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: &Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: &BodyVelocities,
+    reference: &ReferenceBody,
+) -> Option<(Entity, Vec3, Orbit, OrbitalElements)> {
+    let (transform, velocity) = query.get_single().ok()?;
+    let (body, body_transform, gravity) =
+        reference_attractor(reference, body_query, transform.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })?;
+    let pos = (transform.translation - body_transform.translation).as_dvec3();
+    let vel = (velocity.linvel - velocities.of(body)).as_dvec3();
+    Some((
+        body,
+        body_transform.translation,
+        Orbit::from_pos_dir_3d(gravity.mass, pos, vel),
+        OrbitalElements::from_state(gravity.mass, pos, vel),
+    ))
+}
+
+pub fn take_orbit_snapshot(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    reference: Res<ReferenceBody>,
+) {
+    if !keyboard_input.just_pressed(bindings.snapshot) {
+        return;
+    }
+    if let Some((attractor, _, orbit, elements)) =
+        ship_orbit(&query, &body_query, &velocities, &reference)
+    {
+        commands.insert_resource(OrbitSnapshot {
+            attractor,
+            orbit,
+            elements,
+        });
+        info!("Saved the orbit: {elements}");
+    }
+}
+
+/// Draws the snapshot in gray and shows how the live orbit differs from it.
+pub fn draw_orbit_snapshot(
+    units: HudUnits,
+    snapshot: Option<Res<OrbitSnapshot>>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    reference: Res<ReferenceBody>,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let Some(snapshot) = snapshot else {
+        text.sections[17].value = "-".to_owned();
+        return;
+    };
+    let Ok((_, attractor, _)) = body_query.get(snapshot.attractor) else {
+        return;
+    };
+
+    let center = attractor.translation;
+    gizmos.linestrip(
+        orbit_points(&snapshot.elements)
+            .into_iter()
+            .map(|p| center + p),
+        Color::rgba(0.6, 0.6, 0.6, 0.6),
+    );
+
+    text.sections[17].value = match ship_orbit(&query, &body_query, &velocities, &reference) {
+        Some((attractor, _, orbit, _)) if attractor == snapshot.attractor => format!(
+            "Ap {} / Pe {} / e {:+.4}",
+            units.distance_change(orbit.apoapsis() - snapshot.orbit.apoapsis()),
+            units.distance_change(orbit.periapsis() - snapshot.orbit.periapsis()),
+            orbit.eccentricity - snapshot.orbit.eccentricity,
+        ),
+        _ => "around another body".to_owned(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{orbit_points, SNAPSHOT_SEGMENTS};
+    use crate::orbit::OrbitalElements;
+
+    #[test]
+    fn closed_orbit_is_a_loop() {
+        let elements: OrbitalElements = "a=2000 e=0.3 i=20 raan=10 argp=40 nu=0".parse().unwrap();
+        let points = orbit_points(&elements);
+
+        assert_eq!(points.len(), SNAPSHOT_SEGMENTS + 1);
+        assert!(points[0].distance(points[SNAPSHOT_SEGMENTS]) < 1e-2);
+        // starts at the apoapsis
+        assert!((points[0].length() - 2600.0).abs() < 1e-2, "{}", points[0]);
+    }
+
+    #[test]
+    fn open_orbit_is_cut_off() {
+        let elements: OrbitalElements = "a=-2000 e=1.5 i=0 raan=0 argp=0 nu=0".parse().unwrap();
+        let periapsis = elements.radius_at_true_anomaly(0.0);
+        let points = orbit_points(&elements);
+
+        assert!(!points.is_empty());
+        assert!(points.len() < SNAPSHOT_SEGMENTS);
+        assert!(points.iter().all(|p| p.length() as f64 <= periapsis * 10.0));
+    }
+}
@@ -0,0 +1,243 @@
+//! Spheres of influence of the bodies on rails, and a countdown to the ship crossing into or
+//! out of one, where the gravity hands off to another body in the patched conic picture.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use glam::DVec3;
+
+use crate::{
+    dominant_attractor,
+    orbit::{Orbit, OrbitalElements},
+    rails::OnRails,
+    GravityAttractor, OrbitText, Spaceship,
+};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SoiCountdown {
+    pub enabled: bool,
+    /// How many points along the orbit are checked for entering the sphere of a moon.
+    pub samples: usize,
+}
+
+impl Default for SoiCountdown {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            samples: 256,
+        }
+    }
+}
+
+/// The radius of the Laplace sphere of influence of a body with `mass`, orbiting a parent
+/// with `parent_mass` at `semi_major_axis`.
+pub fn soi_radius(semi_major_axis: f64, mass: f64, parent_mass: f64) -> f64 {
+    semi_major_axis * (mass / parent_mass).powf(0.4)
+}
+
+/// The true anomaly at which the orbit goes out through the sphere with `radius` around the
+/// attractor. `None` if it always stays inside or outside.
+pub fn exit_true_anomaly(elements: &OrbitalElements, radius: f64) -> Option<f64> {
+    let e = elements.eccentricity;
+    let p = elements.semi_major_axis * (1.0 - e * e);
+    // r = p / (1 + e cos nu), going outwards in the first half of the orbit
+    let cos = (p / radius - 1.0) / e;
+    (e > 0.0 && (-1.0..=1.0).contains(&cos)).then(|| cos.acos())
+}
+
+/// The first time within `horizon` seconds at which the ship comes within `radius` of a body,
+/// with both positions given as a function of the time from now.
+/// The path is sampled at `samples` points, and the crossing found by bisection.
+pub fn entry_time(
+    ship: impl Fn(f64) -> DVec3,
+    body: impl Fn(f64) -> DVec3,
+    radius: f64,
+    horizon: f64,
+    samples: usize,
+) -> Option<f64> {
+    let inside = |t: f64| ship(t).distance(body(t)) < radius;
+    if inside(0.0) {
+        return None;
+    }
+
+    let step = horizon / samples as f64;
+    let end = (1..=samples)
+        .map(|i| i as f64 * step)
+        .find(|&t| inside(t))?;
+    let (mut outside, mut within) = (end - step, end);
+    for _ in 0..40 {
+        let mid = (outside + within) / 2.0;
+        if inside(mid) {
+            within = mid;
+        } else {
+            outside = mid;
+        }
+    }
+    Some(within)
+}
+
+struct Body {
+    entity: Entity,
+    position: Vec3,
+    mass: f64,
+    /// The velocity relative to the parent and the radius of the sphere of influence,
+    /// for bodies on rails.
+    rails: Option<(OnRails, DVec3, f64)>,
+}
+
+/// The next crossing, as the seconds until it and whether it's out of the current sphere.
+fn next_crossing(
+    bodies: &[Body],
+    ship: Vec3,
+    ship_velocity: Vec3,
+    now: f64,
+    samples: usize,
+) -> Option<(f64, bool)> {
+    // the smallest sphere the ship is in, otherwise the body dominating it
+    let current = bodies
+        .iter()
+        .filter_map(|body| {
+            let (_, _, soi) = body.rails.as_ref()?;
+            (body.position.distance(ship) < *soi as f32).then_some((body, *soi))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(body, _)| body)
+        .or_else(|| {
+            let roots = bodies.iter().filter(|body| body.rails.is_none());
+            dominant_attractor(roots, ship, |body| (body.position, body.mass))
+        })?;
+
+    let body_velocity = current.rails.as_ref().map_or(DVec3::ZERO, |&(_, v, _)| v);
+    let elements = OrbitalElements::from_state(
+        current.mass,
+        (ship - current.position).as_dvec3(),
+        ship_velocity.as_dvec3() - body_velocity,
+    );
+
+    let exit = current.rails.as_ref().and_then(|&(_, _, soi)| {
+        let nu = exit_true_anomaly(&elements, soi)?;
+        elements.time_of_flight(current.mass, nu)
+    });
+
+    // the moons move along as well, which only has a cheap answer for closed orbits
+    let closed = elements.semi_major_axis > 0.0 && elements.eccentricity < 1.0;
+    let entry = closed
+        .then(|| {
+            let period = Orbit {
+                semi_major_axis: elements.semi_major_axis,
+                eccentricity: elements.eccentricity,
+            }
+            .period(current.mass);
+            let horizon = exit.map_or(period, |exit| exit.min(period));
+            bodies
+                .iter()
+                .filter_map(|body| {
+                    let (rails, _, soi) = body.rails.as_ref()?;
+                    (rails.parent == current.entity).then_some((rails, *soi))
+                })
+                .filter_map(|(rails, soi)| {
+                    entry_time(
+                        |t| elements.position_at_time(current.mass, t),
+                        |t| rails.state(current.mass, now + t).0,
+                        soi,
+                        horizon,
+                        samples,
+                    )
+                })
+                .min_by(f64::total_cmp)
+        })
+        .flatten();
+
+    match (exit, entry) {
+        (Some(exit), Some(entry)) if entry < exit => Some((entry, false)),
+        (Some(exit), _) => Some((exit, true)),
+        (None, entry) => entry.map(|entry| (entry, false)),
+    }
+}
+
+pub fn update_soi_countdown(
+    countdown: Res<SoiCountdown>,
+    time: Res<Time>,
+    ship_query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<
+        (Entity, &Transform, &GravityAttractor, Option<&OnRails>),
+        Without<Spaceship>,
+    >,
+    mut text_query: Query<&mut Text, With<OrbitText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    if !countdown.enabled {
+        text.sections[19].value = "-".to_owned();
+        return;
+    }
+    let Ok((ship, velocity)) = ship_query.get_single() else {
+        return;
+    };
+
+    let now = time.elapsed_seconds_f64();
+    let bodies: Vec<_> = body_query
+        .iter()
+        .map(|(entity, transform, gravity, rails)| Body {
+            entity,
+            position: transform.translation,
+            mass: gravity.mass,
+            rails: rails.and_then(|rails| {
+                let (_, _, parent_gravity, _) = body_query.get(rails.parent).ok()?;
+                let soi = soi_radius(
+                    rails.orbit.semi_major_axis,
+                    gravity.mass,
+                    parent_gravity.mass,
+                );
+                let (_, v) = rails.state(parent_gravity.mass, now);
+                Some((*rails, v, soi))
+            }),
+        })
+        .collect();
+
+    text.sections[19].value = match next_crossing(
+        &bodies,
+        ship.translation,
+        velocity.linvel,
+        now,
+        countdown.samples,
+    ) {
+        Some((t, true)) => format!("leaving in {t:.1} s"),
+        Some((t, false)) => format!("entering in {t:.1} s"),
+        None => "N/A".to_owned(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec3;
+
+    use super::{entry_time, exit_true_anomaly};
+    use crate::orbit::OrbitalElements;
+
+    #[test]
+    fn exits_where_the_radius_is_reached() {
+        let elements: OrbitalElements = "a=1000 e=0.5 i=0 raan=0 argp=0 nu=0".parse().unwrap();
+        let nu = exit_true_anomaly(&elements, 1200.0).unwrap();
+        assert!(nu > 0.0 && nu < std::f64::consts::PI, "{nu}");
+        assert!((elements.radius_at_true_anomaly(nu) - 1200.0).abs() < 1e-6);
+
+        // the apoapsis at 1500 is still inside
+        assert_eq!(exit_true_anomaly(&elements, 2000.0), None);
+        let circular: OrbitalElements = "a=1000 e=0 i=0 raan=0 argp=0 nu=0".parse().unwrap();
+        assert_eq!(exit_true_anomaly(&circular, 1200.0), None);
+    }
+
+    #[test]
+    fn finds_the_entry() {
+        // flying along x towards a body at x = 100 with a sphere of 10
+        let ship = |t: f64| DVec3::new(t, 0.0, 0.0);
+        let body = |_| DVec3::new(100.0, 0.0, 0.0);
+        let t = entry_time(ship, body, 10.0, 200.0, 16).unwrap();
+        assert!((t - 90.0).abs() < 1e-6, "{t}");
+
+        assert_eq!(entry_time(ship, body, 10.0, 50.0, 16), None);
+        // already inside
+        assert_eq!(entry_time(ship, body, 200.0, 200.0, 16), None);
+    }
+}
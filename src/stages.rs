@@ -0,0 +1,192 @@
+//! Rockets made of stages, each with its own engine, that are jettisoned once they are spent.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{forces::ExternalForceSet, input::InputBindings, Spaceship, Thrusters, SHIP_HEIGHT};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stage {
+    pub dry_mass: f32,
+    pub fuel: f32,
+    /// The strength of the thrusters while this stage is active.
+    pub thrust: f32,
+}
+
+impl Stage {
+    pub fn mass(&self) -> f32 {
+        self.dry_mass + self.fuel
+    }
+}
+
+/// The stages of the rocket from the top down, so the last one is the active one.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Stages(pub Vec<Stage>);
+
+impl Stages {
+    pub fn current(&self) -> Option<&Stage> {
+        self.0.last()
+    }
+
+    /// The mass of all stages that are still attached.
+    pub fn mass(&self) -> f32 {
+        self.0.iter().map(Stage::mass).sum()
+    }
+
+    /// Removes the active stage if there's another one to take over, returning it.
+    pub fn stage(&mut self) -> Option<Stage> {
+        if self.0.len() < 2 {
+            return None;
+        }
+        self.0.pop()
+    }
+}
+
+/// Changes the mass of a body to `mass`, keeping its shape.
+/// Rapier only fills in [`ReadMassProperties`] when the collider is created,
+/// so it's updated here as well.
+fn set_mass(commands: &mut Commands, entity: Entity, read: &mut ReadMassProperties, mass: f32) {
+    commands
+        .entity(entity)
+        .insert(ColliderMassProperties::Mass(mass));
+
+    let props = &mut read.0;
+    if props.mass > 0.0 {
+        props.principal_inertia *= mass / props.mass;
+        props.mass = mass;
+    }
+}
+
+/// Gives the ship the mass and thrust of its stages.
+pub fn init_stages(
+    mut commands: Commands,
+    mut query: Query<(Entity, &Stages, &mut Thrusters, &mut ReadMassProperties), Added<Stages>>,
+) {
+    for (entity, stages, mut thrusters, mut read) in &mut query {
+        if let Some(stage) = stages.current() {
+            thrusters.strength = stage.thrust;
+        }
+        set_mass(&mut commands, entity, &mut read, stages.mass());
+    }
+}
+
+/// The stage key, G by default, jettisons the active stage, leaving it behind as debris.
+pub fn stage_ship(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Stages,
+            &mut Thrusters,
+            &mut ReadMassProperties,
+            &Transform,
+            &Velocity,
+        ),
+        With<Spaceship>,
+    >,
+) {
+    if !keyboard_input.just_pressed(bindings.stage) {
+        return;
+    }
+    let Ok((entity, mut stages, mut thrusters, mut read, transform, velocity)) =
+        query.get_single_mut()
+    else {
+        return;
+    };
+    let Some(jettisoned) = stages.stage() else {
+        info!("No stage left to jettison");
+        return;
+    };
+
+    let next = stages.current().unwrap();
+    thrusters.strength = next.thrust;
+    set_mass(&mut commands, entity, &mut read, stages.mass());
+    info!(
+        "Staged, {} stages left with a mass of {}",
+        stages.0.len(),
+        stages.mass()
+    );
+
+    // below the engine, so that it doesn't get stuck in the ship
+    let size = Vec3::new(0.6, 1.0, 0.6);
+    let offset = transform.rotation * Vec3::new(0.0, -(SHIP_HEIGHT + size.y) / 2.0 - 0.1, 0.0);
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Box::new(size.x, size.y, size.z))),
+            material: materials.add(Color::rgb(0.5, 0.5, 0.5).into()),
+            transform: transform.with_translation(transform.translation + offset),
+            ..default()
+        },
+        RigidBody::Dynamic,
+        Collider::cuboid(size.x / 2.0, size.y / 2.0, size.z / 2.0),
+        ColliderMassProperties::Mass(jettisoned.mass()),
+        ReadMassProperties::default(),
+        *velocity,
+        ExternalForce::default(),
+        ExternalForceSet::default(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::*;
+
+    use super::{Stage, Stages};
+    use crate::{
+        headless::{self, KeyHold},
+        scenario::Scenario,
+        Spaceship, Thrusters,
+    };
+
+    #[test]
+    fn staging_drops_the_active_stage() {
+        let upper = Stage {
+            dry_mass: 0.5,
+            fuel: 0.5,
+            thrust: 1.0,
+        };
+        let booster = Stage {
+            dry_mass: 1.0,
+            fuel: 2.0,
+            thrust: 4.0,
+        };
+        let mut stages = Stages(vec![upper, booster]);
+        assert_eq!(stages.mass(), 4.0);
+
+        assert_eq!(stages.stage(), Some(booster));
+        assert_eq!(stages.mass(), 1.0);
+        assert_eq!(stages.current(), Some(&upper));
+        // the last stage stays
+        assert_eq!(stages.stage(), None);
+    }
+
+    #[test]
+    fn staging_updates_mass_and_thrust() {
+        let mut app = headless::headless_app(Scenario::Rocket);
+        let ship_state = |app: &mut App| {
+            let (thrusters, mass) = app
+                .world
+                .query_filtered::<(&Thrusters, &ReadMassProperties), With<Spaceship>>()
+                .single(&app.world);
+            (thrusters.strength, mass.0.mass)
+        };
+
+        headless::run_app(&mut app, &[], 2);
+        let (thrust, mass) = ship_state(&mut app);
+
+        let stage = KeyHold {
+            key: KeyCode::G,
+            ticks: 0..1,
+        };
+        headless::run_app(&mut app, &[stage], 2);
+        let (staged_thrust, staged_mass) = ship_state(&mut app);
+
+        assert!(staged_mass < mass, "{staged_mass} < {mass}");
+        assert_eq!((thrust, staged_thrust), (4.0, 1.0));
+    }
+}
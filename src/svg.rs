@@ -0,0 +1,173 @@
+//! Exporting the ship's orbit as an SVG diagram in its orbital plane.
+
+use std::{f64::consts::TAU, fmt::Write as _, path::Path};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+use glam::DVec2;
+
+use crate::{
+    input::InputBindings,
+    orbit::OrbitalElements,
+    reference::{reference_attractor, BodyVelocities, ReferenceBody},
+    snapshot::OPEN_ORBIT_EXTENT,
+    GravityAttractor, Planet, Spaceship,
+};
+
+/// Where the key writes the diagram to.
+pub const SVG_PATH: &str = "orbit.svg";
+
+/// The polyline has this many points per turn of the true anomaly. More than the lines on
+/// screen, as the file can be zoomed into.
+const SVG_SEGMENTS: usize = 256;
+
+/// A point of the orbit in the perifocal frame, with the periapsis along +X.
+fn perifocal(elements: &OrbitalElements, nu: f64) -> DVec2 {
+    DVec2::from_angle(nu) * elements.radius_at_true_anomaly(nu)
+}
+
+/// The orbit around a planet of `planet_radius` as an SVG document, seen from above the
+/// orbital plane, with the periapsis to the right. The ship's position is the dot, the
+/// periapsis and the apoapsis are marked in red and blue.
+pub fn orbit_svg(elements: &OrbitalElements, planet_radius: f64) -> String {
+    let closed = elements.eccentricity < 1.0;
+    let max_radius = elements.radius_at_true_anomaly(0.0) * OPEN_ORBIT_EXTENT;
+    let points: Vec<DVec2> = (0..=SVG_SEGMENTS)
+        .map(|i| TAU * i as f64 / SVG_SEGMENTS as f64 - TAU / 2.0)
+        .filter(|&nu| {
+            let r = elements.radius_at_true_anomaly(nu);
+            closed || (r > 0.0 && r < max_radius)
+        })
+        .map(|nu| perifocal(elements, nu))
+        .collect();
+
+    let (min, max) = points.iter().fold(
+        (DVec2::splat(-planet_radius), DVec2::splat(planet_radius)),
+        |(min, max), &p| (min.min(p), max.max(p)),
+    );
+    let margin = (max - min).max_element() * 0.05;
+    let (min, size) = (min - margin, max - min + 2.0 * margin);
+    // SVG has y pointing down
+    let flip = |p: DVec2| DVec2::new(p.x, -p.y);
+    let stroke = size.max_element() / 400.0;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min.x,
+        -max.y - margin,
+        size.x,
+        size.y
+    );
+    let _ = writeln!(
+        svg,
+        r##"<circle cx="0" cy="0" r="{planet_radius}" fill="#888"/>"##
+    );
+    let path: Vec<String> = points
+        .iter()
+        .map(|&p| {
+            let p = flip(p);
+            format!("{},{}", p.x, p.y)
+        })
+        .collect();
+    let _ = writeln!(
+        svg,
+        r#"<polyline points="{}" fill="none" stroke="black" stroke-width="{stroke}"/>"#,
+        path.join(" ")
+    );
+
+    let mut marker = |p: DVec2, color: &str| {
+        let p = flip(p);
+        let _ = writeln!(
+            svg,
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{color}"/>"#,
+            p.x,
+            p.y,
+            stroke * 4.0
+        );
+    };
+    marker(perifocal(elements, 0.0), "red");
+    if closed {
+        marker(perifocal(elements, TAU / 2.0), "blue");
+    }
+    marker(perifocal(elements, elements.true_anomaly), "green");
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn export_orbit_svg(
+    elements: &OrbitalElements,
+    planet_radius: f64,
+    path: &Path,
+) -> std::io::Result<()> {
+    std::fs::write(path, orbit_svg(elements, planet_radius))
+}
+
+pub fn export_orbit_svg_on_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    reference: Res<ReferenceBody>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor, &Planet), Without<Spaceship>>,
+    velocities: BodyVelocities,
+) {
+    if !keyboard_input.just_pressed(bindings.export_svg) {
+        return;
+    }
+    let Ok((transform, velocity)) = query.get_single() else {
+        return;
+    };
+    let Some((body, body_transform, gravity, planet)) = reference_attractor(
+        &reference,
+        &body_query,
+        transform.translation,
+        |(_, t, g, _)| (t.translation, g.mass),
+    ) else {
+        return;
+    };
+
+    let elements = OrbitalElements::from_state(
+        gravity.mass,
+        (transform.translation - body_transform.translation).as_dvec3(),
+        (velocity.linvel - velocities.of(body)).as_dvec3(),
+    );
+    let path = Path::new(SVG_PATH);
+    match export_orbit_svg(&elements, planet.radius, path) {
+        Ok(()) => info!("Saved the orbit to {}", path.display()),
+        Err(err) => error!("Can't save the orbit to {}: {err}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::orbit_svg;
+    use crate::orbit::OrbitalElements;
+
+    #[test]
+    fn closed_orbit_diagram() {
+        let elements: OrbitalElements = "a=2000 e=0.5 i=30 raan=0 argp=0 nu=90".parse().unwrap();
+        let svg = orbit_svg(&elements, 800.0);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(r##"<circle cx="0" cy="0" r="800" fill="#888"/>"##));
+        assert!(svg.contains("<polyline"));
+        // periapsis, apoapsis and ship
+        assert_eq!(svg.matches("<circle").count(), 4);
+        // fits the apoapsis at -3000
+        let view_box = svg.split("viewBox=\"").nth(1).unwrap();
+        let x: f64 = view_box.split(' ').next().unwrap().parse().unwrap();
+        assert!(x < -3000.0, "{x}");
+    }
+
+    #[test]
+    fn open_orbit_has_no_apoapsis() {
+        let elements: OrbitalElements = "a=-2000 e=1.5 i=0 raan=0 argp=0 nu=0".parse().unwrap();
+        let svg = orbit_svg(&elements, 800.0);
+
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert!(!svg.contains("NaN") && !svg.contains("inf"));
+    }
+}
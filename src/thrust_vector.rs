@@ -0,0 +1,122 @@
+//! The direction of the thrust next to the prograde and retrograde markers, with the angle
+//! between thrust and prograde, to see how well a burn is aligned, and where it is turning to.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{forces::ExternalForceSet, hud::ShipStatusText, Spaceship, ThrusterForce, Thrusters};
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ThrustVector {
+    pub enabled: bool,
+    /// Length of the drawn rays.
+    pub length: f32,
+    /// Where the thrust axis will point this many seconds ahead at the current rotation is
+    /// shown as a ghost marker, to see the overshoot coming. 0 turns it off.
+    pub lead_time: f32,
+}
+
+impl Default for ThrustVector {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            length: 5.0,
+            lead_time: 1.0,
+        }
+    }
+}
+
+/// The direction of `local_axis` after turning with `angvel` from `rotation` for `lead_time`
+/// seconds. The angular velocity is in the world frame, like the one from Rapier.
+pub fn lead_direction(rotation: Quat, angvel: Vec3, lead_time: f32, local_axis: Vec3) -> Vec3 {
+    Quat::from_scaled_axis(angvel * lead_time) * rotation * local_axis
+}
+
+/// The angle between the thrust and the velocity in degrees, 0 for a prograde burn and 180
+/// for a retrograde one. `None` while not thrusting or not moving.
+pub fn thrust_angle(thrust: Vec3, velocity: Vec3) -> Option<f32> {
+    let thrust = thrust.try_normalize()?;
+    let prograde = velocity.try_normalize()?;
+    Some(thrust.dot(prograde).clamp(-1.0, 1.0).acos().to_degrees())
+}
+
+pub fn draw_thrust_vector(
+    config: Res<ThrustVector>,
+    query: Query<(&Transform, &Velocity, &ExternalForceSet, &Thrusters), With<Spaceship>>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+    mut gizmos: Gizmos,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let (true, Ok((transform, velocity, forces, thrusters))) = (config.enabled, query.get_single())
+    else {
+        text.sections[11].value = "-".to_owned();
+        return;
+    };
+
+    let ship = transform.translation;
+    let thrust = forces.get::<ThrusterForce>().force;
+    if let Some(prograde) = velocity.linvel.try_normalize() {
+        gizmos.ray(ship, prograde * config.length, Color::LIME_GREEN);
+        gizmos.ray(ship, -prograde * config.length, Color::rgb(0.5, 0.8, 0.5));
+    }
+    if let Some(direction) = thrust.try_normalize() {
+        gizmos.ray(ship, direction * config.length * 1.2, Color::ORANGE);
+    }
+
+    // where the engine will point, whether it's firing or not
+    let axis = thrusters.local_thrust().try_normalize().unwrap_or(Vec3::Y);
+    if config.lead_time > 0.0 && velocity.angvel != Vec3::ZERO {
+        let length = config.length * 1.2;
+        let now = transform.rotation * axis * length;
+        let lead = lead_direction(transform.rotation, velocity.angvel, config.lead_time, axis);
+        let ghost = Color::ORANGE.with_a(0.4);
+        gizmos.line(ship + now, ship + lead * length, ghost);
+        gizmos.circle(ship + lead * length, lead, config.length * 0.1, ghost);
+    }
+
+    text.sections[11].value = match thrust_angle(thrust, velocity.linvel) {
+        Some(angle) => format!("{angle:.1}° off prograde"),
+        None => "-".to_owned(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{lead_direction, thrust_angle};
+
+    #[test]
+    fn angle_to_prograde() {
+        let velocity = Vec3::new(0.0, 0.0, 3.0);
+        assert_eq!(thrust_angle(Vec3::new(0.0, 0.0, 0.5), velocity), Some(0.0));
+        assert_eq!(
+            thrust_angle(Vec3::new(0.0, 0.0, -0.5), velocity),
+            Some(180.0)
+        );
+
+        let angle = thrust_angle(Vec3::new(1.0, 0.0, 1.0), velocity).unwrap();
+        assert!((angle - 45.0).abs() < 1e-4, "{angle}");
+
+        assert_eq!(thrust_angle(Vec3::ZERO, velocity), None);
+        assert_eq!(thrust_angle(Vec3::X, Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn leads_by_the_rotation() {
+        // a quarter turn per second around Z
+        let angvel = Vec3::new(0.0, 0.0, std::f32::consts::FRAC_PI_2);
+        let lead = lead_direction(Quat::IDENTITY, angvel, 1.0, Vec3::Y);
+        assert!(lead.distance(-Vec3::X) < 1e-5, "{lead}");
+
+        let tilted = Quat::from_rotation_x(0.3);
+        assert_eq!(
+            lead_direction(tilted, Vec3::ZERO, 1.0, Vec3::Y),
+            tilted * Vec3::Y
+        );
+        let lead = lead_direction(tilted, angvel, 0.0, Vec3::Y);
+        assert!(lead.distance(tilted * Vec3::Y) < 1e-6, "{lead}");
+    }
+}
@@ -0,0 +1,380 @@
+//! Scripted events at fixed points of the simulation time, for demos and tutorials that play
+//! out the same way every time.
+//!
+//! The file format is plain text:
+//!
+//! ```text
+//! spaceflight timeline 1
+//! 10 burn prograde 50
+//! 25 message Now circularize
+//! 30 spawn 20 0 -20
+//! 40 camera 0 30000 60000 0 0 0
+//! 60 camera follow
+//! ```
+//!
+//! After the version, every line is a time in seconds followed by an action. Burns are along
+//! `prograde`, `retrograde`, `normal`, `antinormal`, `radial` or `antiradial`, spawns put a
+//! target ship at an offset from the ship moving along with it, and the camera is either
+//! fixed at a position looking at a point or follows the ship again. Empty lines and lines
+//! starting with `#` are skipped.
+
+use std::{fmt, path::PathBuf};
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    camera::{CameraMode, FixedCamera},
+    dominant_attractor,
+    hud::WarningText,
+    impulse::ImpulseBurn,
+    spawn_spaceship, GravityAttractor, Spaceship, SpaceshipBundle,
+};
+
+pub const TIMELINE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BurnDirection {
+    Prograde,
+    Retrograde,
+    Normal,
+    Antinormal,
+    Radial,
+    Antiradial,
+}
+
+impl BurnDirection {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "prograde" => BurnDirection::Prograde,
+            "retrograde" => BurnDirection::Retrograde,
+            "normal" => BurnDirection::Normal,
+            "antinormal" => BurnDirection::Antinormal,
+            "radial" => BurnDirection::Radial,
+            "antiradial" => BurnDirection::Antiradial,
+            _ => return None,
+        })
+    }
+
+    /// The direction for a ship at `pos` relative to the attractor, moving with `vel`.
+    pub fn direction(self, pos: Vec3, vel: Vec3) -> Vec3 {
+        let prograde = vel.normalize_or_zero();
+        let normal = pos.cross(vel).normalize_or_zero();
+        let radial = prograde.cross(normal);
+        match self {
+            BurnDirection::Prograde => prograde,
+            BurnDirection::Retrograde => -prograde,
+            BurnDirection::Normal => normal,
+            BurnDirection::Antinormal => -normal,
+            BurnDirection::Radial => radial,
+            BurnDirection::Antiradial => -radial,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimelineAction {
+    Burn(BurnDirection, f32),
+    /// A target ship at this offset from the ship.
+    Spawn(Vec3),
+    Camera(CameraMode),
+    Message(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimelineError {
+    NotATimeline,
+    UnsupportedVersion(String),
+    /// The line number, counting from 1, and the line.
+    InvalidLine(usize, String),
+}
+
+impl fmt::Display for TimelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelineError::NotATimeline => write!(f, "not a timeline file"),
+            TimelineError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported timeline version {version}, only {TIMELINE_VERSION} is supported"
+            ),
+            TimelineError::InvalidLine(number, line) => {
+                write!(f, "invalid line {number} `{line}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TimelineError {}
+
+/// The actions by the simulation time they are due at, and how far it got through them.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct Timeline {
+    pub actions: Vec<(f64, TimelineAction)>,
+    next: usize,
+    /// The last message and when it was shown.
+    message: Option<(String, f64)>,
+    /// How long a message stays on the HUD, in seconds.
+    pub message_duration: f64,
+}
+
+impl Timeline {
+    pub fn new(mut actions: Vec<(f64, TimelineAction)>) -> Self {
+        // stable, so actions at the same time stay in the order they were written
+        actions.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Timeline {
+            actions,
+            next: 0,
+            message: None,
+            message_duration: 5.0,
+        }
+    }
+
+    /// The actions that became due up to `now`, each only once.
+    pub fn due(&mut self, now: f64) -> &[(f64, TimelineAction)] {
+        let start = self.next;
+        self.next += self.actions[start..].partition_point(|&(at, _)| at <= now);
+        &self.actions[start..self.next]
+    }
+
+    /// `--timeline=<path>` plays a timeline from the start.
+    pub fn from_args() -> Option<Self> {
+        let path =
+            std::env::args().find_map(|arg| arg.strip_prefix("--timeline=").map(PathBuf::from))?;
+        let timeline = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|s| s.parse::<Timeline>().map_err(|err| err.to_string()));
+        match timeline {
+            Ok(timeline) => Some(timeline),
+            Err(err) => {
+                error!("Can't play timeline {}: {err}", path.display());
+                None
+            }
+        }
+    }
+}
+
+fn parse_vec3(parts: &[&str]) -> Option<Vec3> {
+    let [x, y, z] = parts else {
+        return None;
+    };
+    Some(Vec3::new(x.parse().ok()?, y.parse().ok()?, z.parse().ok()?))
+}
+
+fn parse_action(line: &str) -> Option<(f64, TimelineAction)> {
+    let (at, rest) = line.split_once(char::is_whitespace)?;
+    let at: f64 = at.parse().ok().filter(|at: &f64| at.is_finite())?;
+    let (action, args) = rest.trim().split_once(' ').unwrap_or((rest.trim(), ""));
+    let parts: Vec<&str> = args.split_whitespace().collect();
+
+    let action = match (action, parts.as_slice()) {
+        ("burn", [direction, dv]) => TimelineAction::Burn(
+            BurnDirection::from_name(direction)?,
+            dv.parse().ok().filter(|dv: &f32| dv.is_finite())?,
+        ),
+        ("spawn", offset) => TimelineAction::Spawn(parse_vec3(offset)?),
+        ("camera", ["follow"]) => TimelineAction::Camera(CameraMode::Follow),
+        ("camera", pose) if pose.len() == 6 => {
+            TimelineAction::Camera(CameraMode::Fixed(FixedCamera {
+                position: parse_vec3(&pose[..3])?,
+                look_at: parse_vec3(&pose[3..])?,
+            }))
+        }
+        ("message", _) if !args.trim().is_empty() => {
+            TimelineAction::Message(args.trim().to_owned())
+        }
+        _ => return None,
+    };
+    Some((at, action))
+}
+
+impl std::str::FromStr for Timeline {
+    type Err = TimelineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let version = lines
+            .next()
+            .and_then(|(_, line)| line.strip_prefix("spaceflight timeline "))
+            .ok_or(TimelineError::NotATimeline)?;
+        if version != TIMELINE_VERSION.to_string() {
+            return Err(TimelineError::UnsupportedVersion(version.to_owned()));
+        }
+
+        let actions = lines
+            .map(|(number, line)| {
+                parse_action(line)
+                    .ok_or_else(|| TimelineError::InvalidLine(number, line.to_owned()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Timeline::new(actions))
+    }
+}
+
+pub fn run_timeline(
+    mut commands: Commands,
+    time: Res<Time>,
+    timeline: Option<ResMut<Timeline>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut camera_mode: ResMut<CameraMode>,
+    mut burns: EventWriter<ImpulseBurn>,
+    ship_query: Query<(&Transform, &Velocity), With<Spaceship>>,
+    body_query: Query<(&Transform, &GravityAttractor), Without<Spaceship>>,
+    mut text_query: Query<&mut Text, With<WarningText>>,
+) {
+    let Some(mut timeline) = timeline else {
+        return;
+    };
+    let now = time.elapsed_seconds_f64();
+
+    let mut message = None;
+    let ship = ship_query.get_single().ok();
+    for (at, action) in timeline.due(now).to_vec() {
+        info!("Timeline at {at}s: {action:?}");
+        match action {
+            TimelineAction::Burn(direction, dv) => {
+                let Some((transform, velocity)) = ship else {
+                    continue;
+                };
+                let pos = transform.translation;
+                let attractor =
+                    dominant_attractor(&body_query, pos, |(t, g)| (t.translation, g.mass))
+                        .map_or(Vec3::ZERO, |(t, _)| t.translation);
+                burns.send(ImpulseBurn {
+                    dv: direction.direction(pos - attractor, velocity.linvel) * dv,
+                });
+            }
+            TimelineAction::Spawn(offset) => {
+                let Some((transform, velocity)) = ship else {
+                    continue;
+                };
+                let mut target = SpaceshipBundle::new(
+                    &mut meshes,
+                    &mut materials,
+                    transform.translation + offset,
+                    Vec3::ZERO,
+                );
+                target.vel.linvel = velocity.linvel;
+                let target = spawn_spaceship(&mut commands, &mut meshes, &mut materials, target);
+                commands.entity(target).remove::<Spaceship>();
+            }
+            TimelineAction::Camera(mode) => *camera_mode = mode,
+            TimelineAction::Message(text) => message = Some(text),
+        }
+    }
+    if let Some(text) = message {
+        timeline.message = Some((text, now));
+    }
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[7].value = match &timeline.message {
+            Some((message, at)) if now - at < timeline.message_duration => message.clone(),
+            _ => String::new(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{BurnDirection, Timeline, TimelineAction, TimelineError};
+    use crate::camera::{CameraMode, FixedCamera};
+
+    #[test]
+    fn parses_every_action() {
+        let timeline: Timeline = "spaceflight timeline 1
+            # comments and empty lines are skipped
+
+            30 spawn 20 0 -20
+            10 burn prograde 50
+            25 message Now   circularize
+            40 camera 0 30000 60000 0 0 0
+            60 camera follow
+            "
+        .parse()
+        .unwrap();
+
+        let actions: Vec<_> = timeline.actions.iter().map(|(at, _)| *at).collect();
+        assert_eq!(actions, [10.0, 25.0, 30.0, 40.0, 60.0]);
+        assert_eq!(
+            timeline.actions[0].1,
+            TimelineAction::Burn(BurnDirection::Prograde, 50.0)
+        );
+        assert_eq!(
+            timeline.actions[1].1,
+            TimelineAction::Message("Now   circularize".to_owned())
+        );
+        assert_eq!(
+            timeline.actions[2].1,
+            TimelineAction::Spawn(Vec3::new(20.0, 0.0, -20.0))
+        );
+        assert_eq!(
+            timeline.actions[3].1,
+            TimelineAction::Camera(CameraMode::Fixed(FixedCamera {
+                position: Vec3::new(0.0, 30000.0, 60000.0),
+                look_at: Vec3::ZERO,
+            }))
+        );
+        assert_eq!(
+            timeline.actions[4].1,
+            TimelineAction::Camera(CameraMode::Follow)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(
+            "10 burn prograde 50".parse::<Timeline>(),
+            Err(TimelineError::NotATimeline)
+        );
+        assert_eq!(
+            "spaceflight timeline 2".parse::<Timeline>(),
+            Err(TimelineError::UnsupportedVersion("2".to_owned()))
+        );
+        for line in [
+            "10 burn sideways 50",
+            "10 burn prograde",
+            "ten burn prograde 50",
+            "10 spawn 1 2",
+            "10 camera 1 2 3",
+            "10 message",
+            "10 explode",
+        ] {
+            let timeline = format!("spaceflight timeline 1\n{line}");
+            assert_eq!(
+                timeline.parse::<Timeline>(),
+                Err(TimelineError::InvalidLine(2, line.to_owned())),
+            );
+        }
+    }
+
+    #[test]
+    fn actions_are_due_once() {
+        let mut timeline = Timeline::new(vec![
+            (1.0, TimelineAction::Message("a".to_owned())),
+            (1.0, TimelineAction::Message("b".to_owned())),
+            (3.0, TimelineAction::Message("c".to_owned())),
+        ]);
+        assert!(timeline.due(0.5).is_empty());
+        assert_eq!(timeline.due(2.0).len(), 2);
+        assert!(timeline.due(2.5).is_empty());
+        // warped past everything at once
+        assert_eq!(timeline.due(100.0).len(), 1);
+        assert!(timeline.due(200.0).is_empty());
+    }
+
+    #[test]
+    fn burn_directions() {
+        let pos = Vec3::new(10.0, 0.0, 0.0);
+        let vel = Vec3::new(0.0, 0.0, -2.0);
+        assert_eq!(BurnDirection::Prograde.direction(pos, vel), -Vec3::Z);
+        assert_eq!(BurnDirection::Radial.direction(pos, vel), Vec3::X);
+        assert_eq!(BurnDirection::Normal.direction(pos, vel), Vec3::Y);
+    }
+}
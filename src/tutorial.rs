@@ -0,0 +1,217 @@
+//! A guided tutorial through the basic maneuvers, with `--tutorial`. Best played in the
+//! `circular` scenario, which starts in a low circular orbit.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Velocity;
+
+use crate::{
+    dominant_attractor,
+    forces::ExternalForceSet,
+    hud::{self, HudPanel},
+    input::InputBindings,
+    orbit::Orbit,
+    reference::BodyVelocities,
+    GravityAttractor, Spaceship, ThrusterForce,
+};
+
+/// What the steps of the tutorial can check to see if they are done.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TutorialContext {
+    pub thrusting: bool,
+    /// The cosine of the angle between the nose of the ship and its velocity,
+    /// 1 when pointing prograde.
+    pub prograde_alignment: f32,
+    pub eccentricity: f64,
+}
+
+pub struct TutorialStep {
+    /// The prompt, naming the keys of the bindings.
+    pub prompt: fn(&InputBindings) -> String,
+    pub done: fn(&TutorialContext) -> bool,
+}
+
+#[derive(Resource)]
+pub struct Tutorial {
+    pub steps: Vec<TutorialStep>,
+    /// The index of the step that is shown, `steps.len()` once all are done.
+    pub current: usize,
+}
+
+impl Tutorial {
+    /// Thrust, turn prograde, raise the apoapsis and circularize again.
+    pub fn basics() -> Self {
+        Tutorial {
+            steps: vec![
+                TutorialStep {
+                    prompt: |keys| format!("Hold {:?} to fire the engine", keys.thrust),
+                    done: |ctx| ctx.thrusting,
+                },
+                TutorialStep {
+                    prompt: |keys| {
+                        format!(
+                            "Turn the nose prograde, along the velocity, with {:?}/{:?} and {:?}/{:?}",
+                            keys.pitch_up, keys.pitch_down, keys.yaw_left, keys.yaw_right
+                        )
+                    },
+                    done: |ctx| ctx.prograde_alignment > 0.95,
+                },
+                TutorialStep {
+                    prompt: |_| "Thrust prograde to raise the far side of the orbit".to_owned(),
+                    done: |ctx| ctx.eccentricity > 0.05,
+                },
+                TutorialStep {
+                    prompt: |keys| {
+                        format!(
+                            "Press {:?} to circularize at the apoapsis",
+                            keys.circularize
+                        )
+                    },
+                    done: |ctx| ctx.eccentricity < 0.01,
+                },
+            ],
+            current: 0,
+        }
+    }
+
+    pub fn from_args() -> Option<Self> {
+        std::env::args()
+            .any(|arg| arg == "--tutorial")
+            .then(Tutorial::basics)
+    }
+
+    pub fn prompt(&self, bindings: &InputBindings) -> String {
+        self.steps
+            .get(self.current)
+            .map_or("Done! Fly safe.".to_owned(), |step| (step.prompt)(bindings))
+    }
+
+    /// Moves on to the next step if the current one is done, returning whether it did.
+    pub fn advance(&mut self, ctx: &TutorialContext) -> bool {
+        let done = self
+            .steps
+            .get(self.current)
+            .is_some_and(|step| (step.done)(ctx));
+        if done {
+            self.current += 1;
+        }
+        done
+    }
+}
+
+#[derive(Component)]
+pub struct TutorialText;
+
+pub fn spawn_tutorial_panel(mut commands: Commands, tutorial: Option<Res<Tutorial>>) {
+    if tutorial.is_some() {
+        hud::spawn_panel(
+            &mut commands,
+            HudPanel::Tutorial,
+            &["Objective"],
+            TutorialText,
+        );
+    }
+}
+
+pub fn update_tutorial(
+    tutorial: Option<ResMut<Tutorial>>,
+    bindings: Res<InputBindings>,
+    query: Query<(&Transform, &Velocity, &ExternalForceSet), With<Spaceship>>,
+    body_query: Query<(Entity, &Transform, &GravityAttractor), Without<Spaceship>>,
+    velocities: BodyVelocities,
+    mut text_query: Query<&mut Text, With<TutorialText>>,
+) {
+    let (Some(mut tutorial), Ok(mut text)) = (tutorial, text_query.get_single_mut()) else {
+        return;
+    };
+    let Ok((transform, velocity, forces)) = query.get_single() else {
+        return;
+    };
+    let Some((body, body_transform, gravity)) =
+        dominant_attractor(&body_query, transform.translation, |(_, t, g)| {
+            (t.translation, g.mass)
+        })
+    else {
+        return;
+    };
+
+    let nose = transform.rotation * Vec3::Y;
+    let vel = velocity.linvel - velocities.of(body);
+    let orbit = Orbit::from_pos_dir_3d(
+        gravity.mass,
+        (transform.translation - body_transform.translation).as_dvec3(),
+        vel.as_dvec3(),
+    );
+    let ctx = TutorialContext {
+        thrusting: forces.get::<ThrusterForce>().force != Vec3::ZERO,
+        prograde_alignment: nose.dot(vel.normalize_or_zero()),
+        eccentricity: orbit.eccentricity,
+    };
+
+    if tutorial.advance(&ctx) {
+        info!(
+            "Tutorial step {}/{} done",
+            tutorial.current,
+            tutorial.steps.len()
+        );
+    }
+    let prompt = tutorial.prompt(&bindings);
+    if text.sections[1].value != prompt {
+        text.sections[1].value = prompt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tutorial, TutorialContext};
+    use crate::input::{InputBindings, LayoutProfile};
+
+    #[test]
+    fn steps_advance_in_order() {
+        let mut tutorial = Tutorial::basics();
+        let mut ctx = TutorialContext {
+            thrusting: false,
+            prograde_alignment: 0.0,
+            eccentricity: 0.0,
+        };
+
+        // circular already, but the circularize step isn't reached yet
+        assert!(!tutorial.advance(&ctx));
+        assert_eq!(tutorial.current, 0);
+
+        ctx.thrusting = true;
+        assert!(tutorial.advance(&ctx));
+        ctx.prograde_alignment = 1.0;
+        assert!(tutorial.advance(&ctx));
+        assert!(!tutorial.advance(&ctx));
+        ctx.eccentricity = 0.2;
+        assert!(tutorial.advance(&ctx));
+        assert!(!tutorial.advance(&ctx));
+        ctx.eccentricity = 0.001;
+        assert!(tutorial.advance(&ctx));
+
+        assert_eq!(tutorial.current, tutorial.steps.len());
+        assert!(!tutorial.advance(&ctx));
+        assert_eq!(
+            tutorial.prompt(&InputBindings::default()),
+            "Done! Fly safe."
+        );
+    }
+
+    #[test]
+    fn prompts_name_the_bound_keys() {
+        let tutorial = Tutorial::basics();
+        assert_eq!(
+            tutorial.prompt(&InputBindings::default()),
+            "Hold Space to fire the engine"
+        );
+
+        let mut tutorial = Tutorial::basics();
+        tutorial.current = 1;
+        let dvorak = LayoutProfile::Dvorak.bindings().unwrap();
+        assert!(
+            tutorial.prompt(&dvorak).ends_with("with Comma/O and A/E"),
+            "{}",
+            tutorial.prompt(&dvorak)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Formatting the numbers on the HUD, either in raw game units or in meters.
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+/// How many meters one game unit stands for.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct WorldScale {
+    pub meters_per_unit: f64,
+}
+
+impl Default for WorldScale {
+    fn default() -> Self {
+        Self {
+            meters_per_unit: 1.0,
+        }
+    }
+}
+
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayUnits {
+    #[default]
+    Game,
+    /// Converted with the [`WorldScale`], in meters or kilometers.
+    Metric,
+}
+
+/// From this many meters on, distances are shown in kilometers.
+const KILOMETER_THRESHOLD: f64 = 10_000.0;
+
+impl DisplayUnits {
+    fn format_distance(self, scale: &WorldScale, distance: f64, signed: bool) -> String {
+        let (value, unit) = match self {
+            DisplayUnits::Game => (distance, ""),
+            DisplayUnits::Metric => {
+                let meters = distance * scale.meters_per_unit;
+                if meters.abs() >= KILOMETER_THRESHOLD {
+                    (meters / 1000.0, " km")
+                } else {
+                    (meters, " m")
+                }
+            }
+        };
+
+        if signed {
+            format!("{value:+.2}{unit}")
+        } else {
+            format!("{value:.2}{unit}")
+        }
+    }
+
+    fn format_speed(self, scale: &WorldScale, speed: f64, signed: bool) -> String {
+        let (value, unit) = match self {
+            DisplayUnits::Game => (speed, ""),
+            DisplayUnits::Metric => (speed * scale.meters_per_unit, " m/s"),
+        };
+
+        if signed {
+            format!("{value:+.2}{unit}")
+        } else {
+            format!("{value:.2}{unit}")
+        }
+    }
+}
+
+/// Everything needed to format HUD values in the selected units.
+#[derive(SystemParam)]
+pub struct HudUnits<'w> {
+    units: Res<'w, DisplayUnits>,
+    scale: Res<'w, WorldScale>,
+}
+
+impl HudUnits<'_> {
+    pub fn distance(&self, distance: f64) -> String {
+        self.units.format_distance(&self.scale, distance, false)
+    }
+
+    /// A change in distance, always with a sign.
+    pub fn distance_change(&self, distance: f64) -> String {
+        self.units.format_distance(&self.scale, distance, true)
+    }
+
+    pub fn speed(&self, speed: f64) -> String {
+        self.units.format_speed(&self.scale, speed, false)
+    }
+
+    /// A change in speed, always with a sign.
+    pub fn speed_change(&self, speed: f64) -> String {
+        self.units.format_speed(&self.scale, speed, true)
+    }
+}
+
+/// F11 switches between game units and metric.
+pub fn toggle_display_units(keyboard_input: Res<Input<KeyCode>>, mut units: ResMut<DisplayUnits>) {
+    if keyboard_input.just_pressed(KeyCode::F11) {
+        *units = match *units {
+            DisplayUnits::Game => DisplayUnits::Metric,
+            DisplayUnits::Metric => DisplayUnits::Game,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DisplayUnits, WorldScale};
+
+    #[test]
+    fn game_units_are_unchanged() {
+        let scale = WorldScale {
+            meters_per_unit: 1000.0,
+        };
+        assert_eq!(
+            DisplayUnits::Game.format_distance(&scale, 1234.567, false),
+            "1234.57"
+        );
+        assert_eq!(DisplayUnits::Game.format_speed(&scale, -0.5, true), "-0.50");
+    }
+
+    #[test]
+    fn metric_uses_world_scale() {
+        let scale = WorldScale {
+            meters_per_unit: 2.0,
+        };
+        assert_eq!(
+            DisplayUnits::Metric.format_distance(&scale, 100.0, false),
+            "200.00 m"
+        );
+        assert_eq!(
+            DisplayUnits::Metric.format_distance(&scale, 21000.0, true),
+            "+42.00 km"
+        );
+        assert_eq!(
+            DisplayUnits::Metric.format_speed(&scale, 3.0, false),
+            "6.00 m/s"
+        );
+    }
+}
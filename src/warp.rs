@@ -0,0 +1,169 @@
+//! Speeding up time, and slowing back down automatically before a scheduled burn
+//! so that it isn't overshot.
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    headless::TICK,
+    hud::ShipStatusText,
+    impulse::{ImpulseBurn, ScheduledBurns},
+    input::InputBindings,
+};
+
+/// The warp factors that can be selected. Gravity is only updated once per frame,
+/// so going much faster makes orbits drift.
+pub const WARP_LEVELS: [f32; 5] = [1.0, 2.0, 5.0, 10.0, 20.0];
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct TimeWarp {
+    /// The index into [`WARP_LEVELS`] selected by the player.
+    pub level: usize,
+    /// The factor that's actually applied, which can be lower near a burn.
+    pub current: f32,
+}
+
+impl Default for TimeWarp {
+    fn default() -> Self {
+        Self {
+            level: 0,
+            current: 1.0,
+        }
+    }
+}
+
+impl TimeWarp {
+    pub fn requested(&self) -> f32 {
+        WARP_LEVELS[self.level]
+    }
+}
+
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct AutoWarp {
+    /// How long before a burn the warp is back at 1x, in simulation seconds.
+    pub lead_time: f32,
+    /// How many real seconds the approach to the lead time should at least take.
+    /// This makes the warp ramp down smoothly instead of dropping to 1x at once.
+    pub ramp_time: f32,
+    /// Whether to go back to the requested warp after the burn.
+    pub resume: bool,
+}
+
+impl Default for AutoWarp {
+    fn default() -> Self {
+        Self {
+            lead_time: 2.0,
+            ramp_time: 1.0,
+            resume: true,
+        }
+    }
+}
+
+/// The warp factor to use when the next burn is `time_to_burn` simulation seconds away.
+/// It is capped so that reaching the lead time takes at least the ramp time.
+pub fn auto_warp_factor(config: &AutoWarp, requested: f32, time_to_burn: Option<f32>) -> f32 {
+    let Some(time_to_burn) = time_to_burn else {
+        return requested;
+    };
+
+    let until_lead = time_to_burn - config.lead_time;
+    let cap = (until_lead / config.ramp_time).max(1.0);
+    requested.min(cap)
+}
+
+pub fn change_time_warp(
+    keyboard_input: Res<Input<KeyCode>>,
+    bindings: Res<InputBindings>,
+    mut warp: ResMut<TimeWarp>,
+) {
+    if keyboard_input.just_pressed(bindings.warp_up) {
+        warp.level = (warp.level + 1).min(WARP_LEVELS.len() - 1);
+    }
+    if keyboard_input.just_pressed(bindings.warp_down) {
+        warp.level = warp.level.saturating_sub(1);
+    }
+}
+
+/// Picks the warp from the requested one and the next burn, and applies it to time and physics.
+pub fn apply_time_warp(
+    config: Res<AutoWarp>,
+    schedule: Res<ScheduledBurns>,
+    mut burns: EventReader<ImpulseBurn>,
+    mut warp: ResMut<TimeWarp>,
+    mut time: ResMut<Time>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    if !burns.is_empty() && !config.resume {
+        warp.level = 0;
+    }
+    burns.clear();
+
+    let now = time.elapsed_seconds_f64();
+    let time_to_burn = schedule.next().map(|burn| (burn.at - now) as f32);
+    let factor = auto_warp_factor(&config, warp.requested(), time_to_burn);
+    if factor == warp.current {
+        return;
+    }
+
+    warp.current = factor;
+    time.set_relative_speed(factor);
+    // rapier takes the scaled frame time, but needs more substeps to stay stable
+    let substeps = factor.ceil() as usize;
+    rapier_config.timestep_mode = match rapier_config.timestep_mode {
+        // recordings and replays stay on fixed ticks, just longer ones
+        TimestepMode::Fixed { .. } => TimestepMode::Fixed {
+            dt: factor * TICK,
+            substeps,
+        },
+        _ => TimestepMode::Variable {
+            max_dt: factor / 60.0,
+            time_scale: 1.0,
+            substeps,
+        },
+    };
+}
+
+pub fn update_warp_text(
+    time: Res<Time>,
+    warp: Res<TimeWarp>,
+    schedule: Res<ScheduledBurns>,
+    mut text_query: Query<&mut Text, With<ShipStatusText>>,
+) {
+    let mut text = text_query.single_mut();
+
+    let mut value = format!("{}x", warp.current);
+    if let Some(burn) = schedule.next() {
+        let time_to_burn = burn.at - time.elapsed_seconds_f64();
+        value += &format!(" (burn in {time_to_burn:.1}s)");
+    }
+    text.sections[3].value = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{auto_warp_factor, AutoWarp};
+
+    #[test]
+    fn no_warp_in_burn_window() {
+        let config = AutoWarp::default();
+
+        for time_to_burn in [config.lead_time, 1.0, 0.5, 0.0, -0.1] {
+            assert_eq!(auto_warp_factor(&config, 20.0, Some(time_to_burn)), 1.0);
+        }
+    }
+
+    #[test]
+    fn ramps_down_before_burn() {
+        let config = AutoWarp::default();
+
+        assert_eq!(auto_warp_factor(&config, 20.0, None), 20.0);
+        assert_eq!(auto_warp_factor(&config, 20.0, Some(1000.0)), 20.0);
+
+        let mut last = 20.0;
+        for time_to_burn in [15.0, 10.0, 5.0, 3.0] {
+            let factor = auto_warp_factor(&config, 20.0, Some(time_to_burn));
+            assert!(factor < last && factor >= 1.0, "{factor} at {time_to_burn}");
+            last = factor;
+        }
+    }
+}